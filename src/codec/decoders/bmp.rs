@@ -0,0 +1,387 @@
+use log::debug;
+
+use std::io::Read;
+
+use super::{image_samples, Decoder};
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+///
+/// `Decoder` for Windows bitmaps: 1, 4, 8, 16, 24 and 32 bits per
+/// pixel, uncompressed, RLE4, RLE8 or with BITFIELDS masks.
+///
+/// Images with an alpha mask decode to `ColorSpace::RGBA`,
+/// everything else to `ColorSpace::RGB`.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BmpDecoder;
+
+impl Decoder for BmpDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_bytes(&data)
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Result<u16, Error> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| Error::decode("Unexpected end of BMP data"))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Result<u32, Error> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| Error::decode("Unexpected end of BMP data"))
+}
+
+///
+/// Extracts one channel from a packed pixel and scales it to 8 bits.
+///
+#[derive(Debug, Clone, Copy)]
+struct Mask {
+    mask: u32,
+    shift: u32,
+    bits: u32,
+}
+
+impl Mask {
+    fn new(mask: u32) -> Self {
+        Mask {
+            mask,
+            shift: if mask == 0 { 0 } else { mask.trailing_zeros() },
+            bits: mask.count_ones(),
+        }
+    }
+
+    fn extract(&self, pixel: u32) -> u8 {
+        if self.bits == 0 {
+            return 0;
+        }
+        let value = (pixel & self.mask) >> self.shift;
+        let max = (1u64 << self.bits) - 1;
+        ((value as u64 * 255 + max / 2) / max) as u8
+    }
+}
+
+///
+/// The fields of the DIB header used for decoding.
+///
+#[derive(Debug)]
+struct Header {
+    width: usize,
+    height: usize,
+    top_down: bool,
+    bits_per_pixel: u16,
+    compression: u32,
+    /// Red, green, blue and alpha masks.
+    masks: Option<[u32; 4]>,
+    palette: Vec<[u8; 3]>,
+}
+
+impl Header {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        let header_size = u32_at(data, 14)? as usize;
+        let base = 14 + header_size;
+
+        let (width, height, bits_per_pixel, compression, colors_used) = if header_size == 12 {
+            // OS/2 BITMAPCOREHEADER
+            (
+                u16_at(data, 18)? as i32,
+                u16_at(data, 20)? as i16 as i32,
+                u16_at(data, 24)?,
+                BI_RGB,
+                0,
+            )
+        } else if header_size >= 40 {
+            (
+                u32_at(data, 18)? as i32,
+                u32_at(data, 22)? as i32,
+                u16_at(data, 28)?,
+                u32_at(data, 30)?,
+                u32_at(data, 46)? as usize,
+            )
+        } else {
+            return Err(Error::decode(format!(
+                "Unsupported BMP header size {header_size}"
+            )));
+        };
+
+        if width <= 0 || height == 0 || height == i32::MIN {
+            return Err(Error::decode("Invalid BMP dimensions"));
+        }
+
+        let mut palette_start = base;
+        let masks = match compression {
+            BI_BITFIELDS | BI_ALPHABITFIELDS => {
+                let alpha = compression == BI_ALPHABITFIELDS || header_size >= 56;
+                if header_size >= 52 {
+                    // Masks are part of V2 and later headers.
+                    Some([
+                        u32_at(data, 54)?,
+                        u32_at(data, 58)?,
+                        u32_at(data, 62)?,
+                        if alpha { u32_at(data, 66)? } else { 0 },
+                    ])
+                } else {
+                    // BITMAPINFOHEADER: masks follow the header.
+                    palette_start += if alpha { 16 } else { 12 };
+                    Some([
+                        u32_at(data, base)?,
+                        u32_at(data, base + 4)?,
+                        u32_at(data, base + 8)?,
+                        if alpha { u32_at(data, base + 12)? } else { 0 },
+                    ])
+                }
+            }
+            BI_RGB | BI_RLE8 | BI_RLE4 => None,
+            other => {
+                return Err(Error::NotImplemented(format!(
+                    "BMP compression method {other}"
+                )));
+            }
+        };
+
+        let mut palette = Vec::new();
+        if bits_per_pixel <= 8 {
+            let entry_size = if header_size == 12 { 3 } else { 4 };
+            let count = match colors_used {
+                0 => 1 << bits_per_pixel,
+                count => count.min(256),
+            };
+            for i in 0..count {
+                let offset = palette_start + i * entry_size;
+                match data.get(offset..offset + 3) {
+                    Some(entry) => palette.push([entry[2], entry[1], entry[0]]),
+                    None => break,
+                }
+            }
+            if palette.is_empty() {
+                return Err(Error::decode("Missing BMP colour table"));
+            }
+        }
+
+        Ok(Header {
+            width: width as usize,
+            height: height.unsigned_abs() as usize,
+            top_down: height < 0,
+            bits_per_pixel,
+            compression,
+            masks,
+            palette,
+        })
+    }
+
+    fn color(&self, index: u8) -> [u8; 3] {
+        // Out of range indices are black, as in Windows.
+        self.palette.get(index as usize).copied().unwrap_or([0; 3])
+    }
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    if !data.starts_with(b"BM") {
+        return Err(Error::decode("Invalid BMP Signature"));
+    }
+
+    let pixel_offset = u32_at(data, 10)? as usize;
+    let header = Header::parse(data)?;
+    debug!("{header:?}");
+
+    let pixels = data
+        .get(pixel_offset..)
+        .ok_or_else(|| Error::decode("Pixel data offset is out of bounds"))?;
+    let (width, height) = (header.width, header.height);
+
+    let has_alpha = header.masks.is_some_and(|masks| masks[3] != 0);
+    let colorspace = if has_alpha {
+        ColorSpace::RGBA
+    } else {
+        ColorSpace::RGB
+    };
+    let channels = colorspace.channels();
+    let too_large = || Error::decode(format!("BMP dimensions {width}x{height} are too large"));
+    let size = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(channels))
+        .ok_or_else(too_large)?;
+
+    let out = match (header.compression, header.bits_per_pixel) {
+        (BI_RLE8, 8) | (BI_RLE4, 4) => {
+            // RLE escapes can skip any number of pixels, so the size of
+            // compressed images cannot be checked against their data.
+            if image_samples(width, height, channels, 1).is_none() {
+                return Err(too_large());
+            }
+            let mut out = vec![0u8; size];
+            decode_rle(pixels, &header, &mut out)?;
+            out
+        }
+        (BI_RLE8 | BI_RLE4, bits) => {
+            return Err(Error::decode(format!("RLE with {bits} bits per pixel")));
+        }
+        (_, bits @ (1 | 2 | 4 | 8 | 16 | 24 | 32)) => {
+            let stride = width
+                .checked_mul(bits as usize)
+                .map(|row_bits| row_bits.div_ceil(32) * 4)
+                .ok_or_else(too_large)?;
+            if stride
+                .checked_mul(height)
+                .is_none_or(|needed| pixels.len() < needed)
+            {
+                return Err(Error::decode("Truncated BMP pixel data"));
+            }
+            let masks = header.masks.map(|masks| masks.map(Mask::new));
+            let masks = masks.unwrap_or_else(|| {
+                // 16-bit defaults to 5-5-5, 32-bit to 8-8-8 with an unused byte.
+                let default = if bits == 16 {
+                    [0x7C00, 0x03E0, 0x001F, 0]
+                } else {
+                    [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0]
+                };
+                default.map(Mask::new)
+            });
+
+            let mut out = vec![0u8; size];
+            for row in 0..height {
+                let y = if header.top_down {
+                    row
+                } else {
+                    height - 1 - row
+                };
+                let line = &pixels[row * stride..(row + 1) * stride];
+                let out_row = &mut out[y * width * channels..(y + 1) * width * channels];
+
+                for (x, pixel) in out_row.chunks_exact_mut(channels).enumerate() {
+                    match bits {
+                        1 | 2 | 4 | 8 => {
+                            let bit = x * bits as usize;
+                            let shift = 8 - bits as usize - bit % 8;
+                            let index = (line[bit / 8] >> shift) & ((1u16 << bits) - 1) as u8;
+                            pixel.copy_from_slice(&header.color(index));
+                        }
+                        24 => {
+                            let p = &line[x * 3..x * 3 + 3];
+                            pixel.copy_from_slice(&[p[2], p[1], p[0]]);
+                        }
+                        _ => {
+                            let value = if bits == 16 {
+                                u16::from_le_bytes([line[x * 2], line[x * 2 + 1]]) as u32
+                            } else {
+                                u32::from_le_bytes(line[x * 4..x * 4 + 4].try_into().unwrap())
+                            };
+                            for (channel, mask) in pixel.iter_mut().zip(&masks) {
+                                *channel = mask.extract(value);
+                            }
+                        }
+                    }
+                }
+            }
+            out
+        }
+        (_, bits) => {
+            return Err(Error::decode(format!("Unsupported BMP bit depth {bits}")));
+        }
+    };
+
+    let shape = Shape::new(width, height, Some(channels));
+    Ok(Image::from_data(out, shape, colorspace))
+}
+
+///
+/// Expand RLE8 or RLE4 data into the RGB buffer `out`.
+/// Pixels skipped by delta or end-of-line escapes are left black.
+///
+fn decode_rle(data: &[u8], header: &Header, out: &mut [u8]) -> Result<(), Error> {
+    let (width, height) = (header.width, header.height);
+    let four_bit = header.compression == BI_RLE4;
+
+    let (mut x, mut row) = (0usize, 0usize);
+    let mut position = 0;
+    let mut put = |x: usize, row: usize, index: u8| {
+        if x < width && row < height {
+            // Rows are stored bottom-up.
+            let offset = ((height - 1 - row) * width + x) * 3;
+            out[offset..offset + 3].copy_from_slice(&header.color(index));
+        }
+    };
+
+    while position + 1 < data.len() {
+        let (count, value) = (data[position] as usize, data[position + 1]);
+        position += 2;
+
+        if count > 0 {
+            // Encoded run
+            for i in 0..count {
+                let index = if four_bit {
+                    if i % 2 == 0 {
+                        value >> 4
+                    } else {
+                        value & 0x0F
+                    }
+                } else {
+                    value
+                };
+                put(x, row, index);
+                x += 1;
+            }
+            continue;
+        }
+
+        match value {
+            0 => {
+                x = 0;
+                row += 1;
+            }
+            1 => break,
+            2 => {
+                let delta = data
+                    .get(position..position + 2)
+                    .ok_or_else(|| Error::decode("Truncated RLE delta"))?;
+                x += delta[0] as usize;
+                row += delta[1] as usize;
+                position += 2;
+            }
+            literal => {
+                // Absolute mode, padded to a 16-bit boundary.
+                let literal = literal as usize;
+                let size = if four_bit {
+                    literal.div_ceil(2)
+                } else {
+                    literal
+                };
+                let bytes = data
+                    .get(position..position + size)
+                    .ok_or_else(|| Error::decode("Truncated RLE literal"))?;
+                for i in 0..literal {
+                    let index = if four_bit {
+                        if i % 2 == 0 {
+                            bytes[i / 2] >> 4
+                        } else {
+                            bytes[i / 2] & 0x0F
+                        }
+                    } else {
+                        bytes[i]
+                    };
+                    put(x, row, index);
+                    x += 1;
+                }
+                position += size + size % 2;
+            }
+        }
+    }
+
+    Ok(())
+}
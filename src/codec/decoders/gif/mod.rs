@@ -0,0 +1,456 @@
+use log::debug;
+
+use std::io::Read;
+use std::time::Duration;
+
+use super::{image_samples, Decoder};
+use crate::codec::lzw::{self, BitOrder};
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+pub(crate) const EXTENSION: u8 = 0x21;
+pub(crate) const IMAGE_DESCRIPTOR: u8 = 0x2C;
+pub(crate) const TRAILER: u8 = 0x3B;
+pub(crate) const GRAPHIC_CONTROL: u8 = 0xF9;
+pub(crate) const APPLICATION: u8 = 0xFF;
+
+///
+/// `Decoder` for GIF87a and GIF89a files, returning the first frame.
+///
+/// Frames decode to `ColorSpace::RGBA` at the size of the logical
+/// screen, with pixels outside the frame and transparent pixels
+/// left fully transparent. Use `frames` to read an animation.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GifDecoder;
+
+impl Decoder for GifDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_bytes(&data)
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    match frames(data)?.next() {
+        Some(frame) => Ok(frame?.image),
+        None => Err(Error::decode("GIF contains no frames")),
+    }
+}
+
+///
+/// Iterate over the frames of a (possibly animated) GIF.
+///
+/// Each frame is composited onto the frames before it,
+/// honouring their disposal methods, so every `Frame::image`
+/// is the full picture to display.
+///
+pub fn frames(data: &[u8]) -> Result<Frames<'_>, Error> {
+    Frames::new(data)
+}
+
+///
+/// What happens to a frame's area before the next frame is drawn.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Disposal {
+    /// Leave the frame in place (methods 0 and 1).
+    Keep,
+    /// Clear the frame's area to transparent (method 2).
+    Background,
+    /// Restore the area to how it was before the frame (method 3).
+    Previous,
+}
+
+///
+/// Settings from a Graphic Control Extension, which apply
+/// to the image that follows it.
+///
+#[derive(Debug, Clone, Copy)]
+struct GraphicControl {
+    disposal: Disposal,
+    /// In hundredths of a second.
+    delay: u16,
+    transparent: Option<u8>,
+}
+
+impl Default for GraphicControl {
+    fn default() -> Self {
+        GraphicControl {
+            disposal: Disposal::Keep,
+            delay: 0,
+            transparent: None,
+        }
+    }
+}
+
+///
+/// A composited frame of an animation.
+///
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// The whole logical screen after drawing this frame.
+    pub image: Image,
+    /// How long to show the frame. Many viewers raise very short
+    /// delays (under 20ms) to 100ms; the stored value is kept here.
+    pub delay: Duration,
+}
+
+///
+/// Iterator over the composited frames of a GIF, see `frames`.
+///
+#[derive(Debug)]
+pub struct Frames<'a> {
+    data: &'a [u8],
+    position: usize,
+    width: usize,
+    height: usize,
+    global_palette: Option<Vec<[u8; 3]>>,
+    loop_count: Option<u16>,
+    /// RGBA canvas holding the result of the previous frames.
+    canvas: Vec<u8>,
+    /// Area to clear or restore before the next frame, and the
+    /// pixels to restore it from.
+    pending: Option<(Disposal, Rect, Vec<u8>)>,
+    done: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+}
+
+impl<'a> Frames<'a> {
+    fn new(data: &'a [u8]) -> Result<Self, Error> {
+        if !(data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a")) {
+            return Err(Error::decode("Invalid GIF Signature"));
+        }
+        let screen = data
+            .get(6..13)
+            .ok_or_else(|| Error::decode("Truncated GIF header"))?;
+        let width = u16::from_le_bytes([screen[0], screen[1]]) as usize;
+        let height = u16::from_le_bytes([screen[2], screen[3]]) as usize;
+        let flags = screen[4];
+        if width == 0 || height == 0 {
+            return Err(Error::decode("Invalid GIF dimensions"));
+        }
+        if image_samples(width, height, 4, 1).is_none() {
+            return Err(Error::decode(format!(
+                "GIF dimensions {width}x{height} are too large"
+            )));
+        }
+
+        let mut position = 13;
+        let global_palette = if flags & 0x80 != 0 {
+            let palette = read_palette(data, position, flags)?;
+            position += palette.len() * 3;
+            Some(palette)
+        } else {
+            None
+        };
+
+        Ok(Frames {
+            data,
+            position,
+            width,
+            height,
+            loop_count: scan_loop_count(data, position),
+            global_palette,
+            canvas: vec![0; width * height * 4],
+            pending: None,
+            done: false,
+        })
+    }
+
+    ///
+    /// Size of the logical screen, which every frame is composited onto.
+    ///
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    ///
+    /// The repeat count of the `NETSCAPE2.0` extension: `None` when
+    /// the animation plays once, `Some(0)` when it loops forever.
+    ///
+    pub fn loop_count(&self) -> Option<u16> {
+        self.loop_count
+    }
+
+    fn byte(&mut self) -> Result<u8, Error> {
+        let byte = *self
+            .data
+            .get(self.position)
+            .ok_or_else(|| Error::decode("Unexpected end of GIF data"))?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, count: usize) -> Result<&'a [u8], Error> {
+        let bytes = self
+            .data
+            .get(self.position..self.position + count)
+            .ok_or_else(|| Error::decode("Unexpected end of GIF data"))?;
+        self.position += count;
+        Ok(bytes)
+    }
+
+    ///
+    /// Read a chain of data sub-blocks, ending with an empty block.
+    ///
+    /// A truncated chain yields whatever data is present, so that
+    /// partially downloaded files still show their complete rows.
+    ///
+    fn sub_blocks(&mut self) -> Vec<u8> {
+        let mut out = Vec::new();
+        while let Some(&size) = self.data.get(self.position) {
+            self.position += 1;
+            if size == 0 {
+                return out;
+            }
+            let end = (self.position + size as usize).min(self.data.len());
+            out.extend_from_slice(&self.data[self.position..end]);
+            self.position = end;
+        }
+        debug!("GIF data ends inside a block");
+        out
+    }
+
+    ///
+    /// Read blocks up to and including the next image, returning
+    /// `None` at the trailer.
+    ///
+    fn next_frame(&mut self) -> Result<Option<Frame>, Error> {
+        let mut control = GraphicControl::default();
+
+        loop {
+            if self.position >= self.data.len() {
+                // Tolerate a missing trailer.
+                debug!("GIF ends without a trailer");
+                return Ok(None);
+            }
+
+            match self.byte()? {
+                TRAILER => return Ok(None),
+                EXTENSION => {
+                    let label = self.byte()?;
+                    let payload = self.sub_blocks();
+                    if label == GRAPHIC_CONTROL && payload.len() >= 4 {
+                        control = GraphicControl {
+                            disposal: match (payload[0] >> 2) & 0x07 {
+                                2 => Disposal::Background,
+                                3 => Disposal::Previous,
+                                _ => Disposal::Keep,
+                            },
+                            delay: u16::from_le_bytes([payload[1], payload[2]]),
+                            transparent: (payload[0] & 0x01 != 0).then_some(payload[3]),
+                        };
+                    }
+                }
+                IMAGE_DESCRIPTOR => return self.read_image(control).map(Some),
+                other => {
+                    return Err(Error::decode(format!("Unknown GIF block {other:#04X}")));
+                }
+            }
+        }
+    }
+
+    fn read_image(&mut self, control: GraphicControl) -> Result<Frame, Error> {
+        let descriptor = self.bytes(9)?;
+        let field = |i: usize| u16::from_le_bytes([descriptor[i], descriptor[i + 1]]) as usize;
+        let rect = Rect {
+            left: field(0),
+            top: field(2),
+            width: field(4),
+            height: field(6),
+        };
+        let flags = descriptor[8];
+        let interlaced = flags & 0x40 != 0;
+
+        let local_palette = if flags & 0x80 != 0 {
+            let palette = read_palette(self.data, self.position, flags)?;
+            self.position += palette.len() * 3;
+            Some(palette)
+        } else {
+            None
+        };
+        let min_code_size = self.byte()?;
+        let compressed = self.sub_blocks();
+
+        // Undo the previous frame if its disposal asks for it.
+        if let Some((disposal, area, saved)) = self.pending.take() {
+            self.dispose(disposal, area, &saved);
+        }
+        if control.disposal == Disposal::Previous {
+            self.pending = Some((Disposal::Previous, rect, self.canvas.clone()));
+        } else {
+            self.pending = Some((control.disposal, rect, Vec::new()));
+        }
+
+        let palette = local_palette
+            .as_ref()
+            .or(self.global_palette.as_ref())
+            .ok_or_else(|| Error::decode("GIF frame has no colour table"))?;
+        if !(1..=8).contains(&min_code_size) {
+            return Err(Error::decode(format!(
+                "Invalid LZW minimum code size {min_code_size}"
+            )));
+        }
+        if image_samples(rect.width, rect.height, 1, 1).is_none() {
+            return Err(Error::decode(format!(
+                "GIF frame {}x{} is too large",
+                rect.width, rect.height
+            )));
+        }
+        let indices = lzw::decompress(
+            &compressed,
+            min_code_size,
+            rect.width * rect.height,
+            BitOrder::Lsb,
+            false,
+        )?;
+        if indices.len() < rect.width * rect.height {
+            debug!("GIF frame data ends early, leaving pixels unchanged");
+        }
+
+        for (i, &index) in indices.iter().enumerate() {
+            let (x, row) = (i % rect.width, i / rect.width);
+            let y = if interlaced {
+                interlaced_row(row, rect.height)
+            } else {
+                row
+            };
+            let (x, y) = (rect.left + x, rect.top + y);
+            if x >= self.width || y >= self.height || Some(index) == control.transparent {
+                continue;
+            }
+            // Out of range indices are drawn black, as browsers do.
+            let [r, g, b] = palette.get(index as usize).copied().unwrap_or([0; 3]);
+            let offset = (y * self.width + x) * 4;
+            self.canvas[offset..offset + 4].copy_from_slice(&[r, g, b, 255]);
+        }
+
+        let shape = Shape::new(self.width, self.height, Some(4));
+        Ok(Frame {
+            image: Image::from_data(self.canvas.clone(), shape, ColorSpace::RGBA),
+            delay: Duration::from_millis(control.delay as u64 * 10),
+        })
+    }
+
+    fn dispose(&mut self, disposal: Disposal, area: Rect, saved: &[u8]) {
+        if disposal == Disposal::Keep {
+            return;
+        }
+        let right = (area.left + area.width).min(self.width);
+        let bottom = (area.top + area.height).min(self.height);
+        for y in area.top..bottom {
+            let start = (y * self.width + area.left) * 4;
+            let end = (y * self.width + right) * 4;
+            if start >= end {
+                continue;
+            }
+            match disposal {
+                Disposal::Previous => self.canvas[start..end].copy_from_slice(&saved[start..end]),
+                _ => self.canvas[start..end].fill(0),
+            }
+        }
+    }
+}
+
+impl Iterator for Frames<'_> {
+    type Item = Result<Frame, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.next_frame().transpose();
+        if !matches!(frame, Some(Ok(_))) {
+            // Stop after the trailer or the first error.
+            self.done = true;
+        }
+        frame
+    }
+}
+
+fn read_palette(data: &[u8], position: usize, flags: u8) -> Result<Vec<[u8; 3]>, Error> {
+    let size = 2usize << (flags & 0x07);
+    let table = data
+        .get(position..position + size * 3)
+        .ok_or_else(|| Error::decode("Truncated GIF colour table"))?;
+    Ok(table.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect())
+}
+
+///
+/// The image row stored at position `row` of an interlaced frame.
+/// Rows are stored in four passes: every 8th row from 0, every
+/// 8th from 4, every 4th from 2 and every 2nd from 1.
+///
+fn interlaced_row(row: usize, height: usize) -> usize {
+    let mut row = row;
+    for (start, step) in [(0, 8), (4, 8), (2, 4), (1, 2)] {
+        let count = (height + step - 1 - start) / step;
+        if row < count {
+            return start + row * step;
+        }
+        row -= count;
+    }
+    row
+}
+
+///
+/// Find the `NETSCAPE2.0` loop count without decoding any image data.
+///
+fn scan_loop_count(data: &[u8], mut position: usize) -> Option<u16> {
+    let skip_sub_blocks = |mut position: usize| -> Option<usize> {
+        loop {
+            let size = *data.get(position)? as usize;
+            position += 1 + size;
+            if size == 0 {
+                return Some(position);
+            }
+        }
+    };
+
+    loop {
+        match *data.get(position)? {
+            EXTENSION => {
+                let label = *data.get(position + 1)?;
+                let block = data.get(position + 2..position + 2 + 12)?;
+                if label == APPLICATION
+                    && (block == b"\x0BNETSCAPE2.0" || block == b"\x0BANIMEXTS1.0")
+                {
+                    let sub_block = data.get(position + 14..position + 18)?;
+                    if sub_block[0] >= 3 && sub_block[1] == 1 {
+                        return Some(u16::from_le_bytes([sub_block[2], sub_block[3]]));
+                    }
+                }
+                position = skip_sub_blocks(position + 2)?;
+            }
+            IMAGE_DESCRIPTOR => {
+                let flags = *data.get(position + 9)?;
+                position += 10;
+                if flags & 0x80 != 0 {
+                    position += 3 * (2 << (flags & 0x07));
+                }
+                // Skip the LZW minimum code size and the image data.
+                position = skip_sub_blocks(position + 1)?;
+            }
+            _ => return None,
+        }
+    }
+}
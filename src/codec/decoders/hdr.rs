@@ -0,0 +1,236 @@
+use log::debug;
+
+use std::io::Read;
+
+use super::{image_samples, Decoder};
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::{FloatImage, Image};
+
+pub(crate) const MAGIC: &[u8; 10] = b"#?RADIANCE";
+/// Written by some other tools in place of `MAGIC`.
+pub(crate) const MAGIC_RGBE: &[u8; 6] = b"#?RGBE";
+pub(crate) const FORMAT: &str = "32-bit_rle_rgbe";
+
+/// Scanlines of this many pixels may be run length encoded.
+pub(crate) const RLE_WIDTHS: std::ops::RangeInclusive<usize> = 8..=0x7FFF;
+
+///
+/// Expand an RGBE pixel, three 8-bit mantissas sharing
+/// one exponent, to linear floating point RGB.
+///
+pub(crate) fn rgbe_to_rgb([r, g, b, e]: [u8; 4]) -> [f32; 3] {
+    if e == 0 {
+        return [0.0; 3];
+    }
+    let scale = 2f32.powi(e as i32 - (128 + 8));
+    [r, g, b].map(|mantissa| mantissa as f32 * scale)
+}
+
+///
+/// `Decoder` for Radiance RGBE (`.hdr`) files.
+///
+/// As an 8-bit `Image` the radiance is clamped to 0.0 - 1.0, so use
+/// `decode_float` to keep the full range. Images decode to
+/// `ColorSpace::RGB`. `EXPOSURE` and other header variables are
+/// not applied.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HdrDecoder;
+
+impl Decoder for HdrDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    Ok(decode_float(reader)?.to_image())
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    Ok(decode_float_bytes(data)?.to_image())
+}
+
+pub fn decode_float<R: Read>(reader: &mut R) -> Result<FloatImage, Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_float_bytes(&data)
+}
+
+///
+/// Decode to linear radiance, where 1.0 is nominal white.
+///
+pub fn decode_float_bytes(data: &[u8]) -> Result<FloatImage, Error> {
+    let (width, height, mut position) = parse_header(data)?;
+    debug!("HDR {width}x{height}");
+
+    // Grown one scanline at a time, so truncated data fails early.
+    let mut samples = Vec::new();
+    let mut row = vec![[0u8; 4]; width];
+    for _ in 0..height {
+        read_scanline(data, &mut position, &mut row)?;
+        samples.extend(row.iter().flat_map(|&pixel| rgbe_to_rgb(pixel)));
+    }
+
+    Ok(FloatImage::from_data(
+        samples,
+        Shape::new(width, height, Some(3)),
+        ColorSpace::RGB,
+    ))
+}
+
+///
+/// Read the header lines and resolution string.
+///
+/// # Returns
+///
+/// * The width, height and offset of the pixel data
+///
+fn parse_header(data: &[u8]) -> Result<(usize, usize, usize), Error> {
+    if !data.starts_with(MAGIC) && !data.starts_with(MAGIC_RGBE) {
+        return Err(Error::decode("Invalid HDR signature"));
+    }
+
+    let mut position = 0;
+    let mut next_line = || {
+        let length = data[position..].iter().position(|&byte| byte == b'\n')?;
+        let line = String::from_utf8_lossy(&data[position..position + length]);
+        position += length + 1;
+        Some(line)
+    };
+    let truncated = || Error::decode("Truncated HDR header");
+
+    next_line().ok_or_else(truncated)?;
+    // Variables such as FORMAT and EXPOSURE, up to a blank line.
+    loop {
+        let line = next_line().ok_or_else(truncated)?;
+        let line = line.trim();
+        if line.is_empty() {
+            break;
+        }
+        if let Some(format) = line.strip_prefix("FORMAT=") {
+            if format != FORMAT {
+                return Err(Error::NotImplemented(format!("HDR format {format}")));
+            }
+        }
+    }
+
+    let resolution = next_line().ok_or_else(truncated)?;
+    let fields: Vec<&str> = resolution.split_whitespace().collect();
+    let [y_axis, height, x_axis, width] = fields[..] else {
+        return Err(Error::decode(format!(
+            "Invalid HDR resolution \"{resolution}\""
+        )));
+    };
+    // Rows from the top, pixels from the left, as almost every file is.
+    if (y_axis, x_axis) != ("-Y", "+X") {
+        return Err(Error::NotImplemented(format!(
+            "HDR orientation \"{resolution}\""
+        )));
+    }
+
+    let parse = |value: &str| {
+        value
+            .parse::<usize>()
+            .ok()
+            .filter(|&value| value > 0)
+            .ok_or_else(|| Error::decode(format!("Invalid HDR resolution \"{resolution}\"")))
+    };
+    let (width, height) = (parse(width)?, parse(height)?);
+    // Repeat codes let a few bytes cover any number of pixels, so the
+    // resolution is capped rather than checked against the data.
+    if image_samples(width, height, 3, size_of::<f32>()).is_none() {
+        return Err(Error::decode(format!(
+            "HDR resolution {width}x{height} is too large"
+        )));
+    }
+    // Every scanline takes at least one pixel or run length header.
+    if (data.len() - position) / 4 < height {
+        return Err(Error::decode("Truncated HDR pixel data"));
+    }
+    Ok((width, height, position))
+}
+
+///
+/// Read one scanline of RGBE pixels: run length encoded one channel
+/// at a time, flat, or flat with the original format's repeat codes.
+///
+fn read_scanline(data: &[u8], position: &mut usize, row: &mut [[u8; 4]]) -> Result<(), Error> {
+    let width = row.len();
+    let byte = |position: &mut usize| {
+        let value = *data
+            .get(*position)
+            .ok_or_else(|| Error::decode("Truncated HDR data"))?;
+        *position += 1;
+        Ok::<u8, Error>(value)
+    };
+
+    let start = data.get(*position..*position + 4);
+    let encoded = matches!(start, Some(&[2, 2, high, _]) if high & 0x80 == 0);
+    if RLE_WIDTHS.contains(&width) && encoded {
+        let start = start.unwrap();
+        if (start[2] as usize) << 8 | start[3] as usize != width {
+            return Err(Error::decode("HDR scanline length mismatch"));
+        }
+        *position += 4;
+
+        for channel in 0..4 {
+            let mut x = 0;
+            while x < width {
+                let count = byte(position)? as usize;
+                let (run, repeat) = if count > 128 {
+                    (count - 128, true)
+                } else {
+                    (count, false)
+                };
+                if run == 0 || x + run > width {
+                    return Err(Error::decode("Invalid HDR run length"));
+                }
+                if repeat {
+                    let value = byte(position)?;
+                    row[x..x + run].iter_mut().for_each(|p| p[channel] = value);
+                } else {
+                    for pixel in &mut row[x..x + run] {
+                        pixel[channel] = byte(position)?;
+                    }
+                }
+                x += run;
+            }
+        }
+        return Ok(());
+    }
+
+    let mut x = 0;
+    // Consecutive repeat codes hold successively higher bytes of a count.
+    let mut shift = 0;
+    while x < width {
+        let pixel = [
+            byte(position)?,
+            byte(position)?,
+            byte(position)?,
+            byte(position)?,
+        ];
+        if pixel[..3] != [1, 1, 1] {
+            row[x] = pixel;
+            x += 1;
+            shift = 0;
+            continue;
+        }
+
+        // Three bytes of count are more than any scanline needs.
+        if x == 0 || shift > 16 {
+            return Err(Error::decode("Invalid HDR repeat"));
+        }
+        let count = (pixel[3] as usize) << shift;
+        if x + count > width {
+            return Err(Error::decode("Invalid HDR repeat"));
+        }
+        let previous = row[x - 1];
+        row[x..x + count].fill(previous);
+        x += count;
+        shift += 8;
+    }
+    Ok(())
+}
@@ -0,0 +1,207 @@
+use crate::error::Error;
+
+/// Codes up to this many bits are resolved with a single table lookup.
+const LOOKUP_BITS: u32 = 9;
+
+///
+/// A Huffman table as defined by a `DHT` segment.
+///
+/// Codes are canonical, so each code length covers a contiguous
+/// range of codes, described here by `min_code`, `max_code` and
+/// `value_offset` per length (Annex C / F.2.2.3 of T.81).
+///
+#[derive(Debug, Clone)]
+pub(crate) struct HuffmanTable {
+    values: Vec<u8>,
+    min_code: [i32; 17],
+    max_code: [i32; 17],
+    value_offset: [i32; 17],
+    /// (code length, value) for every `LOOKUP_BITS` bit prefix.
+    lookup: Vec<(u8, u8)>,
+}
+
+impl HuffmanTable {
+    ///
+    /// # Arguments
+    ///
+    /// * `counts` - Number of codes of each length from 1 to 16 bits
+    /// * `values` - The symbols, ordered by code
+    ///
+    pub fn new(counts: &[u8; 16], values: &[u8]) -> Result<Self, Error> {
+        let total: usize = counts.iter().map(|&count| count as usize).sum();
+        if total != values.len() || total > 256 {
+            return Err(Error::decode("Invalid Huffman table"));
+        }
+
+        let mut table = HuffmanTable {
+            values: values.to_vec(),
+            min_code: [0; 17],
+            max_code: [-1; 17],
+            value_offset: [0; 17],
+            lookup: vec![(0, 0); 1 << LOOKUP_BITS],
+        };
+
+        let mut code = 0i32;
+        let mut index = 0usize;
+        for length in 1..=16 {
+            let count = counts[length - 1] as i32;
+            table.value_offset[length] = index as i32;
+            table.min_code[length] = code;
+
+            for _ in 0..count {
+                if code >= 1 << length {
+                    return Err(Error::decode("Huffman table has too many codes"));
+                }
+                if length as u32 <= LOOKUP_BITS {
+                    let shift = LOOKUP_BITS - length as u32;
+                    let start = (code << shift) as usize;
+                    for entry in &mut table.lookup[start..start + (1 << shift)] {
+                        *entry = (length as u8, values[index]);
+                    }
+                }
+                code += 1;
+                index += 1;
+            }
+
+            if count > 0 {
+                table.max_code[length] = code - 1;
+            }
+            code <<= 1;
+        }
+
+        Ok(table)
+    }
+}
+
+///
+/// Reads bits, most significant first, from entropy coded data.
+///
+/// `0xFF 0x00` sequences are unstuffed to `0xFF`. Reaching a marker
+/// (or the end of the data) yields zero bits, as libjpeg does, so a
+/// truncated scan decodes to flat blocks instead of failing.
+///
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u64,
+    count: u32,
+    at_marker: bool,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            position: 0,
+            buffer: 0,
+            count: 0,
+            at_marker: false,
+        }
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        if self.at_marker || self.position >= self.data.len() {
+            return 0;
+        }
+
+        let byte = self.data[self.position];
+        if byte == 0xFF {
+            if self.data.get(self.position + 1) == Some(&0x00) {
+                self.position += 2;
+            } else {
+                self.at_marker = true;
+                return 0;
+            }
+        } else {
+            self.position += 1;
+        }
+        byte
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 56 {
+            self.buffer |= (self.next_byte() as u64) << (56 - self.count);
+            self.count += 8;
+        }
+    }
+
+    fn peek(&mut self, bits: u32) -> u32 {
+        if self.count < bits {
+            self.fill();
+        }
+        (self.buffer >> (64 - bits)) as u32
+    }
+
+    fn consume(&mut self, bits: u32) {
+        self.buffer <<= bits;
+        self.count -= bits;
+    }
+
+    pub fn read_bits(&mut self, bits: u32) -> u32 {
+        if bits == 0 {
+            return 0;
+        }
+        let value = self.peek(bits);
+        self.consume(bits);
+        value
+    }
+
+    pub fn read_bit(&mut self) -> bool {
+        self.read_bits(1) == 1
+    }
+
+    ///
+    /// Read `bits` bits and sign extend them as described by
+    /// the EXTEND procedure (F.2.2.1) of T.81.
+    ///
+    pub fn receive_extend(&mut self, bits: u32) -> i32 {
+        if bits == 0 {
+            return 0;
+        }
+        let value = self.read_bits(bits) as i32;
+        if value < 1 << (bits - 1) {
+            value - (1 << bits) + 1
+        } else {
+            value
+        }
+    }
+
+    pub fn decode(&mut self, table: &HuffmanTable) -> Result<u8, Error> {
+        let prefix = self.peek(LOOKUP_BITS) as usize;
+        let (length, value) = table.lookup[prefix];
+        if length > 0 {
+            self.consume(length as u32);
+            return Ok(value);
+        }
+
+        for length in LOOKUP_BITS as usize + 1..=16 {
+            let code = self.peek(length as u32) as i32;
+            if code <= table.max_code[length] {
+                self.consume(length as u32);
+                let index = table.value_offset[length] + code - table.min_code[length];
+                return Ok(table.values[index as usize]);
+            }
+        }
+
+        Err(Error::decode("Invalid Huffman code"))
+    }
+
+    ///
+    /// Handle a restart marker: drop buffered bits and skip
+    /// past the next `RSTn` marker in the stream.
+    ///
+    pub fn restart(&mut self) {
+        self.buffer = 0;
+        self.count = 0;
+        self.at_marker = false;
+
+        while self.position + 1 < self.data.len() {
+            let (byte, next) = (self.data[self.position], self.data[self.position + 1]);
+            self.position += 1;
+            if byte == 0xFF && (0xD0..=0xD7).contains(&next) {
+                self.position += 1;
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::sync::OnceLock;
+
+///
+/// cos((2x + 1)uπ / 16) scaled by C(u) / 2, indexed as [x][u].
+///
+fn cosines() -> &'static [[f32; 8]; 8] {
+    static TABLE: OnceLock<[[f32; 8]; 8]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [[0.0; 8]; 8];
+        for (x, row) in table.iter_mut().enumerate() {
+            for (u, value) in row.iter_mut().enumerate() {
+                let scale = if u == 0 {
+                    std::f32::consts::FRAC_1_SQRT_2
+                } else {
+                    1.0
+                };
+                let angle = (2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0;
+                *value = scale * angle.cos() / 2.0;
+            }
+        }
+        table
+    })
+}
+
+///
+/// Inverse DCT of a dequantized block in natural (row major) order,
+/// writing level shifted and clamped samples into `out`.
+///
+/// # Arguments
+///
+/// * `block` - 64 dequantized coefficients
+/// * `out` - Destination plane
+/// * `stride` - Row length of `out`
+///
+pub(crate) fn idct_block(block: &[i32; 64], out: &mut [u8], stride: usize) {
+    if block[1..].iter().all(|&value| value == 0) {
+        // Only the DC term: every sample has the same value.
+        let value = clamp(block[0] as f32 / 8.0 + 128.0);
+        for y in 0..8 {
+            out[y * stride..y * stride + 8].fill(value);
+        }
+        return;
+    }
+
+    let table = cosines();
+
+    // Rows first: tmp[v][x] = sum_u C[x][u] * F[v][u]
+    let mut tmp = [[0f32; 8]; 8];
+    for v in 0..8 {
+        let coefficients = &block[v * 8..v * 8 + 8];
+        for x in 0..8 {
+            tmp[v][x] = (0..8).map(|u| table[x][u] * coefficients[u] as f32).sum();
+        }
+    }
+
+    // Then columns: f[y][x] = sum_v C[y][v] * tmp[v][x]
+    for y in 0..8 {
+        for x in 0..8 {
+            let value: f32 = (0..8).map(|v| table[y][v] * tmp[v][x]).sum();
+            out[y * stride + x] = clamp(value + 128.0);
+        }
+    }
+}
+
+fn clamp(value: f32) -> u8 {
+    value.round().clamp(0.0, 255.0) as u8
+}
@@ -0,0 +1,808 @@
+mod huffman;
+mod idct;
+
+use log::debug;
+
+use std::io::Read;
+
+use super::{image_samples, Decoder};
+use crate::codec::exif::Exif;
+use crate::codec::yuv::upsample;
+use crate::color::{ycbcr_to_rgb, ColorSpace};
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+use huffman::{BitReader, HuffmanTable};
+
+///
+/// Natural (row major) position of the n-th coefficient in zigzag order.
+///
+pub(crate) const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+///
+/// `Decoder` for baseline and progressive (Huffman coded) JPEG data.
+///
+/// Single component files decode to `ColorSpace::GRAY`, everything
+/// else to `ColorSpace::RGB`.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JpegDecoder;
+
+impl Decoder for JpegDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    let (image, _) = decode_with_metadata(reader)?;
+    Ok(image)
+}
+
+///
+/// Metadata read from a JPEG's application segments.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// EXIF fields from the `APP1` segment.
+    pub exif: Option<Exif>,
+}
+
+///
+/// Decode a JPEG, also returning the metadata it carries.
+///
+pub fn decode_with_metadata<R: Read>(reader: &mut R) -> Result<(Image, Metadata), Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_segments(&data)
+}
+
+struct Component {
+    id: u8,
+    /// Horizontal and vertical sampling factors.
+    h: usize,
+    v: usize,
+    quant_index: usize,
+    /// Quantization table in natural order, latched at the first scan.
+    quant: Option<[u16; 64]>,
+    /// Blocks per row and column, padded to whole MCUs.
+    blocks_w: usize,
+    blocks_h: usize,
+    /// Quantized coefficients in natural order, one entry per block,
+    /// allocated by the first scan that includes the component.
+    coefficients: Vec<[i16; 64]>,
+    dc_prediction: i32,
+}
+
+struct Frame {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    /// Set for SOF2 frames, whose coefficients arrive over several scans.
+    progressive: bool,
+    h_max: usize,
+    v_max: usize,
+    mcus_x: usize,
+    mcus_y: usize,
+}
+
+impl Frame {
+    fn parse(segment: &[u8], progressive: bool) -> Result<Self, Error> {
+        if segment.len() < 6 {
+            return Err(Error::decode("Truncated SOF segment"));
+        }
+
+        let precision = segment[0];
+        if precision != 8 {
+            return Err(Error::NotImplemented(format!(
+                "{precision}-bit JPEG samples"
+            )));
+        }
+
+        let height = be16(segment, 1)?;
+        let width = be16(segment, 3)?;
+        let count = segment[5] as usize;
+        if width == 0 || height == 0 {
+            return Err(Error::decode("Image dimensions must be non-zero"));
+        }
+        if count == 0 || segment.len() < 6 + count * 3 {
+            return Err(Error::decode("Truncated SOF segment"));
+        }
+
+        let mut components = Vec::with_capacity(count);
+        for i in 0..count {
+            let offset = 6 + i * 3;
+            let (h, v) = (segment[offset + 1] >> 4, segment[offset + 1] & 0x0F);
+            if !(1..=4).contains(&h) || !(1..=4).contains(&v) {
+                return Err(Error::decode("Invalid sampling factor"));
+            }
+            components.push(Component {
+                id: segment[offset],
+                h: h as usize,
+                v: v as usize,
+                quant_index: (segment[offset + 2] & 0x03) as usize,
+                quant: None,
+                blocks_w: 0,
+                blocks_h: 0,
+                coefficients: Vec::new(),
+                dc_prediction: 0,
+            });
+        }
+
+        let h_max = components.iter().map(|c| c.h).max().unwrap_or(1);
+        let v_max = components.iter().map(|c| c.v).max().unwrap_or(1);
+        let mcus_x = width.div_ceil(8 * h_max);
+        let mcus_y = height.div_ceil(8 * v_max);
+        // Each component holds at most one 16-bit coefficient per padded pixel.
+        if image_samples(mcus_x * 8 * h_max, mcus_y * 8 * v_max, count, 2).is_none() {
+            return Err(Error::decode(format!(
+                "JPEG dimensions {width}x{height} are too large"
+            )));
+        }
+
+        for component in &mut components {
+            component.blocks_w = mcus_x * component.h;
+            component.blocks_h = mcus_y * component.v;
+        }
+
+        Ok(Frame {
+            width,
+            height,
+            components,
+            progressive,
+            h_max,
+            v_max,
+            mcus_x,
+            mcus_y,
+        })
+    }
+
+    ///
+    /// Size in samples of a component before upsampling.
+    ///
+    fn component_size(&self, index: usize) -> (usize, usize) {
+        let component = &self.components[index];
+        (
+            (self.width * component.h).div_ceil(self.h_max),
+            (self.height * component.v).div_ceil(self.v_max),
+        )
+    }
+}
+
+struct ScanComponent {
+    index: usize,
+    dc_table: usize,
+    ac_table: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanKind {
+    Sequential,
+    DcFirst,
+    DcRefine,
+    AcFirst,
+    AcRefine,
+}
+
+struct Scan {
+    components: Vec<ScanComponent>,
+    /// Band of zigzag coefficients coded by this scan.
+    spectral_start: usize,
+    spectral_end: usize,
+    /// Successive approximation bit positions.
+    approx_high: u8,
+    approx_low: u8,
+}
+
+impl Scan {
+    fn parse(segment: &[u8], frame: &Frame) -> Result<Self, Error> {
+        let count = *segment.first().unwrap_or(&0) as usize;
+        if count == 0 || count > 4 || segment.len() < 1 + count * 2 + 3 {
+            return Err(Error::decode("Invalid SOS segment"));
+        }
+
+        let mut components = Vec::with_capacity(count);
+        for i in 0..count {
+            let id = segment[1 + i * 2];
+            let tables = segment[2 + i * 2];
+            let index = frame
+                .components
+                .iter()
+                .position(|component| component.id == id)
+                .ok_or_else(|| Error::decode(format!("Scan references unknown component {id}")))?;
+            components.push(ScanComponent {
+                index,
+                dc_table: (tables >> 4) as usize & 0x03,
+                ac_table: (tables & 0x0F) as usize & 0x03,
+            });
+        }
+
+        let parameters = &segment[1 + count * 2..];
+        Ok(Scan {
+            components,
+            spectral_start: parameters[0] as usize,
+            spectral_end: parameters[1] as usize,
+            approx_high: parameters[2] >> 4,
+            approx_low: parameters[2] & 0x0F,
+        })
+    }
+
+    fn kind(&self, progressive: bool) -> Result<ScanKind, Error> {
+        if !progressive {
+            return Ok(ScanKind::Sequential);
+        }
+
+        let (start, end) = (self.spectral_start, self.spectral_end);
+        if start > end || end > 63 || self.approx_low > 13 {
+            return Err(Error::decode("Invalid progressive scan parameters"));
+        }
+        if start == 0 {
+            if end != 0 {
+                return Err(Error::decode("DC scans must not include AC coefficients"));
+            }
+            return Ok(if self.approx_high == 0 {
+                ScanKind::DcFirst
+            } else {
+                ScanKind::DcRefine
+            });
+        }
+
+        if self.components.len() != 1 {
+            return Err(Error::decode("AC scans must have a single component"));
+        }
+        Ok(if self.approx_high == 0 {
+            ScanKind::AcFirst
+        } else {
+            ScanKind::AcRefine
+        })
+    }
+}
+
+#[derive(Default)]
+struct Tables {
+    quant: [Option<[u16; 64]>; 4],
+    dc: [Option<HuffmanTable>; 4],
+    ac: [Option<HuffmanTable>; 4],
+}
+
+impl Tables {
+    fn read_quant(&mut self, segment: &[u8]) -> Result<(), Error> {
+        let mut offset = 0;
+        while offset < segment.len() {
+            let precision = segment[offset] >> 4;
+            let index = (segment[offset] & 0x0F) as usize;
+            let size = if precision == 0 { 64 } else { 128 };
+            if index > 3 || segment.len() < offset + 1 + size {
+                return Err(Error::decode("Invalid DQT segment"));
+            }
+
+            let mut table = [0u16; 64];
+            for (k, &position) in ZIGZAG.iter().enumerate() {
+                table[position] = if precision == 0 {
+                    segment[offset + 1 + k] as u16
+                } else {
+                    be16(segment, offset + 1 + k * 2)? as u16
+                };
+            }
+            self.quant[index] = Some(table);
+            offset += 1 + size;
+        }
+        Ok(())
+    }
+
+    fn read_huffman(&mut self, segment: &[u8]) -> Result<(), Error> {
+        let mut offset = 0;
+        while offset < segment.len() {
+            let class = segment[offset] >> 4;
+            let index = (segment[offset] & 0x0F) as usize;
+            if class > 1 || index > 3 || segment.len() < offset + 17 {
+                return Err(Error::decode("Invalid DHT segment"));
+            }
+
+            let mut counts = [0u8; 16];
+            counts.copy_from_slice(&segment[offset + 1..offset + 17]);
+            let total: usize = counts.iter().map(|&count| count as usize).sum();
+            let values = segment
+                .get(offset + 17..offset + 17 + total)
+                .ok_or_else(|| Error::decode("Truncated DHT segment"))?;
+
+            let table = HuffmanTable::new(&counts, values)?;
+            if class == 0 {
+                self.dc[index] = Some(table);
+            } else {
+                self.ac[index] = Some(table);
+            }
+            offset += 17 + total;
+        }
+        Ok(())
+    }
+}
+
+fn be16(data: &[u8], offset: usize) -> Result<usize, Error> {
+    match data.get(offset..offset + 2) {
+        Some(bytes) => Ok(u16::from_be_bytes([bytes[0], bytes[1]]) as usize),
+        None => Err(Error::decode("Unexpected end of JPEG data")),
+    }
+}
+
+///
+/// Index of the first marker after entropy coded data starting at
+/// `start`, skipping stuffed bytes and restart markers.
+///
+fn scan_end(data: &[u8], start: usize) -> usize {
+    let mut position = start;
+    while position + 1 < data.len() {
+        if data[position] == 0xFF {
+            let next = data[position + 1];
+            if next != 0x00 && !(0xD0..=0xD7).contains(&next) {
+                return position;
+            }
+        }
+        position += 1;
+    }
+    data.len()
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    let (image, _) = decode_segments(data)?;
+    Ok(image)
+}
+
+fn decode_segments(data: &[u8]) -> Result<(Image, Metadata), Error> {
+    if !data.starts_with(&[0xFF, 0xD8]) {
+        return Err(Error::decode("Invalid JPEG Signature"));
+    }
+
+    let mut tables = Tables::default();
+    let mut frame: Option<Frame> = None;
+    let mut restart_interval = 0;
+    let mut adobe_transform = None;
+    let mut metadata = Metadata::default();
+    let mut position = 2;
+
+    while position < data.len() {
+        if data[position] != 0xFF {
+            return Err(Error::decode(format!(
+                "Expected marker at offset {position}"
+            )));
+        }
+        // Any number of 0xFF fill bytes may precede a marker.
+        while position < data.len() && data[position] == 0xFF {
+            position += 1;
+        }
+        let Some(&marker) = data.get(position) else {
+            break;
+        };
+        position += 1;
+
+        match marker {
+            0xD9 => break,
+            0xD8 | 0xD0..=0xD7 | 0x01 => continue,
+            _ => {}
+        }
+
+        let length = be16(data, position)?;
+        let segment = data
+            .get(position + 2..position + length)
+            .ok_or_else(|| Error::decode("Truncated JPEG segment"))?;
+        position += length;
+        debug!("Reading marker {marker:02X} ({length} bytes)");
+
+        match marker {
+            0xC0..=0xC2 => {
+                frame = Some(Frame::parse(segment, marker == 0xC2)?);
+            }
+            0xC3 | 0xC5..=0xC7 | 0xC9..=0xCB | 0xCD..=0xCF => {
+                return Err(Error::NotImplemented(format!(
+                    "JPEG process with SOF marker {marker:02X}"
+                )));
+            }
+            0xC4 => tables.read_huffman(segment)?,
+            0xDB => tables.read_quant(segment)?,
+            0xDD => restart_interval = be16(segment, 0)?,
+            0xDA => {
+                let frame = frame
+                    .as_mut()
+                    .ok_or_else(|| Error::decode("SOS before SOF"))?;
+                let scan = Scan::parse(segment, frame)?;
+
+                let end = scan_end(data, position);
+                decode_scan(
+                    frame,
+                    &scan,
+                    &tables,
+                    restart_interval,
+                    &data[position..end],
+                )?;
+                position = end;
+            }
+            0xE1 if segment.starts_with(b"Exif\0\0") => {
+                // Malformed metadata should not prevent reading the pixels.
+                match Exif::parse(&segment[6..]) {
+                    Ok(exif) => metadata.exif = Some(exif),
+                    Err(error) => debug!("Ignoring malformed EXIF segment: {error}"),
+                }
+            }
+            0xEE if segment.starts_with(b"Adobe") && segment.len() >= 12 => {
+                adobe_transform = Some(segment[11]);
+            }
+            _ => {
+                debug!("Skipping marker {marker:02X}");
+            }
+        }
+    }
+
+    let frame = frame.ok_or_else(|| Error::decode("Missing SOF segment"))?;
+    let image = render(&frame, &tables, adobe_transform)?;
+    Ok((image, metadata))
+}
+
+///
+/// Decode every block of a scan into the frame's coefficient buffers.
+///
+/// Progressive scans only fill in part of each block, so
+/// coefficients accumulate across scans until the frame is rendered.
+///
+fn decode_scan(
+    frame: &mut Frame,
+    scan: &Scan,
+    tables: &Tables,
+    restart_interval: usize,
+    data: &[u8],
+) -> Result<(), Error> {
+    let kind = scan.kind(frame.progressive)?;
+    for scan_component in &scan.components {
+        let component = &mut frame.components[scan_component.index];
+        component.dc_prediction = 0;
+        if component.coefficients.is_empty() {
+            component.coefficients = vec![[0; 64]; component.blocks_w * component.blocks_h];
+        }
+        if component.quant.is_none() {
+            component.quant = tables.quant[component.quant_index];
+        }
+
+        let needs_dc = matches!(kind, ScanKind::Sequential | ScanKind::DcFirst);
+        let needs_ac = !matches!(kind, ScanKind::DcFirst | ScanKind::DcRefine);
+        if (needs_dc && tables.dc[scan_component.dc_table].is_none())
+            || (needs_ac && tables.ac[scan_component.ac_table].is_none())
+        {
+            return Err(Error::decode("Scan uses an undefined Huffman table"));
+        }
+    }
+
+    let mut reader = BitReader::new(data);
+    let mut eob_run = 0;
+    let mut mcu = 0;
+    let restart = |reader: &mut BitReader, eob_run: &mut u32, frame: &mut Frame, mcu: usize| {
+        if restart_interval > 0 && mcu > 0 && mcu % restart_interval == 0 {
+            reader.restart();
+            *eob_run = 0;
+            for scan_component in &scan.components {
+                frame.components[scan_component.index].dc_prediction = 0;
+            }
+        }
+    };
+
+    if let [scan_component] = &scan.components[..] {
+        // Non-interleaved: blocks in raster order, only those covering the image.
+        let (width, height) = frame.component_size(scan_component.index);
+        for block_y in 0..height.div_ceil(8) {
+            for block_x in 0..width.div_ceil(8) {
+                restart(&mut reader, &mut eob_run, frame, mcu);
+                let mut block = BlockDecoder {
+                    reader: &mut reader,
+                    eob_run: &mut eob_run,
+                    scan,
+                    tables,
+                };
+                block.decode(kind, frame, scan_component, block_x, block_y)?;
+                mcu += 1;
+            }
+        }
+    } else {
+        for mcu_y in 0..frame.mcus_y {
+            for mcu_x in 0..frame.mcus_x {
+                restart(&mut reader, &mut eob_run, frame, mcu);
+                for scan_component in &scan.components {
+                    let (h, v) = {
+                        let component = &frame.components[scan_component.index];
+                        (component.h, component.v)
+                    };
+                    for y in 0..v {
+                        for x in 0..h {
+                            let mut block = BlockDecoder {
+                                reader: &mut reader,
+                                eob_run: &mut eob_run,
+                                scan,
+                                tables,
+                            };
+                            block.decode(
+                                kind,
+                                frame,
+                                scan_component,
+                                mcu_x * h + x,
+                                mcu_y * v + y,
+                            )?;
+                        }
+                    }
+                }
+                mcu += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+///
+/// Entropy decoding state shared by the blocks of one scan.
+///
+struct BlockDecoder<'r, 'a> {
+    reader: &'r mut BitReader<'a>,
+    /// Remaining blocks of an end-of-band run (progressive AC scans).
+    eob_run: &'r mut u32,
+    scan: &'r Scan,
+    tables: &'r Tables,
+}
+
+impl BlockDecoder<'_, '_> {
+    fn decode(
+        &mut self,
+        kind: ScanKind,
+        frame: &mut Frame,
+        scan_component: &ScanComponent,
+        block_x: usize,
+        block_y: usize,
+    ) -> Result<(), Error> {
+        let component = &mut frame.components[scan_component.index];
+        let block = &mut component.coefficients[block_y * component.blocks_w + block_x];
+        let dc_table = self.tables.dc[scan_component.dc_table].as_ref();
+        let ac_table = self.tables.ac[scan_component.ac_table].as_ref();
+
+        match (kind, dc_table, ac_table) {
+            (ScanKind::Sequential, Some(dc_table), Some(ac_table)) => {
+                self.dc_first(dc_table, &mut component.dc_prediction, block)?;
+                self.sequential_ac(ac_table, block)
+            }
+            (ScanKind::DcFirst, Some(dc_table), _) => {
+                self.dc_first(dc_table, &mut component.dc_prediction, block)
+            }
+            (ScanKind::DcRefine, _, _) => {
+                if self.reader.read_bit() {
+                    block[0] |= 1 << self.scan.approx_low;
+                }
+                Ok(())
+            }
+            (ScanKind::AcFirst, _, Some(ac_table)) => self.ac_first(ac_table, block),
+            (ScanKind::AcRefine, _, Some(ac_table)) => self.ac_refine(ac_table, block),
+            _ => Err(Error::decode("Scan uses an undefined Huffman table")),
+        }
+    }
+
+    ///
+    /// DC difference, shifted by the successive approximation
+    /// bit position (zero for sequential scans).
+    ///
+    fn dc_first(
+        &mut self,
+        table: &HuffmanTable,
+        prediction: &mut i32,
+        block: &mut [i16; 64],
+    ) -> Result<(), Error> {
+        let size = self.reader.decode(table)?;
+        if size > 11 {
+            return Err(Error::decode("Invalid DC coefficient size"));
+        }
+        *prediction += self.reader.receive_extend(size as u32);
+        block[0] = (*prediction << self.scan.approx_low) as i16;
+        Ok(())
+    }
+
+    ///
+    /// AC coefficients of a sequential block (F.2.2 of T.81).
+    ///
+    fn sequential_ac(&mut self, table: &HuffmanTable, block: &mut [i16; 64]) -> Result<(), Error> {
+        let mut k = 1;
+        while k < 64 {
+            let symbol = self.reader.decode(table)?;
+            let (run, size) = ((symbol >> 4) as usize, (symbol & 0x0F) as u32);
+            if size == 0 {
+                if run == 15 {
+                    // ZRL: sixteen zero coefficients
+                    k += 16;
+                    continue;
+                }
+                // EOB
+                break;
+            }
+
+            k += run;
+            if k > 63 {
+                return Err(Error::decode("AC coefficient index out of range"));
+            }
+            block[ZIGZAG[k]] = self.reader.receive_extend(size) as i16;
+            k += 1;
+        }
+        Ok(())
+    }
+
+    ///
+    /// First scan of a spectral band (G.1.2.2 of T.81).
+    ///
+    fn ac_first(&mut self, table: &HuffmanTable, block: &mut [i16; 64]) -> Result<(), Error> {
+        if *self.eob_run > 0 {
+            *self.eob_run -= 1;
+            return Ok(());
+        }
+
+        let mut k = self.scan.spectral_start;
+        while k <= self.scan.spectral_end {
+            let symbol = self.reader.decode(table)?;
+            let (run, size) = ((symbol >> 4) as u32, (symbol & 0x0F) as u32);
+            if size == 0 {
+                if run < 15 {
+                    // EOBn: this block and the next 2^n - 1 + extra bits blocks are done.
+                    *self.eob_run = (1 << run) - 1 + self.reader.read_bits(run);
+                    break;
+                }
+                k += 16;
+                continue;
+            }
+
+            k += run as usize;
+            if k > 63 {
+                return Err(Error::decode("AC coefficient index out of range"));
+            }
+            block[ZIGZAG[k]] = (self.reader.receive_extend(size) << self.scan.approx_low) as i16;
+            k += 1;
+        }
+        Ok(())
+    }
+
+    ///
+    /// Refinement scan of a spectral band (G.1.2.3 of T.81): one more
+    /// bit for every non-zero coefficient and newly non-zero
+    /// coefficients of magnitude one.
+    ///
+    fn ac_refine(&mut self, table: &HuffmanTable, block: &mut [i16; 64]) -> Result<(), Error> {
+        let positive = 1i16 << self.scan.approx_low;
+        let negative = -1i16 << self.scan.approx_low;
+        let end = self.scan.spectral_end;
+        let mut k = self.scan.spectral_start;
+
+        if *self.eob_run == 0 {
+            while k <= end {
+                let symbol = self.reader.decode(table)?;
+                let (mut run, size) = ((symbol >> 4) as i32, symbol & 0x0F);
+                let mut value = 0;
+                if size == 0 {
+                    if run < 15 {
+                        *self.eob_run = (1 << run) + self.reader.read_bits(run as u32);
+                        break;
+                    }
+                    // ZRL: skip sixteen zero coefficients, refining those in between
+                } else {
+                    if size != 1 {
+                        return Err(Error::decode("Invalid AC refinement size"));
+                    }
+                    value = if self.reader.read_bit() {
+                        positive
+                    } else {
+                        negative
+                    };
+                }
+
+                while k <= end {
+                    let coefficient = &mut block[ZIGZAG[k]];
+                    if *coefficient != 0 {
+                        self.refine(coefficient, positive, negative);
+                    } else {
+                        if run == 0 {
+                            if value != 0 {
+                                *coefficient = value;
+                            }
+                            k += 1;
+                            break;
+                        }
+                        run -= 1;
+                    }
+                    k += 1;
+                }
+            }
+        }
+
+        if *self.eob_run > 0 {
+            // Inside an end-of-band run only existing coefficients are refined.
+            while k <= end {
+                let coefficient = &mut block[ZIGZAG[k]];
+                if *coefficient != 0 {
+                    self.refine(coefficient, positive, negative);
+                }
+                k += 1;
+            }
+            *self.eob_run -= 1;
+        }
+        Ok(())
+    }
+
+    fn refine(&mut self, coefficient: &mut i16, positive: i16, negative: i16) {
+        if self.reader.read_bit() && *coefficient & positive == 0 {
+            *coefficient += if *coefficient >= 0 {
+                positive
+            } else {
+                negative
+            };
+        }
+    }
+}
+
+///
+/// Dequantize and inverse transform every block, then upsample
+/// and colour convert the component planes into an `Image`.
+///
+fn render(frame: &Frame, tables: &Tables, adobe_transform: Option<u8>) -> Result<Image, Error> {
+    let (width, height) = (frame.width, frame.height);
+
+    let mut planes = Vec::with_capacity(frame.components.len());
+    for (index, component) in frame.components.iter().enumerate() {
+        let quant = component
+            .quant
+            .or(tables.quant[component.quant_index])
+            .ok_or_else(|| Error::decode("Missing quantization table"))?;
+
+        let stride = component.blocks_w * 8;
+        let mut plane = vec![0u8; stride * component.blocks_h * 8];
+        let mut dequantized = [0i32; 64];
+        for i in 0..component.blocks_w * component.blocks_h {
+            // Components no scan has reached render as flat grey blocks.
+            let block = component.coefficients.get(i).unwrap_or(&[0; 64]);
+            for k in 0..64 {
+                dequantized[k] = block[k] as i32 * quant[k] as i32;
+            }
+            let (block_x, block_y) = (i % component.blocks_w, i / component.blocks_w);
+            let offset = block_y * 8 * stride + block_x * 8;
+            idct::idct_block(&dequantized, &mut plane[offset..], stride);
+        }
+
+        planes.push(upsample(
+            &plane,
+            stride,
+            frame.component_size(index),
+            (component.h, component.v),
+            (frame.h_max, frame.v_max),
+            (width, height),
+        ));
+    }
+
+    let (data, colorspace) = match &planes[..] {
+        [gray] => (gray.clone(), ColorSpace::GRAY),
+        [first, second, third] => {
+            let ids: Vec<u8> = frame.components.iter().map(|c| c.id).collect();
+            let is_rgb = adobe_transform == Some(0) || ids == b"RGB";
+
+            let mut data = Vec::with_capacity(width * height * 3);
+            for i in 0..width * height {
+                if is_rgb {
+                    data.extend_from_slice(&[first[i], second[i], third[i]]);
+                } else {
+                    data.extend_from_slice(&ycbcr_to_rgb(first[i], second[i], third[i]));
+                }
+            }
+            (data, ColorSpace::RGB)
+        }
+        _ => {
+            return Err(Error::NotImplemented(format!(
+                "JPEG with {} components",
+                planes.len()
+            )));
+        }
+    };
+
+    let shape = Shape::new(width, height, Some(colorspace.channels()));
+    Ok(Image::from_data(data, shape, colorspace))
+}
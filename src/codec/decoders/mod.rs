@@ -1,8 +1,56 @@
+pub mod bmp;
+pub mod gif;
+pub mod hdr;
 pub mod jpeg;
+pub mod netpbm;
 pub mod png;
+pub mod qoi;
+pub mod tiff;
+pub mod webp;
+pub mod y4m;
 
+use super::Codex;
+use crate::error::Error;
 use crate::image::Image;
 
+/// Decoders refuse images that would need more memory than this,
+/// so a forged header in a small file cannot exhaust memory.
+pub(crate) const MAX_IMAGE_BYTES: usize = 1 << 30;
+
+///
+/// The number of samples in a `width` x `height` image with
+/// `channels` samples of `sample_bytes` bytes per pixel, or `None`
+/// when it overflows or exceeds `MAX_IMAGE_BYTES`.
+///
+pub(crate) fn image_samples(
+    width: usize,
+    height: usize,
+    channels: usize,
+    sample_bytes: usize,
+) -> Option<usize> {
+    let samples = width.checked_mul(height)?.checked_mul(channels)?;
+    (samples.checked_mul(sample_bytes)? <= MAX_IMAGE_BYTES).then_some(samples)
+}
+
 pub trait Decoder {
     fn decode(&self, data: &[u8]) -> Result<Image, crate::error::Error>;
 }
+
+///
+/// Decode an in-memory image of the given format
+/// using the default options of its `Decoder`.
+///
+pub fn decode(data: &[u8], codec: Codex) -> Result<Image, Error> {
+    match codec {
+        Codex::PNG => png::PngDecoder::default().decode(data),
+        Codex::JPG => jpeg::JpegDecoder.decode(data),
+        Codex::BMP => bmp::BmpDecoder.decode(data),
+        Codex::PBM | Codex::PGM | Codex::PPM | Codex::PAM => netpbm::NetpbmDecoder.decode(data),
+        Codex::QOI => qoi::QoiDecoder.decode(data),
+        Codex::GIF => gif::GifDecoder.decode(data),
+        Codex::TIFF => tiff::TiffDecoder.decode(data),
+        Codex::WEBP => webp::WebpDecoder.decode(data),
+        Codex::HDR => hdr::HdrDecoder.decode(data),
+        Codex::Y4M => y4m::Y4mDecoder.decode(data),
+    }
+}
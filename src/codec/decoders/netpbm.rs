@@ -0,0 +1,289 @@
+use log::debug;
+
+use std::io::Read;
+
+use super::Decoder;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+///
+/// `Decoder` for the Netpbm family: PBM (`P1`, `P4`), PGM (`P2`, `P5`),
+/// PPM (`P3`, `P6`) and PAM (`P7`), in both the ASCII and binary forms.
+///
+/// Samples with a `maxval` other than 255, including 16-bit ones,
+/// are scaled to 8 bits. Bitmaps decode to `ColorSpace::GRAY` with
+/// black as 0. Only the first image of a multi-image file is read.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NetpbmDecoder;
+
+impl Decoder for NetpbmDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_bytes(&data)
+}
+
+///
+/// Splits the header (and ASCII rasters) into whitespace separated
+/// tokens, skipping `#` comments.
+///
+struct Tokens<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(&byte) = self.data.get(self.position) {
+            if byte == b'#' {
+                while self.data.get(self.position).is_some_and(|&b| b != b'\n') {
+                    self.position += 1;
+                }
+            } else if byte.is_ascii_whitespace() {
+                self.position += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn token(&mut self) -> Result<&'a [u8], Error> {
+        self.skip_whitespace();
+        let start = self.position;
+        while self
+            .data
+            .get(self.position)
+            .is_some_and(|b| !b.is_ascii_whitespace())
+        {
+            self.position += 1;
+        }
+        if start == self.position {
+            return Err(Error::decode("Unexpected end of Netpbm data"));
+        }
+        Ok(&self.data[start..self.position])
+    }
+
+    fn number(&mut self) -> Result<u32, Error> {
+        let token = self.token()?;
+        std::str::from_utf8(token)
+            .ok()
+            .and_then(|text| text.parse().ok())
+            .ok_or_else(|| {
+                Error::decode(format!(
+                    "Expected a number, found {:?}",
+                    String::from_utf8_lossy(token)
+                ))
+            })
+    }
+
+    ///
+    /// The rest of the current line, for PAM headers.
+    ///
+    fn line(&mut self) -> &'a [u8] {
+        let start = self.position;
+        while self.data.get(self.position).is_some_and(|&b| b != b'\n') {
+            self.position += 1;
+        }
+        let line = &self.data[start..self.position];
+        self.position += 1;
+        line
+    }
+}
+
+#[derive(Debug)]
+struct Header {
+    width: usize,
+    height: usize,
+    /// Samples per pixel.
+    depth: usize,
+    maxval: u32,
+    ascii: bool,
+    /// PBM rasters store one bit per pixel with 1 as black.
+    bitmap: bool,
+}
+
+impl Header {
+    fn parse(tokens: &mut Tokens) -> Result<Self, Error> {
+        let magic = tokens.token()?;
+        let (ascii, bitmap, depth) = match magic {
+            b"P1" => (true, true, 1),
+            b"P2" => (true, false, 1),
+            b"P3" => (true, false, 3),
+            b"P4" => (false, true, 1),
+            b"P5" => (false, false, 1),
+            b"P6" => (false, false, 3),
+            b"P7" => return Header::parse_pam(tokens),
+            _ => return Err(Error::decode("Invalid Netpbm magic number")),
+        };
+
+        let width = tokens.number()? as usize;
+        let height = tokens.number()? as usize;
+        let maxval = if bitmap { 1 } else { tokens.number()? };
+        if !ascii {
+            // Exactly one whitespace byte separates the header from the raster.
+            tokens.position += 1;
+        }
+
+        Header {
+            width,
+            height,
+            depth,
+            maxval,
+            ascii,
+            bitmap,
+        }
+        .validate()
+    }
+
+    fn parse_pam(tokens: &mut Tokens) -> Result<Self, Error> {
+        let (mut width, mut height, mut depth, mut maxval) = (None, None, None, None);
+        tokens.line();
+
+        loop {
+            if tokens.position >= tokens.data.len() {
+                return Err(Error::decode("PAM header is missing ENDHDR"));
+            }
+            let line = tokens.line();
+            let text = String::from_utf8_lossy(line);
+            let mut fields = text.split_ascii_whitespace();
+            let (Some(keyword), value) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            let number = || -> Result<u32, Error> {
+                value
+                    .and_then(|value| value.parse().ok())
+                    .ok_or_else(|| Error::decode(format!("Invalid PAM {keyword} value")))
+            };
+
+            match keyword {
+                "ENDHDR" => break,
+                "WIDTH" => width = Some(number()?),
+                "HEIGHT" => height = Some(number()?),
+                "DEPTH" => depth = Some(number()?),
+                "MAXVAL" => maxval = Some(number()?),
+                // The tuple type is implied by the depth.
+                "TUPLTYPE" => debug!("PAM tuple type {value:?}"),
+                keyword if keyword.starts_with('#') => {}
+                keyword => debug!("Ignoring PAM header line {keyword}"),
+            }
+        }
+
+        let missing = |name: &str| Error::decode(format!("PAM header is missing {name}"));
+        Header {
+            width: width.ok_or_else(|| missing("WIDTH"))? as usize,
+            height: height.ok_or_else(|| missing("HEIGHT"))? as usize,
+            depth: depth.ok_or_else(|| missing("DEPTH"))? as usize,
+            maxval: maxval.ok_or_else(|| missing("MAXVAL"))?,
+            ascii: false,
+            bitmap: false,
+        }
+        .validate()
+    }
+
+    fn validate(self) -> Result<Self, Error> {
+        if self.width == 0 || self.height == 0 {
+            return Err(Error::decode("Invalid Netpbm dimensions"));
+        }
+        if self.maxval == 0 || self.maxval > u16::MAX as u32 {
+            return Err(Error::decode(format!("Invalid maxval {}", self.maxval)));
+        }
+        Ok(self)
+    }
+
+    fn colorspace(&self) -> Result<ColorSpace, Error> {
+        match self.depth {
+            1 => Ok(ColorSpace::GRAY),
+            2 => Ok(ColorSpace::GRAYA),
+            3 => Ok(ColorSpace::RGB),
+            4 => Ok(ColorSpace::RGBA),
+            depth => Err(Error::NotImplemented(format!("PAM with depth {depth}"))),
+        }
+    }
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    let mut tokens = Tokens { data, position: 0 };
+    let header = Header::parse(&mut tokens)?;
+    debug!("{header:?}");
+
+    let colorspace = header.colorspace()?;
+    let (width, height) = (header.width, header.height);
+    let count = width
+        .checked_mul(height)
+        .and_then(|pixels| pixels.checked_mul(header.depth))
+        .ok_or_else(|| Error::decode("Netpbm dimensions are too large"))?;
+    let raster = data.get(tokens.position..).unwrap_or_default();
+
+    let max = header.maxval;
+    let scale = |value: u32| -> Result<u8, Error> {
+        if value > max {
+            return Err(Error::decode(format!(
+                "Sample {value} exceeds maxval {max}"
+            )));
+        }
+        Ok(((value * 255 + max / 2) / max) as u8)
+    };
+
+    // Every sample takes at least one bit of the raster.
+    let mut out = Vec::with_capacity(count.min(raster.len() * 8));
+    if header.bitmap && header.ascii {
+        // Plain PBM digits need not be separated by whitespace.
+        for &byte in raster {
+            match byte {
+                b'0' => out.push(255),
+                b'1' => out.push(0),
+                byte if byte.is_ascii_whitespace() => {}
+                _ => return Err(Error::decode("Invalid character in plain PBM raster")),
+            }
+            if out.len() == count {
+                break;
+            }
+        }
+    } else if header.bitmap {
+        let stride = width.div_ceil(8);
+        if raster.len() < stride * height {
+            return Err(Error::decode("Truncated PBM raster"));
+        }
+        for row in raster.chunks_exact(stride).take(height) {
+            out.extend((0..width).map(|x| {
+                let bit = (row[x / 8] >> (7 - x % 8)) & 1;
+                if bit == 1 {
+                    0
+                } else {
+                    255
+                }
+            }));
+        }
+    } else if header.ascii {
+        for _ in 0..count {
+            out.push(scale(tokens.number()?)?);
+        }
+    } else {
+        let bytes_per_sample = if header.maxval > 255 { 2 } else { 1 };
+        let raster = raster
+            .get(..count * bytes_per_sample)
+            .ok_or_else(|| Error::decode("Truncated Netpbm raster"))?;
+        for sample in raster.chunks_exact(bytes_per_sample) {
+            let value = match sample {
+                [value] => *value as u32,
+                _ => u16::from_be_bytes([sample[0], sample[1]]) as u32,
+            };
+            out.push(scale(value)?);
+        }
+    }
+
+    if out.len() != count {
+        return Err(Error::decode("Truncated Netpbm raster"));
+    }
+
+    let shape = Shape::new(width, height, Some(header.depth));
+    Ok(Image::from_data(out, shape, colorspace))
+}
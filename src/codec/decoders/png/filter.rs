@@ -0,0 +1,127 @@
+use crate::error::Error;
+
+///
+/// Per-scanline filter types defined by PNG filter method 0.
+///
+/// Every row of the decompressed image data is prefixed with one
+/// of these bytes, describing how the row was predicted from its
+/// left (`a`), upper (`b`) and upper-left (`c`) neighbours.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    None = 0,
+    Sub = 1,
+    Up = 2,
+    Average = 3,
+    Paeth = 4,
+}
+
+impl TryFrom<u8> for FilterType {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(FilterType::None),
+            1 => Ok(FilterType::Sub),
+            2 => Ok(FilterType::Up),
+            3 => Ok(FilterType::Average),
+            4 => Ok(FilterType::Paeth),
+            other => Err(Error::decode(format!("Invalid filter type {other}"))),
+        }
+    }
+}
+
+///
+/// The Paeth predictor as described in the PNG specification.
+/// Picks whichever of `a`, `b` or `c` is closest to `a + b - c`.
+///
+pub(crate) fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+///
+/// Reverse the filter applied to a single scanline, in-place.
+///
+/// # Arguments
+///
+/// * `filter` - The filter type read from the start of the row
+/// * `bpp` - Bytes per complete pixel (rounded up to 1 for sub-byte depths)
+/// * `previous` - The already reconstructed previous row (all zeros for the first row)
+/// * `current` - The filtered row, without the leading filter byte
+///
+pub(crate) fn unfilter(filter: FilterType, bpp: usize, previous: &[u8], current: &mut [u8]) {
+    debug_assert_eq!(previous.len(), current.len());
+
+    match filter {
+        FilterType::None => {}
+        FilterType::Sub => {
+            for i in bpp..current.len() {
+                current[i] = current[i].wrapping_add(current[i - bpp]);
+            }
+        }
+        FilterType::Up => {
+            for (value, &above) in current.iter_mut().zip(previous) {
+                *value = value.wrapping_add(above);
+            }
+        }
+        FilterType::Average => {
+            for i in 0..current.len() {
+                let left = if i >= bpp { current[i - bpp] as u16 } else { 0 };
+                let above = previous[i] as u16;
+                current[i] = current[i].wrapping_add(((left + above) / 2) as u8);
+            }
+        }
+        FilterType::Paeth => {
+            for i in 0..current.len() {
+                let (left, upper_left) = if i >= bpp {
+                    (current[i - bpp], previous[i - bpp])
+                } else {
+                    (0, 0)
+                };
+                current[i] =
+                    current[i].wrapping_add(paeth_predictor(left, previous[i], upper_left));
+            }
+        }
+    }
+}
+
+///
+/// Apply `filter` to a scanline, appending the filter type byte
+/// followed by the filtered bytes to `out`. The inverse of `unfilter`.
+///
+pub(crate) fn filter(
+    filter: FilterType,
+    bpp: usize,
+    previous: &[u8],
+    current: &[u8],
+    out: &mut Vec<u8>,
+) {
+    debug_assert_eq!(previous.len(), current.len());
+
+    out.push(filter as u8);
+    for i in 0..current.len() {
+        let left = if i >= bpp { current[i - bpp] } else { 0 };
+        let upper_left = if i >= bpp { previous[i - bpp] } else { 0 };
+        let above = previous[i];
+
+        let predicted = match filter {
+            FilterType::None => 0,
+            FilterType::Sub => left,
+            FilterType::Up => above,
+            FilterType::Average => ((left as u16 + above as u16) / 2) as u8,
+            FilterType::Paeth => paeth_predictor(left, above, upper_left),
+        };
+        out.push(current[i].wrapping_sub(predicted));
+    }
+}
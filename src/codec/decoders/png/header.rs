@@ -0,0 +1,115 @@
+use crate::color::ColorSpace;
+use crate::error::Error;
+
+///
+/// Contents of the `IHDR` chunk.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    pub color_type: u8,
+    pub compression_method: u8,
+    pub filter_method: u8,
+    pub interlace_method: u8,
+}
+
+impl Header {
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        if data.len() != 13 {
+            return Err(Error::decode(format!(
+                "IHDR chunk must be 13 bytes, found {}",
+                data.len()
+            )));
+        }
+
+        let header = Header {
+            width: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            height: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            bit_depth: data[8],
+            color_type: data[9],
+            compression_method: data[10],
+            filter_method: data[11],
+            interlace_method: data[12],
+        };
+
+        if header.width == 0 || header.height == 0 {
+            return Err(Error::decode("Image dimensions must be non-zero"));
+        }
+
+        let valid_depths: &[u8] = match header.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            other => return Err(Error::decode(format!("Invalid colour type {other}"))),
+        };
+
+        if !valid_depths.contains(&header.bit_depth) {
+            return Err(Error::decode(format!(
+                "Invalid bit depth {} for colour type {}",
+                header.bit_depth, header.color_type
+            )));
+        }
+
+        if header.compression_method != 0 {
+            return Err(Error::decode(format!(
+                "Unknown compression method {}",
+                header.compression_method
+            )));
+        }
+
+        if header.filter_method != 0 {
+            return Err(Error::decode(format!(
+                "Unknown filter method {}",
+                header.filter_method
+            )));
+        }
+
+        Ok(header)
+    }
+
+    ///
+    /// Number of samples stored per pixel for the colour type.
+    ///
+    pub fn samples_per_pixel(&self) -> usize {
+        match self.color_type {
+            0 => 1, // Greyscale
+            2 => 3, // Truecolour
+            3 => 1, // Indexed
+            4 => 2, // Greyscale with alpha
+            6 => 4, // Truecolour with alpha
+            _ => 0,
+        }
+    }
+
+    ///
+    /// The `ColorSpace` of the decoded `Image`.
+    /// A `tRNS` chunk adds an alpha channel to colour types
+    /// that do not carry one.
+    ///
+    pub fn color_space(&self, has_transparency: bool) -> ColorSpace {
+        match (self.color_type, has_transparency) {
+            (0, false) => ColorSpace::GRAY,
+            (0, true) | (4, _) => ColorSpace::GRAYA,
+            (2 | 3, false) => ColorSpace::RGB,
+            _ => ColorSpace::RGBA,
+        }
+    }
+
+    ///
+    /// Bytes per complete pixel, used as the filter offset.
+    /// Depths below 8 bits still use an offset of 1 byte.
+    ///
+    pub fn bytes_per_pixel(&self) -> usize {
+        ((self.samples_per_pixel() * self.bit_depth as usize) / 8).max(1)
+    }
+
+    ///
+    /// Number of bytes in a scanline of `width` pixels,
+    /// excluding the leading filter byte.
+    ///
+    pub fn stride(&self, width: usize) -> usize {
+        (width * self.samples_per_pixel() * self.bit_depth as usize).div_ceil(8)
+    }
+}
@@ -0,0 +1,80 @@
+///
+/// A single pass of the Adam7 interlacing scheme.
+///
+/// Each pass covers the pixels at
+/// (x_start + i * x_step, y_start + j * y_step)
+/// of the full image.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Pass {
+    pub x_start: usize,
+    pub y_start: usize,
+    pub x_step: usize,
+    pub y_step: usize,
+}
+
+pub(crate) const ADAM7: [Pass; 7] = [
+    Pass::new(0, 0, 8, 8),
+    Pass::new(4, 0, 8, 8),
+    Pass::new(0, 4, 4, 8),
+    Pass::new(2, 0, 4, 4),
+    Pass::new(0, 2, 2, 4),
+    Pass::new(1, 0, 2, 2),
+    Pass::new(0, 1, 1, 2),
+];
+
+impl Pass {
+    const fn new(x_start: usize, y_start: usize, x_step: usize, y_step: usize) -> Self {
+        Pass {
+            x_start,
+            y_start,
+            x_step,
+            y_step,
+        }
+    }
+
+    ///
+    /// Width in pixels of the reduced image for this pass.
+    /// Can be zero for very narrow images, in which case the
+    /// pass is not present in the data stream at all.
+    ///
+    pub fn width(&self, image_width: usize) -> usize {
+        image_width
+            .saturating_sub(self.x_start)
+            .div_ceil(self.x_step)
+    }
+
+    /// Height in pixels of the reduced image for this pass.
+    pub fn height(&self, image_height: usize) -> usize {
+        image_height
+            .saturating_sub(self.y_start)
+            .div_ceil(self.y_step)
+    }
+
+    ///
+    /// Copy the expanded pixels of a reduced image into their
+    /// final positions in the full image buffer.
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - The pass' pixels, row major, `channels` bytes each
+    /// * `image` - The full image buffer
+    /// * `image_width` - The width of the full image
+    /// * `channels` - Bytes per expanded pixel
+    ///
+    pub fn scatter(&self, pixels: &[u8], image: &mut [u8], image_width: usize, channels: usize) {
+        let pass_width = self.width(image_width);
+        if pass_width == 0 {
+            return;
+        }
+
+        for (j, row) in pixels.chunks_exact(pass_width * channels).enumerate() {
+            let y = self.y_start + j * self.y_step;
+            for (i, pixel) in row.chunks_exact(channels).enumerate() {
+                let x = self.x_start + i * self.x_step;
+                let index = (y * image_width + x) * channels;
+                image[index..index + channels].copy_from_slice(pixel);
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+use flate2::read::ZlibDecoder;
+
+use std::io::Read;
+
+use crate::codec::exif::Exif;
+use crate::error::Error;
+
+/// Larger than any real text chunk or ICC profile.
+const MAX_INFLATED_SIZE: usize = 16 << 20;
+
+///
+/// Ancillary information read from a PNG alongside its pixels.
+///
+/// Only the chunks listed here are interpreted, every other
+/// ancillary chunk is skipped by the decoder.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Metadata {
+    /// Entries from `tEXt`, `zTXt` and `iTXt` chunks, in file order.
+    pub text: Vec<TextEntry>,
+    /// Pixel density from `pHYs`.
+    pub physical_dimensions: Option<PhysicalDimensions>,
+    /// Image gamma from `gAMA`, e.g. 0.45455 for sRGB.
+    pub gamma: Option<f32>,
+    /// Primary chromaticities from `cHRM`.
+    pub chromaticities: Option<Chromaticities>,
+    /// Embedded ICC profile from `iCCP`.
+    pub icc_profile: Option<IccProfile>,
+    /// EXIF fields from `eXIf`.
+    pub exif: Option<Exif>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEntry {
+    pub keyword: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Unknown,
+    Meter,
+}
+
+///
+/// Pixels per unit along each axis. With `Unit::Unknown`
+/// only the aspect ratio is meaningful.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhysicalDimensions {
+    pub x: u32,
+    pub y: u32,
+    pub unit: Unit,
+}
+
+///
+/// CIE 1931 (x, y) chromaticities of the white point and primaries.
+///
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Chromaticities {
+    pub white: (f32, f32),
+    pub red: (f32, f32),
+    pub green: (f32, f32),
+    pub blue: (f32, f32),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IccProfile {
+    pub name: String,
+    /// The decompressed profile.
+    pub data: Vec<u8>,
+}
+
+impl Metadata {
+    ///
+    /// Whether this structure knows how to interpret a chunk type.
+    ///
+    pub(crate) fn handles(chunk_type: &[u8; 4]) -> bool {
+        matches!(
+            chunk_type,
+            b"tEXt" | b"zTXt" | b"iTXt" | b"pHYs" | b"gAMA" | b"cHRM" | b"iCCP" | b"eXIf"
+        )
+    }
+
+    ///
+    /// Parse a chunk accepted by `Metadata::handles` into `self`.
+    ///
+    pub(crate) fn read_chunk(&mut self, chunk_type: &[u8; 4], data: &[u8]) -> Result<(), Error> {
+        match chunk_type {
+            b"tEXt" => {
+                let (keyword, text) = split_keyword(data)?;
+                self.text.push(TextEntry {
+                    keyword,
+                    text: latin1(text),
+                });
+            }
+            b"zTXt" => {
+                let (keyword, rest) = split_keyword(data)?;
+                let text = inflate(compressed_payload(rest)?)?;
+                self.text.push(TextEntry {
+                    keyword,
+                    text: latin1(&text),
+                });
+            }
+            b"iTXt" => {
+                let (keyword, rest) = split_keyword(data)?;
+                if rest.len() < 2 {
+                    return Err(Error::decode("Truncated iTXt chunk"));
+                }
+                let compressed = rest[0] == 1;
+                // Skip the language tag and translated keyword.
+                let mut rest = &rest[2..];
+                for _ in 0..2 {
+                    let end = null_position(rest)?;
+                    rest = &rest[end + 1..];
+                }
+                let text = if compressed {
+                    inflate(rest)?
+                } else {
+                    rest.to_vec()
+                };
+                self.text.push(TextEntry {
+                    keyword,
+                    text: String::from_utf8_lossy(&text).into_owned(),
+                });
+            }
+            b"pHYs" => {
+                if data.len() != 9 {
+                    return Err(Error::decode("pHYs chunk must be 9 bytes"));
+                }
+                self.physical_dimensions = Some(PhysicalDimensions {
+                    x: be_u32(&data[0..4]),
+                    y: be_u32(&data[4..8]),
+                    unit: if data[8] == 1 {
+                        Unit::Meter
+                    } else {
+                        Unit::Unknown
+                    },
+                });
+            }
+            b"gAMA" => {
+                if data.len() != 4 {
+                    return Err(Error::decode("gAMA chunk must be 4 bytes"));
+                }
+                self.gamma = Some(fixed_point(data));
+            }
+            b"cHRM" => {
+                if data.len() != 32 {
+                    return Err(Error::decode("cHRM chunk must be 32 bytes"));
+                }
+                let point = |i: usize| (fixed_point(&data[i..]), fixed_point(&data[i + 4..]));
+                self.chromaticities = Some(Chromaticities {
+                    white: point(0),
+                    red: point(8),
+                    green: point(16),
+                    blue: point(24),
+                });
+            }
+            b"iCCP" => {
+                let (name, rest) = split_keyword(data)?;
+                self.icc_profile = Some(IccProfile {
+                    name,
+                    data: inflate(compressed_payload(rest)?)?,
+                });
+            }
+            b"eXIf" => {
+                self.exif = Some(Exif::parse(data)?);
+            }
+            other => {
+                return Err(Error::decode(format!(
+                    "Chunk {} is not a metadata chunk",
+                    String::from_utf8_lossy(other)
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn be_u32(data: &[u8]) -> u32 {
+    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+/// PNG stores gamma and chromaticities multiplied by 100000.
+fn fixed_point(data: &[u8]) -> f32 {
+    be_u32(data) as f32 / 100_000.0
+}
+
+fn latin1(data: &[u8]) -> String {
+    data.iter().map(|&byte| byte as char).collect()
+}
+
+fn null_position(data: &[u8]) -> Result<usize, Error> {
+    data.iter()
+        .position(|&byte| byte == 0)
+        .ok_or_else(|| Error::decode("Missing null separator"))
+}
+
+/// Split the leading null terminated keyword from a chunk.
+fn split_keyword(data: &[u8]) -> Result<(String, &[u8]), Error> {
+    let end = null_position(data)?;
+    Ok((latin1(&data[..end]), &data[end + 1..]))
+}
+
+/// Strip the compression method byte, of which only 0 (zlib) is defined.
+fn compressed_payload(data: &[u8]) -> Result<&[u8], Error> {
+    match data.first() {
+        Some(0) => Ok(&data[1..]),
+        Some(method) => Err(Error::decode(format!(
+            "Unknown compression method {method}"
+        ))),
+        None => Err(Error::decode("Missing compression method")),
+    }
+}
+
+///
+/// Inflate a compressed text or ICC profile, refusing to expand
+/// past `MAX_INFLATED_SIZE` so a tiny chunk cannot exhaust memory.
+///
+fn inflate(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut decompressed = Vec::new();
+    ZlibDecoder::new(data)
+        .take(MAX_INFLATED_SIZE as u64 + 1)
+        .read_to_end(&mut decompressed)
+        .map_err(Error::ImageDecodeError)?;
+    if decompressed.len() > MAX_INFLATED_SIZE {
+        return Err(Error::decode(format!(
+            "Compressed metadata expands past {MAX_INFLATED_SIZE} bytes"
+        )));
+    }
+    Ok(decompressed)
+}
@@ -0,0 +1,393 @@
+mod filter;
+mod header;
+mod interlace;
+mod metadata;
+mod options;
+mod samples;
+
+use flate2::read::ZlibDecoder;
+use log::debug;
+
+use std::io::{self, Read};
+
+use super::Decoder;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+pub use filter::FilterType;
+pub use header::Header;
+pub use metadata::{Chromaticities, IccProfile, Metadata, PhysicalDimensions, TextEntry, Unit};
+pub use options::{DecodeMode, DecodeOptions, DecodeReport};
+pub use samples::Transparency;
+
+pub(crate) use filter::filter as filter_row;
+
+///
+/// `Decoder` for PNG data held in memory.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PngDecoder {
+    pub options: DecodeOptions,
+}
+
+impl PngDecoder {
+    pub fn new(options: DecodeOptions) -> Self {
+        PngDecoder { options }
+    }
+}
+
+impl Decoder for PngDecoder {
+    fn decode(&self, mut data: &[u8]) -> Result<Image, Error> {
+        Ok(decode_with_options(&mut data, &self.options)?.image)
+    }
+}
+
+///
+/// Everything produced by `decode_with_options`.
+///
+#[derive(Debug, Clone)]
+pub struct Decoded {
+    pub image: Image,
+    pub metadata: Metadata,
+    pub report: DecodeReport,
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    let (image, _) = decode_with_metadata(reader)?;
+    Ok(image)
+}
+
+///
+/// Decode a PNG, also returning the ancillary metadata it carries.
+///
+/// Unknown ancillary chunks are skipped as the specification
+/// requires, while unknown critical chunks are an error since
+/// the image cannot be displayed correctly without them.
+///
+pub fn decode_with_metadata<R: Read>(reader: &mut R) -> Result<(Image, Metadata), Error> {
+    let decoded = decode_with_options(reader, &DecodeOptions::default())?;
+    Ok((decoded.image, decoded.metadata))
+}
+
+///
+/// A single chunk as read from the stream.
+/// `complete` is false when the stream ended inside the chunk,
+/// in which case `data` holds whatever could be read.
+///
+struct Chunk {
+    chunk_type: [u8; 4],
+    data: Vec<u8>,
+    crc_valid: bool,
+    complete: bool,
+}
+
+fn read_chunk<R: Read>(reader: &mut R) -> io::Result<Chunk> {
+    let mut length_bytes = [0; 4];
+    reader.read_exact(&mut length_bytes)?;
+
+    let length = u32::from_be_bytes(length_bytes);
+
+    let mut chunk_type = [0; 4];
+    reader.read_exact(&mut chunk_type)?;
+
+    // Read through `take` so a corrupt length cannot trigger a huge allocation.
+    let mut data = Vec::new();
+    reader.by_ref().take(length as u64).read_to_end(&mut data)?;
+
+    let mut crc_bytes = [0; 4];
+    if data.len() < length as usize || reader.read_exact(&mut crc_bytes).is_err() {
+        return Ok(Chunk {
+            chunk_type,
+            data,
+            crc_valid: false,
+            complete: false,
+        });
+    }
+
+    let mut hasher = crc32fast::Hasher::new();
+    hasher.update(&chunk_type);
+    hasher.update(&data);
+    let crc_valid = hasher.finalize() == u32::from_be_bytes(crc_bytes);
+
+    Ok(Chunk {
+        chunk_type,
+        data,
+        crc_valid,
+        complete: true,
+    })
+}
+
+///
+/// Decode a PNG, verifying chunk CRCs.
+///
+/// In `DecodeMode::Strict` any corruption is an error. In
+/// `DecodeMode::Lenient` the decoder recovers every complete row
+/// it can and describes the damage in `Decoded::report`.
+///
+pub fn decode_with_options<R: Read>(
+    reader: &mut R,
+    options: &DecodeOptions,
+) -> Result<Decoded, Error> {
+    let lenient = options.mode == DecodeMode::Lenient;
+
+    let mut signature = [0; 8];
+    reader.read_exact(&mut signature)?;
+
+    if &signature != b"\x89PNG\r\n\x1a\n" {
+        return Err(Error::decode("Invalid PNG Signature"));
+    }
+
+    let mut header = None;
+    let mut palette = Vec::new();
+    let mut transparency = None;
+    let mut image_data = Vec::new();
+    let mut metadata = Metadata::default();
+    let mut report = DecodeReport::default();
+    'outer: loop {
+        let chunk = match read_chunk(reader) {
+            Ok(chunk) => chunk,
+            Err(error) if lenient && error.kind() == io::ErrorKind::UnexpectedEof => {
+                report.truncated = true;
+                break 'outer;
+            }
+            Err(error) => return Err(Error::ImageDecodeError(error)),
+        };
+        let chunk_type = chunk.chunk_type;
+        let name = String::from_utf8_lossy(&chunk_type).into_owned();
+        debug!("Reading chunk {name}");
+
+        if !chunk.complete {
+            if !lenient {
+                return Err(Error::decode(format!("Truncated {name} chunk")));
+            }
+            report.truncated = true;
+            // A partial IDAT still holds usable rows.
+            if &chunk_type == b"IDAT" {
+                image_data.extend(chunk.data);
+            }
+            break 'outer;
+        }
+
+        if !chunk.crc_valid {
+            if !lenient {
+                return Err(Error::decode(format!("CRC mismatch in {name} chunk")));
+            }
+            report.corrupt_chunks.push(name.clone());
+            if is_ancillary(&chunk_type) {
+                continue;
+            }
+        }
+
+        let chunk_data = chunk.data;
+        match &chunk_type {
+            b"IHDR" => {
+                header = Some(Header::parse(&chunk_data)?);
+            }
+            b"PLTE" => {
+                palette = chunk_data;
+            }
+            b"tRNS" => {
+                let header = header
+                    .as_ref()
+                    .ok_or_else(|| Error::decode("tRNS before IHDR"))?;
+                transparency = Transparency::parse(header, &chunk_data)?;
+            }
+            b"IDAT" => {
+                image_data.extend(chunk_data);
+            }
+            b"IEND" => {
+                break 'outer;
+            }
+            value if Metadata::handles(value) => {
+                // Malformed metadata should not prevent reading the pixels.
+                if let Err(error) = metadata.read_chunk(value, &chunk_data) {
+                    debug!("Ignoring malformed {name} chunk: {error}");
+                }
+            }
+            value if is_ancillary(value) => {
+                debug!("Skipping ancillary chunk {name}");
+            }
+            _ => {
+                return Err(Error::decode(format!("Unsupported critical chunk {name}")));
+            }
+        }
+    }
+
+    let header = header.ok_or_else(|| Error::decode("Missing IHDR chunk"))?;
+    debug!("{header:?}");
+
+    if header.color_type == 3 && palette.is_empty() {
+        return Err(Error::decode("Missing PLTE chunk for indexed image"));
+    }
+
+    // On failure `read_to_end` keeps everything inflated up to that point.
+    let mut zlib_decoder = ZlibDecoder::new(&image_data[..]);
+    let mut decompressed = Vec::new();
+    if let Err(error) = zlib_decoder.read_to_end(&mut decompressed) {
+        if !lenient {
+            return Err(Error::ImageDecodeError(error));
+        }
+        debug!("Image data is damaged: {error}");
+    }
+
+    debug!("Decompressed size: {}", decompressed.len());
+
+    let width = header.width as usize;
+    let height = header.height as usize;
+    let colorspace = header.color_space(transparency.is_some());
+    let channels = colorspace.channels();
+    // Lenient decoding zero-fills whatever is missing, up to this size.
+    let size = super::image_samples(width, height, channels, 1)
+        .ok_or_else(|| Error::decode(format!("PNG dimensions {width}x{height} are too large")))?;
+
+    let data = match header.interlace_method {
+        0 => {
+            let pass = decode_pass(
+                &header,
+                &palette,
+                transparency.as_ref(),
+                &mut decompressed,
+                (width, height),
+                lenient,
+            )?;
+            report.missing_rows = height - pass.rows;
+            pass.pixels
+        }
+        1 => {
+            let expected: usize = interlace::ADAM7
+                .iter()
+                .map(|pass| (pass.width(width), pass.height(height)))
+                .filter(|&(pass_width, pass_height)| pass_width > 0 && pass_height > 0)
+                .map(|(pass_width, pass_height)| (header.stride(pass_width) + 1) * pass_height)
+                .sum();
+            if decompressed.len() < expected && !lenient {
+                return Err(Error::decode(format!(
+                    "Expected {expected} bytes of image data, found {}",
+                    decompressed.len()
+                )));
+            }
+
+            let mut data = vec![0; size];
+            let mut offset = 0;
+            for pass in interlace::ADAM7 {
+                let pass_width = pass.width(width);
+                let pass_height = pass.height(height);
+                if pass_width == 0 || pass_height == 0 {
+                    continue;
+                }
+
+                let start = offset.min(decompressed.len());
+                let decoded = decode_pass(
+                    &header,
+                    &palette,
+                    transparency.as_ref(),
+                    &mut decompressed[start..],
+                    (pass_width, pass_height),
+                    lenient,
+                )?;
+                pass.scatter(&decoded.pixels, &mut data, width, channels);
+                report.missing_rows += pass_height - decoded.rows;
+                offset += decoded.consumed;
+            }
+            data
+        }
+        other => {
+            return Err(Error::decode(format!("Unknown interlace method {other}")));
+        }
+    };
+
+    let shape = Shape::new(width, height, Some(channels));
+
+    Ok(Decoded {
+        image: Image::from_data(data, shape, colorspace),
+        metadata,
+        report,
+    })
+}
+
+///
+/// Bit 5 of the first byte (a lowercase letter) marks
+/// chunks that are safe to ignore.
+///
+fn is_ancillary(chunk_type: &[u8; 4]) -> bool {
+    chunk_type[0] & 0x20 != 0
+}
+
+struct Pass {
+    /// Expanded pixels, always sized for the full pass.
+    pixels: Vec<u8>,
+    /// Bytes of the input consumed by this pass.
+    consumed: usize,
+    /// Number of rows successfully reconstructed.
+    rows: usize,
+}
+
+///
+/// Reconstruct the filtered scanlines of a `width` x `height`
+/// (sub-)image at the start of `data` and expand them into pixels.
+///
+/// When `lenient`, decoding stops at the first row that is missing
+/// or damaged and the remaining pixels are left zeroed.
+///
+fn decode_pass(
+    header: &Header,
+    palette: &[u8],
+    transparency: Option<&Transparency>,
+    data: &mut [u8],
+    (width, height): (usize, usize),
+    lenient: bool,
+) -> Result<Pass, Error> {
+    let bpp = header.bytes_per_pixel();
+    let stride = header.stride(width);
+    let consumed = (stride + 1)
+        .checked_mul(height)
+        .ok_or_else(|| Error::decode(format!("PNG dimensions {width}x{height} are too large")))?;
+
+    if data.len() < consumed && !lenient {
+        return Err(Error::decode(format!(
+            "Expected {} bytes of image data, found {}",
+            consumed,
+            data.len()
+        )));
+    }
+
+    let row_size = width * header.color_space(transparency.is_some()).channels();
+    let mut pixels = Vec::with_capacity(row_size * height);
+    let mut previous = vec![0; stride];
+    let mut rows = 0;
+
+    for y in 0..height {
+        let start = y * (stride + 1);
+        if data.len() < start + 1 + stride {
+            break;
+        }
+
+        let result = FilterType::try_from(data[start]).and_then(|filter| {
+            let row = &mut data[start + 1..start + 1 + stride];
+            filter::unfilter(filter, bpp, &previous, row);
+            previous.copy_from_slice(row);
+
+            samples::expand_row(header, palette, transparency, row, width, &mut pixels)
+        });
+
+        match result {
+            Ok(()) => rows += 1,
+            Err(error) if lenient => {
+                debug!("Stopping at damaged row {y}: {error}");
+                break;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+
+    // Drop any partially expanded row and zero everything not recovered.
+    pixels.truncate(rows * row_size);
+    pixels.resize(row_size * height, 0);
+
+    Ok(Pass {
+        pixels,
+        consumed: consumed.min(data.len()),
+        rows,
+    })
+}
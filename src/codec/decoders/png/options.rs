@@ -0,0 +1,54 @@
+///
+/// How the decoder reacts to damaged files.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeMode {
+    /// Reject any chunk with a bad CRC and any truncated image data.
+    #[default]
+    Strict,
+    /// Recover as much of the image as possible. Chunks with a bad
+    /// CRC are still used when critical and dropped when ancillary,
+    /// and rows that cannot be reconstructed are left zeroed.
+    Lenient,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DecodeOptions {
+    pub mode: DecodeMode,
+}
+
+impl DecodeOptions {
+    pub fn strict() -> Self {
+        DecodeOptions {
+            mode: DecodeMode::Strict,
+        }
+    }
+
+    pub fn lenient() -> Self {
+        DecodeOptions {
+            mode: DecodeMode::Lenient,
+        }
+    }
+}
+
+///
+/// Damage encountered while decoding in `DecodeMode::Lenient`.
+/// A strict decode either fails or returns an empty report.
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecodeReport {
+    /// Types of the chunks whose CRC did not match, in file order.
+    pub corrupt_chunks: Vec<String>,
+    /// Whether the file ended before the `IEND` chunk.
+    pub truncated: bool,
+    /// Number of scanlines that could not be reconstructed.
+    /// For interlaced images these are rows of the reduced pass images.
+    pub missing_rows: usize,
+}
+
+impl DecodeReport {
+    /// `true` when nothing was lost.
+    pub fn is_clean(&self) -> bool {
+        self.corrupt_chunks.is_empty() && !self.truncated && self.missing_rows == 0
+    }
+}
@@ -0,0 +1,114 @@
+use super::Header;
+use crate::error::Error;
+
+///
+/// Contents of the `tRNS` chunk.
+///
+/// For indexed images this is a list of alpha values, one
+/// per palette entry. For greyscale and truecolour images it
+/// is a single sample value that should be treated as fully
+/// transparent, compared at the image's original bit depth.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transparency {
+    Palette(Vec<u8>),
+    Gray(u16),
+    Rgb(u16, u16, u16),
+}
+
+impl Transparency {
+    pub fn parse(header: &Header, data: &[u8]) -> Result<Option<Self>, Error> {
+        let sample = |i: usize| u16::from_be_bytes([data[i], data[i + 1]]);
+
+        match header.color_type {
+            0 if data.len() >= 2 => Ok(Some(Transparency::Gray(sample(0)))),
+            2 if data.len() >= 6 => Ok(Some(Transparency::Rgb(sample(0), sample(2), sample(4)))),
+            3 => Ok(Some(Transparency::Palette(data.to_vec()))),
+            // tRNS is not permitted for colour types with a full alpha channel.
+            4 | 6 => Ok(None),
+            _ => Err(Error::decode("Truncated tRNS chunk")),
+        }
+    }
+}
+
+///
+/// Unpack a reconstructed scanline into one value per sample.
+/// Sub-byte samples are packed from the most significant bit,
+/// and 16-bit samples are stored big-endian.
+///
+fn unpack(row: &[u8], bit_depth: u8, count: usize) -> Vec<u16> {
+    match bit_depth {
+        8 => row[..count].iter().map(|&v| v as u16).collect(),
+        16 => row[..count * 2]
+            .chunks_exact(2)
+            .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+            .collect(),
+        depth => {
+            let depth = depth as usize;
+            let mask = (1u16 << depth) - 1;
+            (0..count)
+                .map(|i| {
+                    let bit = i * depth;
+                    let shift = 8 - depth - bit % 8;
+                    (row[bit / 8] as u16 >> shift) & mask
+                })
+                .collect()
+        }
+    }
+}
+
+///
+/// Convert a reconstructed scanline of `width` pixels into 8-bit
+/// samples in the layout of `Header::color_space`, appending to `out`.
+///
+/// Greyscale samples below 8 bits are scaled to the full range,
+/// 16-bit samples are rounded down to 8 bits, and palette indices
+/// are looked up in `palette`.
+///
+pub(crate) fn expand_row(
+    header: &Header,
+    palette: &[u8],
+    transparency: Option<&Transparency>,
+    row: &[u8],
+    width: usize,
+    out: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let samples = unpack(row, header.bit_depth, width * header.samples_per_pixel());
+
+    let max = (1u32 << header.bit_depth) - 1;
+    let scale = |value: u16| ((value as u32 * 255 + max / 2) / max) as u8;
+
+    match header.color_type {
+        0 => {
+            for &value in &samples {
+                out.push(scale(value));
+                if let Some(Transparency::Gray(key)) = transparency {
+                    out.push(if value == *key { 0 } else { 255 });
+                }
+            }
+        }
+        2 => {
+            for pixel in samples.chunks_exact(3) {
+                out.extend(pixel.iter().map(|&value| scale(value)));
+                if let Some(&Transparency::Rgb(r, g, b)) = transparency {
+                    out.push(if pixel == [r, g, b] { 0 } else { 255 });
+                }
+            }
+        }
+        3 => {
+            for &index in &samples {
+                let index = index as usize;
+                let entry = palette.get(index * 3..index * 3 + 3).ok_or_else(|| {
+                    Error::decode(format!("Palette index {index} is out of range"))
+                })?;
+                out.extend_from_slice(entry);
+                if let Some(Transparency::Palette(alpha)) = transparency {
+                    out.push(alpha.get(index).copied().unwrap_or(255));
+                }
+            }
+        }
+        _ => out.extend(samples.iter().map(|&value| scale(value))),
+    }
+
+    Ok(())
+}
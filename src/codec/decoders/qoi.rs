@@ -0,0 +1,151 @@
+use log::debug;
+
+use std::io::Read;
+
+use super::Decoder;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+pub(crate) const MAGIC: &[u8; 4] = b"qoif";
+pub(crate) const HEADER_SIZE: usize = 14;
+pub(crate) const END_MARKER: [u8; 8] = [0, 0, 0, 0, 0, 0, 0, 1];
+/// The reference implementation refuses larger images.
+pub(crate) const MAX_PIXELS: usize = 400_000_000;
+
+pub(crate) const OP_INDEX: u8 = 0x00;
+pub(crate) const OP_DIFF: u8 = 0x40;
+pub(crate) const OP_LUMA: u8 = 0x80;
+pub(crate) const OP_RUN: u8 = 0xC0;
+pub(crate) const OP_RGB: u8 = 0xFE;
+pub(crate) const OP_RGBA: u8 = 0xFF;
+pub(crate) const MASK: u8 = 0xC0;
+
+///
+/// Position of a pixel in the table of recently seen colours.
+///
+pub(crate) fn hash([r, g, b, a]: [u8; 4]) -> usize {
+    (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64
+}
+
+///
+/// `Decoder` for QOI, the "Quite OK Image" format.
+///
+/// Three channel files decode to `ColorSpace::RGB` and four
+/// channel files to `ColorSpace::RGBA`. The header's colour
+/// space byte (sRGB or linear) is informative and not applied.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct QoiDecoder;
+
+impl Decoder for QoiDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_bytes(&data)
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    if data.len() < HEADER_SIZE || !data.starts_with(MAGIC) {
+        return Err(Error::decode("Invalid QOI Signature"));
+    }
+
+    let width = u32::from_be_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let height = u32::from_be_bytes([data[8], data[9], data[10], data[11]]) as usize;
+    let (channels, colorspace_byte) = (data[12], data[13]);
+    debug!("QOI {width}x{height}, {channels} channels, colour space {colorspace_byte}");
+
+    let colorspace = match channels {
+        3 => ColorSpace::RGB,
+        4 => ColorSpace::RGBA,
+        _ => {
+            return Err(Error::decode(format!(
+                "Invalid QOI channel count {channels}"
+            )))
+        }
+    };
+    let pixel_count = width
+        .checked_mul(height)
+        .filter(|&count| count > 0 && count <= MAX_PIXELS)
+        .ok_or_else(|| Error::decode(format!("Invalid QOI dimensions {width}x{height}")))?;
+
+    let channels = channels as usize;
+    let chunks = &data[HEADER_SIZE..];
+    let mut out = Vec::with_capacity(pixel_count.min(chunks.len() * 62) * channels);
+
+    let mut index = [[0u8; 4]; 64];
+    let mut pixel = [0, 0, 0, 255u8];
+    let mut position = 0;
+    let byte = |position: usize| {
+        chunks
+            .get(position)
+            .copied()
+            .ok_or_else(|| Error::decode("Truncated QOI data"))
+    };
+
+    let mut decoded = 0;
+    while decoded < pixel_count {
+        let op = byte(position)?;
+        position += 1;
+
+        let mut run = 1;
+        match op {
+            OP_RGB => {
+                for channel in &mut pixel[..3] {
+                    *channel = byte(position)?;
+                    position += 1;
+                }
+            }
+            OP_RGBA => {
+                for channel in &mut pixel {
+                    *channel = byte(position)?;
+                    position += 1;
+                }
+            }
+            _ => match op & MASK {
+                OP_INDEX => pixel = index[op as usize],
+                OP_DIFF => {
+                    pixel[0] = pixel[0].wrapping_add((op >> 4) & 0x03).wrapping_sub(2);
+                    pixel[1] = pixel[1].wrapping_add((op >> 2) & 0x03).wrapping_sub(2);
+                    pixel[2] = pixel[2].wrapping_add(op & 0x03).wrapping_sub(2);
+                }
+                OP_LUMA => {
+                    let second = byte(position)?;
+                    position += 1;
+                    let dg = (op & 0x3F).wrapping_sub(32);
+                    pixel[0] = pixel[0]
+                        .wrapping_add(dg)
+                        .wrapping_add(second >> 4)
+                        .wrapping_sub(8);
+                    pixel[1] = pixel[1].wrapping_add(dg);
+                    pixel[2] = pixel[2]
+                        .wrapping_add(dg)
+                        .wrapping_add(second & 0x0F)
+                        .wrapping_sub(8);
+                }
+                _ => run = (op & 0x3F) as usize + 1,
+            },
+        }
+
+        index[hash(pixel)] = pixel;
+        // A run may not extend past the last pixel.
+        let run = run.min(pixel_count - decoded);
+        for _ in 0..run {
+            out.extend_from_slice(&pixel[..channels]);
+        }
+        decoded += run;
+    }
+
+    if chunks.get(position..position + END_MARKER.len()) != Some(&END_MARKER[..]) {
+        debug!("QOI end marker is missing");
+    }
+
+    let shape = Shape::new(width, height, Some(channels));
+    Ok(Image::from_data(out, shape, colorspace))
+}
@@ -0,0 +1,99 @@
+use flate2::read::ZlibDecoder;
+use log::debug;
+
+use std::io::Read;
+
+use crate::codec::lzw::{self, BitOrder};
+use crate::error::Error;
+
+///
+/// How the strips or tiles of a TIFF page are compressed.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    /// Byte oriented run length encoding (tag value 32773).
+    PackBits,
+    Lzw,
+    /// zlib streams, under either the official tag value 8 or
+    /// the older 32946.
+    Deflate,
+}
+
+impl Compression {
+    pub(crate) fn tag(&self) -> u16 {
+        match self {
+            Compression::None => 1,
+            Compression::PackBits => 32773,
+            Compression::Lzw => 5,
+            Compression::Deflate => 8,
+        }
+    }
+
+    pub(crate) fn from_tag(tag: u32) -> Result<Self, Error> {
+        match tag {
+            1 => Ok(Compression::None),
+            32773 => Ok(Compression::PackBits),
+            5 => Ok(Compression::Lzw),
+            8 | 32946 => Ok(Compression::Deflate),
+            _ => Err(Error::NotImplemented(format!("TIFF compression {tag}"))),
+        }
+    }
+
+    ///
+    /// Decompress one strip or tile into `expected` bytes. Short data
+    /// is padded with zeros, as libtiff does after warning.
+    ///
+    pub(crate) fn decompress(&self, data: &[u8], expected: usize) -> Result<Vec<u8>, Error> {
+        let mut out = match self {
+            Compression::None => data[..data.len().min(expected)].to_vec(),
+            Compression::PackBits => unpack_bits(data, expected),
+            // Unlike GIF, codes are packed from the most significant
+            // bit and widen one code early.
+            Compression::Lzw => lzw::decompress(data, 8, expected, BitOrder::Msb, true)?,
+            Compression::Deflate => {
+                let mut out = Vec::with_capacity(expected);
+                let decoder = ZlibDecoder::new(data);
+                if let Err(error) = decoder.take(expected as u64).read_to_end(&mut out) {
+                    debug!("Deflate data is damaged: {error}");
+                }
+                out
+            }
+        };
+        if out.len() < expected {
+            debug!("Strip or tile has {} of {expected} bytes", out.len());
+            out.resize(expected, 0);
+        }
+        Ok(out)
+    }
+}
+
+///
+/// Expand PackBits data: a header byte `n` below 128 copies the
+/// next `n + 1` bytes, above 128 repeats the next byte `257 - n`
+/// times, and 128 is skipped.
+///
+fn unpack_bits(data: &[u8], expected: usize) -> Vec<u8> {
+    let mut out = Vec::with_capacity(expected);
+    let mut position = 0;
+    while out.len() < expected && position < data.len() {
+        let header = data[position] as usize;
+        position += 1;
+        match header {
+            0..=127 => {
+                let end = (position + header + 1).min(data.len());
+                out.extend_from_slice(&data[position..end]);
+                position = end;
+            }
+            128 => {}
+            _ => {
+                if let Some(&byte) = data.get(position) {
+                    out.resize(out.len() + 257 - header, byte);
+                }
+                position += 1;
+            }
+        }
+    }
+    out.truncate(expected);
+    out
+}
@@ -0,0 +1,468 @@
+mod compression;
+
+use log::debug;
+
+use std::collections::HashSet;
+use std::io::Read;
+
+use super::{image_samples, Decoder, MAX_IMAGE_BYTES};
+use crate::codec::ifd::{self, ByteOrder, Entry, Value};
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+pub use compression::Compression;
+
+pub(crate) const TAG_IMAGE_WIDTH: u16 = 256;
+pub(crate) const TAG_IMAGE_LENGTH: u16 = 257;
+pub(crate) const TAG_BITS_PER_SAMPLE: u16 = 258;
+pub(crate) const TAG_COMPRESSION: u16 = 259;
+pub(crate) const TAG_PHOTOMETRIC: u16 = 262;
+pub(crate) const TAG_STRIP_OFFSETS: u16 = 273;
+pub(crate) const TAG_SAMPLES_PER_PIXEL: u16 = 277;
+pub(crate) const TAG_ROWS_PER_STRIP: u16 = 278;
+pub(crate) const TAG_STRIP_BYTE_COUNTS: u16 = 279;
+pub(crate) const TAG_X_RESOLUTION: u16 = 282;
+pub(crate) const TAG_Y_RESOLUTION: u16 = 283;
+pub(crate) const TAG_PLANAR_CONFIGURATION: u16 = 284;
+pub(crate) const TAG_RESOLUTION_UNIT: u16 = 296;
+pub(crate) const TAG_PAGE_NUMBER: u16 = 297;
+pub(crate) const TAG_PREDICTOR: u16 = 317;
+pub(crate) const TAG_COLOR_MAP: u16 = 320;
+pub(crate) const TAG_TILE_WIDTH: u16 = 322;
+pub(crate) const TAG_TILE_LENGTH: u16 = 323;
+pub(crate) const TAG_TILE_OFFSETS: u16 = 324;
+pub(crate) const TAG_TILE_BYTE_COUNTS: u16 = 325;
+pub(crate) const TAG_EXTRA_SAMPLES: u16 = 338;
+pub(crate) const TAG_SAMPLE_FORMAT: u16 = 339;
+
+pub(crate) const PHOTOMETRIC_WHITE_IS_ZERO: u16 = 0;
+pub(crate) const PHOTOMETRIC_BLACK_IS_ZERO: u16 = 1;
+pub(crate) const PHOTOMETRIC_RGB: u16 = 2;
+pub(crate) const PHOTOMETRIC_PALETTE: u16 = 3;
+
+/// `ExtraSamples` values for premultiplied and straight alpha.
+pub(crate) const ASSOCIATED_ALPHA: u32 = 1;
+pub(crate) const UNASSOCIATED_ALPHA: u32 = 2;
+
+/// `Predictor` value for horizontal differencing.
+pub(crate) const HORIZONTAL_PREDICTOR: u32 = 2;
+
+/// Writers often keep their default tile size for small images,
+/// so tiles up to this size may overhang the page.
+const MAX_TILE_OVERHANG: usize = 1024;
+
+///
+/// `Decoder` for baseline TIFF files, returning the first page.
+///
+/// Reads bilevel, greyscale, palette and RGB pages of 1 to 16 bits
+/// per sample, stored in strips or tiles, chunky or planar, and
+/// uncompressed or compressed with PackBits, LZW or Deflate.
+/// Samples are scaled to 8 bits. Use `pages` for multi-page files.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TiffDecoder;
+
+impl Decoder for TiffDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_bytes(&data)
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    match pages(data)?.next() {
+        Some(page) => page,
+        None => Err(Error::decode("TIFF contains no pages")),
+    }
+}
+
+///
+/// Iterate over the pages of a TIFF file, in the order of its IFD chain.
+///
+pub fn pages(data: &[u8]) -> Result<Pages<'_>, Error> {
+    let (order, next) = ifd::parse_header(data)?;
+    Ok(Pages {
+        data,
+        order,
+        next,
+        visited: HashSet::new(),
+    })
+}
+
+///
+/// Iterator over the pages of a TIFF file, see `pages`.
+///
+/// Stops after the first error, since a broken directory
+/// leaves no way to find the pages after it.
+///
+#[derive(Debug)]
+pub struct Pages<'a> {
+    data: &'a [u8],
+    order: ByteOrder,
+    /// Offset of the next IFD, zero after the last page.
+    next: u32,
+    visited: HashSet<u32>,
+}
+
+impl Iterator for Pages<'_> {
+    type Item = Result<Image, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next == 0 {
+            return None;
+        }
+        let offset = std::mem::take(&mut self.next);
+        if !self.visited.insert(offset) {
+            return Some(Err(Error::decode("IFD loop detected")));
+        }
+
+        let (entries, next) = match ifd::read_ifd(self.data, self.order, offset) {
+            Ok(directory) => directory,
+            Err(error) => return Some(Err(error)),
+        };
+        self.next = next;
+
+        let page = Page::parse(&entries).and_then(|page| page.decode(self.data, self.order));
+        if page.is_err() {
+            self.next = 0;
+        }
+        Some(page)
+    }
+}
+
+///
+/// The fields of one IFD needed to decode its image.
+///
+#[derive(Debug)]
+struct Page {
+    width: usize,
+    height: usize,
+    bits: u32,
+    samples: usize,
+    photometric: u16,
+    compression: Compression,
+    predictor: bool,
+    /// Each sample has its own set of strips or tiles.
+    planar: bool,
+    tiled: bool,
+    /// Size of each strip or tile. Strips are as wide as the image.
+    chunk_width: usize,
+    chunk_height: usize,
+    offsets: Vec<u32>,
+    byte_counts: Option<Vec<u32>>,
+    /// The sample after the colour ones is premultiplied alpha.
+    associated_alpha: bool,
+    color_map: Option<Vec<u32>>,
+}
+
+fn field(entries: &[Entry], tag: u16) -> Option<&Value> {
+    entries
+        .iter()
+        .find(|entry| entry.tag == tag)
+        .map(|entry| &entry.value)
+}
+
+fn field_u32(entries: &[Entry], tag: u16) -> Option<u32> {
+    field(entries, tag)?.as_u32()
+}
+
+fn required(entries: &[Entry], tag: u16) -> Result<u32, Error> {
+    field_u32(entries, tag).ok_or_else(|| Error::decode(format!("Missing TIFF tag {tag}")))
+}
+
+impl Page {
+    fn parse(entries: &[Entry]) -> Result<Self, Error> {
+        let width = required(entries, TAG_IMAGE_WIDTH)? as usize;
+        let height = required(entries, TAG_IMAGE_LENGTH)? as usize;
+        if width == 0 || height == 0 {
+            return Err(Error::decode(format!(
+                "Invalid TIFF dimensions {width}x{height}"
+            )));
+        }
+
+        let samples = field_u32(entries, TAG_SAMPLES_PER_PIXEL).unwrap_or(1) as usize;
+        if samples == 0 {
+            return Err(Error::decode("Invalid TIFF samples per pixel 0"));
+        }
+        // Compressed strips can expand to almost any size, so pages are
+        // capped rather than checked against the data. Every sample is
+        // held in 16 bits until the page is scaled.
+        if image_samples(width, height, samples, 2).is_none() {
+            return Err(Error::decode(format!(
+                "TIFF page {width}x{height} with {samples} samples per pixel is too large"
+            )));
+        }
+        let bits = match field(entries, TAG_BITS_PER_SAMPLE).and_then(Value::as_u32_vec) {
+            Some(bits) if bits.windows(2).any(|pair| pair[0] != pair[1]) => {
+                return Err(Error::NotImplemented(format!(
+                    "TIFF samples of mixed sizes {bits:?}"
+                )));
+            }
+            Some(bits) => bits.first().copied().unwrap_or(1),
+            None => 1,
+        };
+        if !matches!(bits, 1 | 2 | 4 | 8 | 16) {
+            return Err(Error::NotImplemented(format!(
+                "TIFF with {bits} bits per sample"
+            )));
+        }
+        if let Some(format) = field_u32(entries, TAG_SAMPLE_FORMAT).filter(|&format| format != 1) {
+            return Err(Error::NotImplemented(format!(
+                "TIFF sample format {format}"
+            )));
+        }
+
+        let compression = Compression::from_tag(field_u32(entries, TAG_COMPRESSION).unwrap_or(1))?;
+        let predictor = match field_u32(entries, TAG_PREDICTOR).unwrap_or(1) {
+            1 => false,
+            HORIZONTAL_PREDICTOR if bits >= 8 => true,
+            predictor => {
+                return Err(Error::NotImplemented(format!(
+                    "TIFF predictor {predictor} with {bits} bits per sample"
+                )));
+            }
+        };
+
+        // Writers sometimes leave it out; guess from the sample count.
+        let photometric = match field_u32(entries, TAG_PHOTOMETRIC) {
+            Some(photometric) => photometric as u16,
+            None if samples >= 3 => PHOTOMETRIC_RGB,
+            None => PHOTOMETRIC_BLACK_IS_ZERO,
+        };
+
+        let tiled = field(entries, TAG_TILE_OFFSETS).is_some();
+        let (chunk_width, chunk_height, offsets, byte_counts) = if tiled {
+            (
+                required(entries, TAG_TILE_WIDTH)? as usize,
+                required(entries, TAG_TILE_LENGTH)? as usize,
+                field(entries, TAG_TILE_OFFSETS).and_then(Value::as_u32_vec),
+                field(entries, TAG_TILE_BYTE_COUNTS).and_then(Value::as_u32_vec),
+            )
+        } else {
+            let rows = field_u32(entries, TAG_ROWS_PER_STRIP).unwrap_or(u32::MAX) as usize;
+            (
+                width,
+                rows.min(height),
+                field(entries, TAG_STRIP_OFFSETS).and_then(Value::as_u32_vec),
+                field(entries, TAG_STRIP_BYTE_COUNTS).and_then(Value::as_u32_vec),
+            )
+        };
+        if chunk_width == 0
+            || chunk_height == 0
+            || tiled && !(valid_tile(chunk_width, width) && valid_tile(chunk_height, height))
+        {
+            return Err(Error::decode(format!(
+                "Invalid TIFF strip or tile size {chunk_width}x{chunk_height}"
+            )));
+        }
+        let offsets = offsets.ok_or_else(|| Error::decode("Missing TIFF strip offsets"))?;
+
+        let associated_alpha = field_u32(entries, TAG_EXTRA_SAMPLES) == Some(ASSOCIATED_ALPHA);
+
+        Ok(Page {
+            width,
+            height,
+            bits,
+            samples,
+            photometric,
+            compression,
+            predictor,
+            planar: samples > 1 && field_u32(entries, TAG_PLANAR_CONFIGURATION) == Some(2),
+            tiled,
+            chunk_width,
+            chunk_height,
+            offsets,
+            byte_counts,
+            associated_alpha,
+            color_map: field(entries, TAG_COLOR_MAP).and_then(Value::as_u32_vec),
+        })
+    }
+
+    fn decode(&self, data: &[u8], order: ByteOrder) -> Result<Image, Error> {
+        let samples = self.read_samples(data, order)?;
+        self.to_image(&samples)
+    }
+
+    ///
+    /// Decompress every strip or tile into one sample per channel
+    /// per pixel, in row major order.
+    ///
+    fn read_samples(&self, data: &[u8], order: ByteOrder) -> Result<Vec<u16>, Error> {
+        let (planes, chunk_samples) = if self.planar {
+            (self.samples, 1)
+        } else {
+            (1, self.samples)
+        };
+        let across = self.width.div_ceil(self.chunk_width);
+        let down = self.height.div_ceil(self.chunk_height);
+        if self.offsets.len() < planes * across * down {
+            return Err(Error::decode(format!(
+                "TIFF has {} of {} strips or tiles",
+                self.offsets.len(),
+                planes * across * down
+            )));
+        }
+
+        let row_bytes = (self.chunk_width * chunk_samples * self.bits as usize).div_ceil(8);
+        let chunk_bytes = row_bytes
+            .checked_mul(self.chunk_height)
+            .filter(|&bytes| bytes <= MAX_IMAGE_BYTES)
+            .ok_or_else(|| Error::decode("TIFF strip or tile is too large"))?;
+        let mut values = vec![0u16; self.width * self.height * self.samples];
+
+        for plane in 0..planes {
+            for chunk_row in 0..down {
+                for chunk_column in 0..across {
+                    let index = (plane * down + chunk_row) * across + chunk_column;
+                    let top = chunk_row * self.chunk_height;
+                    let left = chunk_column * self.chunk_width;
+                    // The last strip may be short, but tiles are always whole.
+                    let expected = if self.tiled {
+                        chunk_bytes
+                    } else {
+                        row_bytes * self.chunk_height.min(self.height - top)
+                    };
+
+                    let start = self.offsets[index] as usize;
+                    let count = match &self.byte_counts {
+                        Some(counts) => counts.get(index).copied().unwrap_or(0) as usize,
+                        // Only an uncompressed image can do without.
+                        None if self.compression == Compression::None => expected,
+                        None => return Err(Error::decode("Missing TIFF strip byte counts")),
+                    };
+                    let end = start.saturating_add(count).min(data.len());
+                    let raw = data.get(start..end).unwrap_or_default();
+                    let chunk = self.compression.decompress(raw, expected)?;
+
+                    for (y, row) in (top..self.height).zip(chunk.chunks_exact(row_bytes)) {
+                        let mut row =
+                            unpack(row, self.bits, self.chunk_width * chunk_samples, order);
+                        if self.predictor {
+                            let mask = ((1u32 << self.bits) - 1) as u16;
+                            for i in chunk_samples..row.len() {
+                                row[i] = row[i].wrapping_add(row[i - chunk_samples]) & mask;
+                            }
+                        }
+
+                        let columns = self.chunk_width.min(self.width - left);
+                        for (x, pixel) in row.chunks_exact(chunk_samples).take(columns).enumerate()
+                        {
+                            let offset = (y * self.width + left + x) * self.samples + plane;
+                            values[offset..offset + chunk_samples].copy_from_slice(pixel);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(values)
+    }
+
+    ///
+    /// Scale the samples to 8 bits in the `ColorSpace` of the page.
+    ///
+    /// A sample after the colour ones is read as alpha whatever
+    /// `ExtraSamples` says, since many writers leave it out.
+    ///
+    fn to_image(&self, values: &[u16]) -> Result<Image, Error> {
+        let max = (1u32 << self.bits) - 1;
+        let scale = |value: u16| ((value as u32 * 255 + max / 2) / max) as u8;
+
+        let (colorspace, color_channels) = match self.photometric {
+            PHOTOMETRIC_WHITE_IS_ZERO | PHOTOMETRIC_BLACK_IS_ZERO | PHOTOMETRIC_PALETTE
+                if self.samples >= 1 =>
+            {
+                let colorspace = match self.photometric {
+                    PHOTOMETRIC_PALETTE => ColorSpace::RGB,
+                    _ if self.samples >= 2 => ColorSpace::GRAYA,
+                    _ => ColorSpace::GRAY,
+                };
+                (colorspace, 1)
+            }
+            PHOTOMETRIC_RGB if self.samples >= 4 => (ColorSpace::RGBA, 3),
+            PHOTOMETRIC_RGB if self.samples == 3 => (ColorSpace::RGB, 3),
+            photometric => {
+                return Err(Error::NotImplemented(format!(
+                    "TIFF photometric interpretation {photometric} with {} samples",
+                    self.samples
+                )));
+            }
+        };
+
+        let color_map = match (self.photometric, &self.color_map) {
+            (PHOTOMETRIC_PALETTE, Some(map)) if map.len() >= 3 << self.bits => Some(map),
+            (PHOTOMETRIC_PALETTE, _) => return Err(Error::decode("Missing TIFF colour map")),
+            _ => None,
+        };
+        let has_alpha = matches!(colorspace, ColorSpace::GRAYA | ColorSpace::RGBA);
+
+        let mut data = Vec::with_capacity(self.width * self.height * colorspace.channels());
+        for pixel in values.chunks_exact(self.samples) {
+            let start = data.len();
+            match (self.photometric, color_map) {
+                (PHOTOMETRIC_WHITE_IS_ZERO, _) => data.push(255 - scale(pixel[0])),
+                (_, Some(map)) => {
+                    // Colour map entries are always 16 bits.
+                    let entries = 1usize << self.bits;
+                    let index = pixel[0] as usize;
+                    data.extend((0..3).map(|channel| (map[channel * entries + index] / 257) as u8));
+                }
+                _ => data.extend(pixel[..color_channels].iter().map(|&value| scale(value))),
+            }
+
+            if has_alpha {
+                let alpha = scale(pixel[color_channels]);
+                if self.associated_alpha && alpha > 0 {
+                    for value in &mut data[start..] {
+                        *value = (*value as u32 * 255 / alpha as u32).min(255) as u8;
+                    }
+                }
+                data.push(alpha);
+            }
+        }
+
+        debug!(
+            "Decoded {}x{} TIFF page as {colorspace:?}",
+            self.width, self.height
+        );
+        let shape = Shape::new(self.width, self.height, Some(colorspace.channels()));
+        Ok(Image::from_data(data, shape, colorspace))
+    }
+}
+
+///
+/// Tiles are a multiple of 16 across and down, and no larger than
+/// the page needs unless they are small.
+///
+fn valid_tile(tile: usize, image: usize) -> bool {
+    tile % 16 == 0 && tile <= image.next_multiple_of(16).max(MAX_TILE_OVERHANG)
+}
+
+///
+/// Split a row into `count` samples. Samples below 8 bits are packed
+/// from the most significant bit, 16-bit samples use the file's byte order.
+///
+fn unpack(row: &[u8], bits: u32, count: usize, order: ByteOrder) -> Vec<u16> {
+    match bits {
+        8 => row[..count].iter().map(|&value| value as u16).collect(),
+        16 => (0..count)
+            .map(|i| order.u16(row, i * 2).unwrap_or(0))
+            .collect(),
+        _ => {
+            let mask = (1u16 << bits) - 1;
+            (0..count)
+                .map(|i| {
+                    let bit = i * bits as usize;
+                    let shift = 8 - bits as usize - bit % 8;
+                    (row[bit / 8] as u16 >> shift) & mask
+                })
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,192 @@
+use crate::error::Error;
+
+/// Codes up to this many bits are resolved with a single table lookup.
+const LOOKUP_BITS: u32 = 8;
+/// VP8L code lengths are at most 15 bits.
+const MAX_LENGTH: usize = 15;
+
+///
+/// A canonical prefix code built from the code length of each symbol.
+///
+/// Codes are stored most significant bit first in a stream that is
+/// otherwise read from the least significant bit, exactly as in
+/// Deflate, so lookups index by the bit reversed code.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct PrefixCode {
+    /// The symbol of a code with only one symbol, which takes no bits.
+    single: Option<u16>,
+    symbols: Vec<u16>,
+    min_code: [i32; MAX_LENGTH + 1],
+    max_code: [i32; MAX_LENGTH + 1],
+    symbol_offset: [i32; MAX_LENGTH + 1],
+    /// (code length, symbol) for every `LOOKUP_BITS` bit prefix.
+    lookup: Vec<(u8, u16)>,
+}
+
+impl PrefixCode {
+    ///
+    /// # Arguments
+    ///
+    /// * `lengths` - The code length of each symbol, 0 for unused ones
+    ///
+    /// # Returns
+    ///
+    /// * An error unless the lengths describe a complete code
+    ///
+    pub fn new(lengths: &[u8]) -> Result<Self, Error> {
+        if lengths.iter().any(|&length| length as usize > MAX_LENGTH) {
+            return Err(Error::decode("Invalid prefix code length"));
+        }
+
+        let mut code = PrefixCode {
+            single: None,
+            symbols: Vec::new(),
+            min_code: [0; MAX_LENGTH + 1],
+            max_code: [-1; MAX_LENGTH + 1],
+            symbol_offset: [0; MAX_LENGTH + 1],
+            lookup: vec![(0, 0); 1 << LOOKUP_BITS],
+        };
+
+        if lengths.iter().filter(|&&length| length > 0).count() == 1 {
+            let symbol = lengths.iter().position(|&length| length > 0).unwrap();
+            code.single = Some(symbol as u16);
+            return Ok(code);
+        }
+
+        let mut next = 0i32;
+        for length in 1..=MAX_LENGTH {
+            code.symbol_offset[length] = code.symbols.len() as i32;
+            code.min_code[length] = next;
+
+            for (symbol, _) in lengths
+                .iter()
+                .enumerate()
+                .filter(|&(_, &l)| l as usize == length)
+            {
+                if length as u32 <= LOOKUP_BITS {
+                    let reversed = reverse(next as u32, length as u32) as usize;
+                    for entry in code.lookup.iter_mut().skip(reversed).step_by(1 << length) {
+                        *entry = (length as u8, symbol as u16);
+                    }
+                }
+                code.symbols.push(symbol as u16);
+                next += 1;
+            }
+
+            if next > 1 << length {
+                return Err(Error::decode("Prefix code has too many codes"));
+            }
+            if next > code.min_code[length] {
+                code.max_code[length] = next - 1;
+            }
+            next <<= 1;
+        }
+
+        if next != 1 << (MAX_LENGTH + 1) {
+            return Err(Error::decode("Incomplete prefix code"));
+        }
+        Ok(code)
+    }
+}
+
+fn reverse(code: u32, length: u32) -> u32 {
+    code.reverse_bits() >> (32 - length)
+}
+
+///
+/// Reads bits, least significant first, from a VP8L bitstream.
+///
+/// Reading past the end yields zero bits, and `overrun` reports
+/// whether any were used, so callers check after a run of reads
+/// instead of on every one.
+///
+pub(crate) struct BitReader<'a> {
+    data: &'a [u8],
+    position: usize,
+    buffer: u64,
+    count: u32,
+    /// Bits of zero padding added to the buffer after the data ended.
+    padding: u32,
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        BitReader {
+            data,
+            position: 0,
+            buffer: 0,
+            count: 0,
+            padding: 0,
+        }
+    }
+
+    fn fill(&mut self) {
+        while self.count <= 56 {
+            match self.data.get(self.position) {
+                Some(&byte) => {
+                    self.buffer |= (byte as u64) << self.count;
+                    self.position += 1;
+                }
+                None => self.padding += 8,
+            }
+            self.count += 8;
+        }
+    }
+
+    fn peek(&mut self, bits: u32) -> u32 {
+        if self.count < bits {
+            self.fill();
+        }
+        (self.buffer & ((1 << bits) - 1)) as u32
+    }
+
+    fn consume(&mut self, bits: u32) {
+        self.buffer >>= bits;
+        self.count -= bits;
+    }
+
+    pub fn read_bits(&mut self, bits: u32) -> u32 {
+        if bits == 0 {
+            return 0;
+        }
+        let value = self.peek(bits);
+        self.consume(bits);
+        value
+    }
+
+    pub fn read_bit(&mut self) -> bool {
+        self.read_bits(1) == 1
+    }
+
+    ///
+    /// Whether more bits were read than the data holds.
+    ///
+    pub fn overrun(&self) -> bool {
+        self.padding > self.count
+    }
+
+    pub fn decode(&mut self, code: &PrefixCode) -> Result<u16, Error> {
+        if let Some(symbol) = code.single {
+            return Ok(symbol);
+        }
+
+        let prefix = self.peek(LOOKUP_BITS) as usize;
+        let (length, symbol) = code.lookup[prefix];
+        if length > 0 {
+            self.consume(length as u32);
+            return Ok(symbol);
+        }
+
+        for length in LOOKUP_BITS as usize + 1..=MAX_LENGTH {
+            let value = reverse(self.peek(length as u32), length as u32) as i32;
+            if value <= code.max_code[length] {
+                self.consume(length as u32);
+                let index = code.symbol_offset[length] + value - code.min_code[length];
+                return Ok(code.symbols[index as usize]);
+            }
+        }
+
+        Err(Error::decode("Invalid prefix code"))
+    }
+}
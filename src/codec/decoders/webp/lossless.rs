@@ -0,0 +1,336 @@
+use log::debug;
+
+use super::huffman::{BitReader, PrefixCode};
+use super::transform::Transform;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+const SIGNATURE: u8 = 0x2F;
+const HEADER_SIZE: usize = 5;
+
+/// Green symbols past the 256 literals are backward reference lengths.
+const LENGTH_CODES: usize = 24;
+const DISTANCE_CODES: usize = 40;
+const MAX_CACHE_BITS: u32 = 11;
+
+/// The order code lengths of the code length code are stored in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    17, 18, 0, 1, 2, 3, 4, 5, 16, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+];
+
+///
+/// (x, y) offsets of the 120 shortest distance codes, nearest first.
+///
+#[rustfmt::skip]
+const DISTANCE_MAP: [(i8, i8); 120] = [
+    (0, 1),  (1, 0),  (1, 1),  (-1, 1), (0, 2),  (2, 0),  (1, 2),  (-1, 2),
+    (2, 1),  (-2, 1), (2, 2),  (-2, 2), (0, 3),  (3, 0),  (1, 3),  (-1, 3),
+    (3, 1),  (-3, 1), (2, 3),  (-2, 3), (3, 2),  (-3, 2), (0, 4),  (4, 0),
+    (1, 4),  (-1, 4), (4, 1),  (-4, 1), (3, 3),  (-3, 3), (2, 4),  (-2, 4),
+    (4, 2),  (-4, 2), (0, 5),  (3, 4),  (-3, 4), (4, 3),  (-4, 3), (5, 0),
+    (1, 5),  (-1, 5), (5, 1),  (-5, 1), (2, 5),  (-2, 5), (5, 2),  (-5, 2),
+    (4, 4),  (-4, 4), (3, 5),  (-3, 5), (5, 3),  (-5, 3), (0, 6),  (6, 0),
+    (1, 6),  (-1, 6), (6, 1),  (-6, 1), (2, 6),  (-2, 6), (6, 2),  (-6, 2),
+    (4, 5),  (-4, 5), (5, 4),  (-5, 4), (3, 6),  (-3, 6), (6, 3),  (-6, 3),
+    (0, 7),  (7, 0),  (1, 7),  (-1, 7), (5, 5),  (-5, 5), (7, 1),  (-7, 1),
+    (4, 6),  (-4, 6), (6, 4),  (-6, 4), (2, 7),  (-2, 7), (7, 2),  (-7, 2),
+    (3, 7),  (-3, 7), (7, 3),  (-7, 3), (5, 6),  (-5, 6), (6, 5),  (-6, 5),
+    (8, 0),  (4, 7),  (-4, 7), (7, 4),  (-7, 4), (8, 1),  (8, 2),  (6, 6),
+    (-6, 6), (8, 3),  (5, 7),  (-5, 7), (7, 5),  (-7, 5), (8, 4),  (6, 7),
+    (-6, 7), (7, 6),  (-7, 6), (8, 5),  (7, 7),  (-7, 7), (8, 6),  (8, 7),
+];
+
+///
+/// Size of an image subsampled by `1 << bits`, such as
+/// the number of blocks across a transform image.
+///
+pub(crate) fn subsample_size(size: usize, bits: u32) -> usize {
+    size.div_ceil(1 << bits)
+}
+
+///
+/// The five prefix codes used together to decode a pixel.
+///
+struct PrefixGroup {
+    /// Green, backward reference lengths and colour cache indices.
+    green: PrefixCode,
+    red: PrefixCode,
+    blue: PrefixCode,
+    alpha: PrefixCode,
+    distance: PrefixCode,
+}
+
+impl PrefixGroup {
+    fn read(reader: &mut BitReader, cache_size: usize) -> Result<Self, Error> {
+        Ok(PrefixGroup {
+            green: read_prefix_code(reader, 256 + LENGTH_CODES + cache_size)?,
+            red: read_prefix_code(reader, 256)?,
+            blue: read_prefix_code(reader, 256)?,
+            alpha: read_prefix_code(reader, 256)?,
+            distance: read_prefix_code(reader, DISTANCE_CODES)?,
+        })
+    }
+}
+
+///
+/// Decode the payload of a `VP8L` chunk.
+///
+/// Images always decode to `ColorSpace::RGBA`; the header's
+/// "alpha is used" hint is informative only.
+///
+pub(crate) fn decode(data: &[u8]) -> Result<Image, Error> {
+    if data.len() < HEADER_SIZE || data[0] != SIGNATURE {
+        return Err(Error::decode("Invalid VP8L signature"));
+    }
+
+    let mut reader = BitReader::new(&data[1..]);
+    let width = reader.read_bits(14) as usize + 1;
+    let height = reader.read_bits(14) as usize + 1;
+    let alpha_used = reader.read_bit();
+    let version = reader.read_bits(3);
+    debug!("VP8L {width}x{height}, alpha used {alpha_used}");
+    if version != 0 {
+        return Err(Error::decode(format!("Invalid VP8L version {version}")));
+    }
+
+    let argb = decode_argb(&mut reader, width, height)?;
+    if reader.overrun() {
+        return Err(Error::decode("Truncated VP8L data"));
+    }
+
+    let data = argb
+        .iter()
+        .flat_map(|pixel| {
+            let [alpha, red, green, blue] = pixel.to_be_bytes();
+            [red, green, blue, alpha]
+        })
+        .collect();
+    Ok(Image::from_data(
+        data,
+        Shape::new(width, height, Some(4)),
+        ColorSpace::RGBA,
+    ))
+}
+
+///
+/// Decode the main image: its transforms, the entropy coded
+/// pixels, then the transforms undone in reverse order.
+///
+fn decode_argb(reader: &mut BitReader, width: usize, height: usize) -> Result<Vec<u32>, Error> {
+    // Each transform with the width of the image it applies to.
+    let mut transforms: Vec<(Transform, usize)> = Vec::new();
+    let mut coded_width = width;
+    while reader.read_bit() {
+        let transform = Transform::read(reader, coded_width, height)?;
+        if transforms.iter().any(|(t, _)| t.kind() == transform.kind()) {
+            return Err(Error::decode("Repeated VP8L transform"));
+        }
+        let transform_width = coded_width;
+        coded_width = transform.coded_width(coded_width);
+        transforms.push((transform, transform_width));
+    }
+
+    let mut pixels = decode_entropy_image(reader, coded_width, height, true)?;
+    for (transform, width) in transforms.iter().rev() {
+        pixels = transform.inverse(pixels, *width, height);
+    }
+    Ok(pixels)
+}
+
+///
+/// Decode entropy coded ARGB pixels.
+///
+/// # Arguments
+///
+/// * `main` - Whether this is the main image, which alone may
+///   use different prefix codes in different areas. Transform
+///   data and the entropy image use a single group of codes.
+///
+pub(crate) fn decode_entropy_image(
+    reader: &mut BitReader,
+    width: usize,
+    height: usize,
+    main: bool,
+) -> Result<Vec<u32>, Error> {
+    let cache_bits = if reader.read_bit() {
+        let bits = reader.read_bits(4);
+        if !(1..=MAX_CACHE_BITS).contains(&bits) {
+            return Err(Error::decode(format!(
+                "Invalid VP8L colour cache bits {bits}"
+            )));
+        }
+        bits
+    } else {
+        0
+    };
+    let cache_size = if cache_bits > 0 { 1 << cache_bits } else { 0 };
+
+    // Which group of prefix codes each block of pixels uses.
+    let mut group_bits = 0;
+    let mut group_image = Vec::new();
+    if main && reader.read_bit() {
+        group_bits = reader.read_bits(3) + 2;
+        group_image = decode_entropy_image(
+            reader,
+            subsample_size(width, group_bits),
+            subsample_size(height, group_bits),
+            false,
+        )?;
+        for group in &mut group_image {
+            *group = *group >> 8 & 0xFFFF;
+        }
+    }
+    let group_count = group_image.iter().max().map_or(1, |&max| max as usize + 1);
+    let groups = (0..group_count)
+        .map(|_| PrefixGroup::read(reader, cache_size))
+        .collect::<Result<Vec<_>, _>>()?;
+    let blocks_wide = subsample_size(width, group_bits);
+
+    let total = width * height;
+    let mut pixels: Vec<u32> = Vec::with_capacity(total);
+    let mut cache = vec![0u32; cache_size];
+    // Pixels before this one have been added to the cache.
+    let mut cached = 0;
+
+    while pixels.len() < total {
+        let position = pixels.len();
+        let group = if group_image.is_empty() {
+            &groups[0]
+        } else {
+            let (x, y) = (position % width, position / width);
+            &groups[group_image[(y >> group_bits) * blocks_wide + (x >> group_bits)] as usize]
+        };
+
+        let green = reader.decode(&group.green)? as usize;
+        if green < 256 {
+            let red = reader.decode(&group.red)? as u32;
+            let blue = reader.decode(&group.blue)? as u32;
+            let alpha = reader.decode(&group.alpha)? as u32;
+            pixels.push(alpha << 24 | red << 16 | (green as u32) << 8 | blue);
+        } else if green < 256 + LENGTH_CODES {
+            let length = prefix_value(reader, green - 256);
+            let symbol = reader.decode(&group.distance)? as usize;
+            let distance = plane_distance(prefix_value(reader, symbol), width);
+            if distance > position || length > total - position {
+                return Err(Error::decode("Invalid VP8L backward reference"));
+            }
+            // The source may overlap the copy, repeating a pattern.
+            for i in position..position + length {
+                pixels.push(pixels[i - distance]);
+            }
+        } else {
+            pixels.push(cache[green - 256 - LENGTH_CODES]);
+        }
+
+        if cache_bits > 0 {
+            for &pixel in &pixels[cached..] {
+                cache[(pixel.wrapping_mul(0x1E35A7BD) >> (32 - cache_bits)) as usize] = pixel;
+            }
+            cached = pixels.len();
+        }
+
+        if reader.overrun() {
+            return Err(Error::decode("Truncated VP8L data"));
+        }
+    }
+
+    Ok(pixels)
+}
+
+///
+/// Read a prefix code, in either its simple form (one or two
+/// symbols) or as code lengths compressed with another prefix code.
+///
+fn read_prefix_code(reader: &mut BitReader, alphabet_size: usize) -> Result<PrefixCode, Error> {
+    let mut lengths = vec![0u8; alphabet_size];
+
+    if reader.read_bit() {
+        let count = reader.read_bits(1) + 1;
+        let first_bits = if reader.read_bit() { 8 } else { 1 };
+        let mut symbols = vec![reader.read_bits(first_bits) as usize];
+        if count == 2 {
+            symbols.push(reader.read_bits(8) as usize);
+        }
+        for symbol in symbols {
+            *lengths
+                .get_mut(symbol)
+                .ok_or_else(|| Error::decode("Invalid VP8L prefix code symbol"))? = 1;
+        }
+        return PrefixCode::new(&lengths);
+    }
+
+    let mut length_lengths = [0u8; CODE_LENGTH_ORDER.len()];
+    let count = reader.read_bits(4) as usize + 4;
+    for &symbol in &CODE_LENGTH_ORDER[..count] {
+        length_lengths[symbol] = reader.read_bits(3) as u8;
+    }
+    let length_code = PrefixCode::new(&length_lengths)?;
+
+    // How many code length symbols follow, when fewer than needed.
+    let mut remaining = if reader.read_bit() {
+        let bits = 2 + 2 * reader.read_bits(3);
+        let max_symbol = 2 + reader.read_bits(bits) as usize;
+        if max_symbol > alphabet_size {
+            return Err(Error::decode("Invalid VP8L code length count"));
+        }
+        max_symbol
+    } else {
+        alphabet_size
+    };
+
+    let mut symbol = 0;
+    let mut previous = 8;
+    while symbol < alphabet_size && remaining > 0 {
+        remaining -= 1;
+        let (value, repeat) = match reader.decode(&length_code)? {
+            length @ 0..=15 => {
+                lengths[symbol] = length as u8;
+                symbol += 1;
+                if length != 0 {
+                    previous = length as u8;
+                }
+                continue;
+            }
+            16 => (previous, 3 + reader.read_bits(2) as usize),
+            17 => (0, 3 + reader.read_bits(3) as usize),
+            _ => (0, 11 + reader.read_bits(7) as usize),
+        };
+        let run = lengths
+            .get_mut(symbol..symbol + repeat)
+            .ok_or_else(|| Error::decode("Invalid VP8L code length repeat"))?;
+        run.fill(value);
+        symbol += repeat;
+    }
+
+    if reader.overrun() {
+        return Err(Error::decode("Truncated VP8L data"));
+    }
+    PrefixCode::new(&lengths)
+}
+
+///
+/// The length or distance coded by a prefix symbol and its extra bits.
+///
+fn prefix_value(reader: &mut BitReader, symbol: usize) -> usize {
+    if symbol < 4 {
+        return symbol + 1;
+    }
+    let extra_bits = (symbol as u32 - 2) >> 1;
+    let offset = (2 + (symbol & 1)) << extra_bits;
+    offset + reader.read_bits(extra_bits) as usize + 1
+}
+
+///
+/// Turn a distance code into a distance in pixels. The first 120
+/// codes name nearby pixels in two dimensions, the rest are linear.
+///
+fn plane_distance(code: usize, width: usize) -> usize {
+    if code > DISTANCE_MAP.len() {
+        return code - DISTANCE_MAP.len();
+    }
+    let (dx, dy) = DISTANCE_MAP[code - 1];
+    let distance = dx as isize + dy as isize * width as isize;
+    distance.max(1) as usize
+}
@@ -0,0 +1,100 @@
+mod huffman;
+mod lossless;
+mod transform;
+
+use log::debug;
+
+use std::io::Read;
+
+use super::Decoder;
+use crate::error::Error;
+use crate::image::Image;
+
+/// `VP8X` flag for files holding an animation.
+const ANIMATION_FLAG: u8 = 0x02;
+
+///
+/// `Decoder` for lossless WebP files.
+///
+/// Both the simple format (a lone `VP8L` chunk) and the extended
+/// format (`VP8X`, with metadata chunks alongside) are read, and
+/// decode to `ColorSpace::RGBA`. Lossy `VP8` data and animations
+/// are rejected with `Error::UnsupportedFormat` and
+/// `Error::NotImplemented` respectively.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WebpDecoder;
+
+impl Decoder for WebpDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    decode_bytes(&data)
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    let mut chunks = chunks(data)?;
+    let (id, payload) = chunks
+        .next()
+        .ok_or_else(|| Error::decode("WebP contains no chunks"))?;
+
+    if &id != b"VP8X" {
+        return decode_chunk(id, payload);
+    }
+
+    let flags = *payload
+        .first()
+        .ok_or_else(|| Error::decode("Truncated VP8X chunk"))?;
+    debug!("Extended WebP, flags {flags:#04X}");
+    if flags & ANIMATION_FLAG != 0 {
+        return Err(Error::NotImplemented("Animated WebP".to_string()));
+    }
+    chunks
+        .find(|(id, _)| id == b"VP8L" || id == b"VP8 ")
+        .map_or_else(
+            || Err(Error::decode("WebP contains no image data")),
+            |(id, payload)| decode_chunk(id, payload),
+        )
+}
+
+fn decode_chunk(id: [u8; 4], payload: &[u8]) -> Result<Image, Error> {
+    match &id {
+        b"VP8L" => lossless::decode(payload),
+        b"VP8 " => Err(Error::UnsupportedFormat(
+            "Lossy WebP (VP8) is not supported, only lossless (VP8L)".to_string(),
+        )),
+        _ => Err(Error::decode(format!(
+            "Unexpected WebP chunk {}",
+            String::from_utf8_lossy(&id)
+        ))),
+    }
+}
+
+///
+/// Iterate over the (id, payload) chunks of a WebP file's RIFF container.
+///
+/// A chunk running past the end of the data is cut short, leaving
+/// its decoder to report the truncation.
+///
+pub(crate) fn chunks(data: &[u8]) -> Result<impl Iterator<Item = ([u8; 4], &[u8])>, Error> {
+    if data.len() < 12 || !data.starts_with(b"RIFF") || &data[8..12] != b"WEBP" {
+        return Err(Error::decode("Invalid WebP signature"));
+    }
+    // The RIFF size counts from the "WEBP" form type.
+    let size = u32::from_le_bytes([data[4], data[5], data[6], data[7]]) as usize;
+    let mut body = &data[12..data.len().min(size.saturating_add(8)).max(12)];
+
+    Ok(std::iter::from_fn(move || {
+        let id: [u8; 4] = body.get(..4)?.try_into().ok()?;
+        let length = u32::from_le_bytes(body.get(4..8)?.try_into().ok()?) as usize;
+        let (payload, rest) = body[8..].split_at(length.min(body.len() - 8));
+        // Chunks are padded to an even length.
+        body = rest.get(length & 1..).unwrap_or_default();
+        Some((id, payload))
+    }))
+}
@@ -0,0 +1,267 @@
+use super::huffman::BitReader;
+use super::lossless::{decode_entropy_image, subsample_size};
+use crate::error::Error;
+
+///
+/// A reversible change the encoder made to the ARGB pixels before
+/// entropy coding them. Decoders undo them in the reverse order
+/// to the one they appear in the bitstream.
+///
+#[derive(Debug, Clone)]
+pub(crate) enum Transform {
+    /// Each pixel is stored as its difference from a prediction
+    /// made from its neighbours, with one of 14 predictors per
+    /// block chosen by the green channel of `modes`.
+    Predictor { bits: u32, modes: Vec<u32> },
+    /// Red and blue are decorrelated from green (and blue from red)
+    /// with multipliers per block, held in `multipliers`.
+    Color { bits: u32, multipliers: Vec<u32> },
+    /// Green is subtracted from red and blue.
+    SubtractGreen,
+    /// Pixels are indices into `palette`, several packed into
+    /// one pixel when there are 16 colours or fewer.
+    ColorIndexing { bits: u32, palette: Vec<u32> },
+}
+
+impl Transform {
+    ///
+    /// Read the transform following a set "transform present" bit.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - Width of the image the transform applies to,
+    ///   which may already be narrowed by colour indexing
+    ///
+    pub fn read(reader: &mut BitReader, width: usize, height: usize) -> Result<Self, Error> {
+        match reader.read_bits(2) {
+            kind @ (0 | 1) => {
+                let bits = reader.read_bits(3) + 2;
+                let data = decode_entropy_image(
+                    reader,
+                    subsample_size(width, bits),
+                    subsample_size(height, bits),
+                    false,
+                )?;
+                Ok(if kind == 0 {
+                    Transform::Predictor { bits, modes: data }
+                } else {
+                    Transform::Color {
+                        bits,
+                        multipliers: data,
+                    }
+                })
+            }
+            2 => Ok(Transform::SubtractGreen),
+            _ => {
+                let size = reader.read_bits(8) as usize + 1;
+                let mut palette = decode_entropy_image(reader, size, 1, false)?;
+                // Entries are stored as differences from the one before.
+                for i in 1..palette.len() {
+                    palette[i] = add_pixels(palette[i], palette[i - 1]);
+                }
+                let bits = match size {
+                    1..=2 => 3,
+                    3..=4 => 2,
+                    5..=16 => 1,
+                    _ => 0,
+                };
+                Ok(Transform::ColorIndexing { bits, palette })
+            }
+        }
+    }
+
+    ///
+    /// The tag the transform is read with, since each
+    /// may appear at most once per image.
+    ///
+    pub fn kind(&self) -> u8 {
+        match self {
+            Transform::Predictor { .. } => 0,
+            Transform::Color { .. } => 1,
+            Transform::SubtractGreen => 2,
+            Transform::ColorIndexing { .. } => 3,
+        }
+    }
+
+    ///
+    /// Width of the pixels the transform produces its input from:
+    /// narrower for packed colour indices, otherwise unchanged.
+    ///
+    pub fn coded_width(&self, width: usize) -> usize {
+        match self {
+            Transform::ColorIndexing { bits, .. } => subsample_size(width, *bits),
+            _ => width,
+        }
+    }
+
+    ///
+    /// Undo the transform.
+    ///
+    /// # Arguments
+    ///
+    /// * `pixels` - ARGB pixels, `coded_width(width)` per row
+    /// * `width` - Width the transform was read with
+    ///
+    pub fn inverse(&self, mut pixels: Vec<u32>, width: usize, height: usize) -> Vec<u32> {
+        match self {
+            Transform::Predictor { bits, modes } => {
+                unpredict(&mut pixels, width, height, *bits, modes);
+                pixels
+            }
+            Transform::Color { bits, multipliers } => {
+                let blocks_wide = subsample_size(width, *bits);
+                for (y, row) in pixels.chunks_exact_mut(width).enumerate() {
+                    let blocks = &multipliers[(y >> bits) * blocks_wide..];
+                    for (x, pixel) in row.iter_mut().enumerate() {
+                        *pixel = uncorrelate(*pixel, blocks[x >> bits]);
+                    }
+                }
+                pixels
+            }
+            Transform::SubtractGreen => {
+                for pixel in &mut pixels {
+                    let green = (*pixel >> 8) & 0xFF;
+                    *pixel = add_pixels(*pixel, green << 16 | green);
+                }
+                pixels
+            }
+            Transform::ColorIndexing { bits, palette } => {
+                let packed_width = subsample_size(width, *bits);
+                let per_pixel = 1 << bits;
+                let index_bits = 8 >> bits;
+                let mask = (1 << index_bits) - 1;
+
+                let mut out = Vec::with_capacity(width * height);
+                for row in pixels.chunks_exact(packed_width) {
+                    for x in 0..width {
+                        let packed = row[x >> bits] >> 8;
+                        let shift = (x & (per_pixel - 1)) * index_bits;
+                        let index = (packed >> shift & mask) as usize;
+                        // Out of range indices are transparent black.
+                        out.push(palette.get(index).copied().unwrap_or(0));
+                    }
+                }
+                out
+            }
+        }
+    }
+}
+
+///
+/// Add two ARGB pixels channel by channel, modulo 256.
+///
+pub(crate) fn add_pixels(a: u32, b: u32) -> u32 {
+    let alpha_green = (a & 0xFF00FF00).wrapping_add(b & 0xFF00FF00);
+    let red_blue = (a & 0x00FF00FF).wrapping_add(b & 0x00FF00FF);
+    (alpha_green & 0xFF00FF00) | (red_blue & 0x00FF00FF)
+}
+
+///
+/// Mean of each channel of two pixels, rounded down.
+///
+fn average2(a: u32, b: u32) -> u32 {
+    (((a ^ b) & 0xFEFEFEFE) >> 1) + (a & b)
+}
+
+fn channels(pixel: u32) -> [i32; 4] {
+    pixel.to_be_bytes().map(|channel| channel as i32)
+}
+
+fn from_channels(channels: [i32; 4]) -> u32 {
+    u32::from_be_bytes(channels.map(|channel| channel.clamp(0, 255) as u8))
+}
+
+///
+/// Whichever of `left` and `top` is closer to the gradient
+/// estimate `left + top - top_left`.
+///
+fn select(left: u32, top: u32, top_left: u32) -> u32 {
+    let (l, t, tl) = (channels(left), channels(top), channels(top_left));
+    let mut to_left = 0;
+    let mut to_top = 0;
+    for c in 0..4 {
+        let estimate = l[c] + t[c] - tl[c];
+        to_left += (estimate - l[c]).abs();
+        to_top += (estimate - t[c]).abs();
+    }
+    if to_left < to_top {
+        left
+    } else {
+        top
+    }
+}
+
+fn clamp_add_subtract_full(a: u32, b: u32, c: u32) -> u32 {
+    let (a, b, c) = (channels(a), channels(b), channels(c));
+    from_channels(std::array::from_fn(|i| a[i] + b[i] - c[i]))
+}
+
+fn clamp_add_subtract_half(a: u32, b: u32) -> u32 {
+    let (a, b) = (channels(a), channels(b));
+    from_channels(std::array::from_fn(|i| a[i] + (a[i] - b[i]) / 2))
+}
+
+fn predict(mode: u32, left: u32, top: u32, top_left: u32, top_right: u32) -> u32 {
+    match mode {
+        1 => left,
+        2 => top,
+        3 => top_right,
+        4 => top_left,
+        5 => average2(average2(left, top_right), top),
+        6 => average2(left, top_left),
+        7 => average2(left, top),
+        8 => average2(top_left, top),
+        9 => average2(top, top_right),
+        10 => average2(average2(left, top_left), average2(top, top_right)),
+        11 => select(left, top, top_left),
+        12 => clamp_add_subtract_full(left, top, top_left),
+        13 => clamp_add_subtract_half(average2(left, top), top_left),
+        // 0, and the unused 14 and 15 as libwebp treats them.
+        _ => 0xFF000000,
+    }
+}
+
+fn unpredict(pixels: &mut [u32], width: usize, height: usize, bits: u32, modes: &[u32]) {
+    let blocks_wide = subsample_size(width, bits);
+    for y in 0..height {
+        for x in 0..width {
+            let i = y * width + x;
+            let prediction = match (x, y) {
+                (0, 0) => 0xFF000000,
+                (_, 0) => pixels[i - 1],
+                (0, _) => pixels[i - width],
+                _ => {
+                    let mode = modes[(y >> bits) * blocks_wide + (x >> bits)] >> 8 & 0x0F;
+                    // For the last column this is the first pixel of
+                    // the current row, as the format specifies.
+                    let top_right = pixels[i - width + 1];
+                    predict(
+                        mode,
+                        pixels[i - 1],
+                        pixels[i - width],
+                        pixels[i - width - 1],
+                        top_right,
+                    )
+                }
+            };
+            pixels[i] = add_pixels(pixels[i], prediction);
+        }
+    }
+}
+
+///
+/// Scale a channel by a signed 3.5 fixed point multiplier.
+///
+fn color_delta(multiplier: u8, channel: u8) -> u8 {
+    ((multiplier as i8 as i32 * channel as i8 as i32) >> 5) as u8
+}
+
+fn uncorrelate(pixel: u32, multipliers: u32) -> u32 {
+    let [alpha, red, green, blue] = pixel.to_be_bytes();
+    let [_, red_to_blue, green_to_blue, green_to_red] = multipliers.to_be_bytes();
+    let red = red.wrapping_add(color_delta(green_to_red, green));
+    let blue = blue
+        .wrapping_add(color_delta(green_to_blue, green))
+        .wrapping_add(color_delta(red_to_blue, red));
+    u32::from_be_bytes([alpha, red, green, blue])
+}
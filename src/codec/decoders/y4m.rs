@@ -0,0 +1,311 @@
+use log::debug;
+
+use std::io::{self, Read};
+
+use super::Decoder;
+use crate::codec::yuv::{self, YuvPlanes};
+use crate::color::{ColorRange, ColorSpace};
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+pub(crate) const MAGIC: &str = "YUV4MPEG2";
+pub(crate) const FRAME: &str = "FRAME";
+
+/// Longest stream or frame header accepted.
+const MAX_HEADER: usize = 4096;
+
+///
+/// Layout of the planes of each frame, the `C` stream parameter.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chroma {
+    /// Half horizontal and vertical chroma resolution. Streams that
+    /// site chroma differently (`420mpeg2`, `420paldv`) are read as
+    /// if it were centred, as in `420jpeg`.
+    Yuv420,
+    /// Half horizontal chroma resolution.
+    Yuv422,
+    /// Full resolution chroma.
+    Yuv444,
+    /// Full resolution chroma followed by an alpha plane.
+    Yuva444,
+    /// Luma only.
+    Mono,
+}
+
+impl Chroma {
+    pub(crate) fn parse(value: &str) -> Result<Self, Error> {
+        match value {
+            "420jpeg" | "420" | "420mpeg2" | "420paldv" => Ok(Chroma::Yuv420),
+            "422" => Ok(Chroma::Yuv422),
+            "444" => Ok(Chroma::Yuv444),
+            "444alpha" => Ok(Chroma::Yuva444),
+            "mono" => Ok(Chroma::Mono),
+            _ => Err(Error::NotImplemented(format!("Y4M colourspace {value}"))),
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Chroma::Yuv420 => "420jpeg",
+            Chroma::Yuv422 => "422",
+            Chroma::Yuv444 => "444",
+            Chroma::Yuva444 => "444alpha",
+            Chroma::Mono => "mono",
+        }
+    }
+
+    ///
+    /// How many luma samples share a chroma sample (horizontal, vertical).
+    ///
+    pub(crate) fn factors(&self) -> (usize, usize) {
+        match self {
+            Chroma::Yuv420 => (2, 2),
+            Chroma::Yuv422 => (2, 1),
+            Chroma::Yuv444 | Chroma::Yuva444 | Chroma::Mono => (1, 1),
+        }
+    }
+
+    pub(crate) fn colorspace(&self) -> ColorSpace {
+        match self {
+            Chroma::Yuva444 => ColorSpace::RGBA,
+            Chroma::Mono => ColorSpace::GRAY,
+            _ => ColorSpace::RGB,
+        }
+    }
+}
+
+///
+/// `Decoder` for YUV4MPEG2 (`.y4m`) streams, returning the first frame.
+///
+/// Frames decode to `ColorSpace::RGB`, or `RGBA` and `GRAY` for
+/// `444alpha` and `mono` streams. Use `frames` to read them all.
+///
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Y4mDecoder;
+
+impl Decoder for Y4mDecoder {
+    fn decode(&self, data: &[u8]) -> Result<Image, Error> {
+        decode_bytes(data)
+    }
+}
+
+pub fn decode<R: Read>(reader: &mut R) -> Result<Image, Error> {
+    match frames(reader)?.next() {
+        Some(frame) => frame,
+        None => Err(Error::decode("Y4M contains no frames")),
+    }
+}
+
+pub fn decode_bytes(data: &[u8]) -> Result<Image, Error> {
+    decode(&mut &data[..])
+}
+
+///
+/// Read the stream header and iterate over the frames as they arrive,
+/// so video can be piped in from another program.
+///
+/// # Arguments
+///
+/// * `reader` - The stream, e.g. `std::io::stdin().lock()`. Headers
+///   are read a byte at a time, so wrap unbuffered sources such as a
+///   `File` in a `BufReader`.
+///
+pub fn frames<R: Read>(reader: R) -> Result<Frames<R>, Error> {
+    Frames::new(reader)
+}
+
+///
+/// Iterator over the frames of a Y4M stream, see `frames`.
+///
+#[derive(Debug)]
+pub struct Frames<R> {
+    reader: R,
+    width: usize,
+    height: usize,
+    frame_rate: (u32, u32),
+    chroma: Chroma,
+    color_range: ColorRange,
+    done: bool,
+}
+
+impl<R: Read> Frames<R> {
+    fn new(mut reader: R) -> Result<Self, Error> {
+        let header = read_line(&mut reader)?.ok_or_else(|| Error::decode("Empty Y4M stream"))?;
+        let mut params = header.split(' ');
+        if params.next() != Some(MAGIC) {
+            return Err(Error::decode("Invalid Y4M signature"));
+        }
+
+        let (mut width, mut height) = (0usize, 0usize);
+        let mut frame_rate = (0, 0);
+        let mut chroma = Chroma::Yuv420;
+        let mut color_range = ColorRange::Limited;
+        for param in params.filter(|param| !param.is_empty()) {
+            let mut chars = param.chars();
+            let tag = chars.next();
+            let value = chars.as_str();
+            let invalid = || Error::decode(format!("Invalid Y4M parameter \"{param}\""));
+            match tag {
+                Some('W') => width = value.parse().map_err(|_| invalid())?,
+                Some('H') => height = value.parse().map_err(|_| invalid())?,
+                Some('F') => {
+                    let (numerator, denominator) = value.split_once(':').ok_or_else(invalid)?;
+                    frame_rate = (
+                        numerator.parse().map_err(|_| invalid())?,
+                        denominator.parse().map_err(|_| invalid())?,
+                    );
+                }
+                Some('C') => chroma = Chroma::parse(value)?,
+                // Written by FFmpeg; other extensions are ignored.
+                Some('X') => match value {
+                    "COLORRANGE=FULL" => color_range = ColorRange::Full,
+                    "COLORRANGE=LIMITED" => color_range = ColorRange::Limited,
+                    _ => {}
+                },
+                // Interlacing, pixel aspect ratio and comments.
+                _ => {}
+            }
+        }
+        let pixels = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(4));
+        if width == 0 || height == 0 || pixels.is_none() {
+            return Err(Error::decode("Invalid Y4M dimensions"));
+        }
+        debug!("Y4M {width}x{height} {chroma:?} at {frame_rate:?}");
+
+        Ok(Frames {
+            reader,
+            width,
+            height,
+            frame_rate,
+            chroma,
+            color_range,
+            done: false,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    ///
+    /// Frames per second as a fraction, `(0, 0)` when unknown.
+    ///
+    pub fn frame_rate(&self) -> (u32, u32) {
+        self.frame_rate
+    }
+
+    pub fn chroma(&self) -> Chroma {
+        self.chroma
+    }
+
+    ///
+    /// From the `XCOLORRANGE` extension, otherwise `Limited`.
+    ///
+    pub fn color_range(&self) -> ColorRange {
+        self.color_range
+    }
+
+    fn plane(&mut self, size: usize) -> Result<Vec<u8>, Error> {
+        // Grows with the data read, whatever size the header claims.
+        let mut plane = Vec::new();
+        (&mut self.reader)
+            .take(size as u64)
+            .read_to_end(&mut plane)?;
+        if plane.len() < size {
+            return Err(Error::decode("Truncated Y4M frame"));
+        }
+        Ok(plane)
+    }
+
+    fn next_frame(&mut self) -> Result<Option<Image>, Error> {
+        let Some(header) = read_line(&mut self.reader)? else {
+            return Ok(None);
+        };
+        if !header.starts_with(FRAME) {
+            return Err(Error::decode("Invalid Y4M frame header"));
+        }
+
+        let (width, height) = (self.width, self.height);
+        let range = self.color_range;
+        let luma = self.plane(width * height)?;
+        if self.chroma == Chroma::Mono {
+            let data = luma.iter().map(|&y| range.luma_to_gray(y)).collect();
+            let shape = Shape::new(width, height, Some(1));
+            return Ok(Some(Image::from_data(data, shape, ColorSpace::GRAY)));
+        }
+
+        let factors = self.chroma.factors();
+        let (chroma_width, chroma_height) = yuv::chroma_size((width, height), factors);
+        let planes = YuvPlanes {
+            luma,
+            cb: self.plane(chroma_width * chroma_height)?,
+            cr: self.plane(chroma_width * chroma_height)?,
+        };
+        let alpha = match self.chroma {
+            Chroma::Yuva444 => Some(self.plane(width * height)?),
+            _ => None,
+        };
+
+        let colorspace = self.chroma.colorspace();
+        let mut data = Vec::with_capacity(width * height * colorspace.channels());
+        let rgb = yuv::to_rgb(&planes, (width, height), factors, range);
+        for (i, pixel) in rgb.iter().enumerate() {
+            data.extend_from_slice(pixel);
+            if let Some(alpha) = &alpha {
+                data.push(alpha[i]);
+            }
+        }
+        let shape = Shape::new(width, height, Some(colorspace.channels()));
+        Ok(Some(Image::from_data(data, shape, colorspace)))
+    }
+}
+
+impl<R: Read> Iterator for Frames<R> {
+    type Item = Result<Image, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let frame = self.next_frame().transpose();
+        if !matches!(frame, Some(Ok(_))) {
+            // Stop at the end of the stream or the first error.
+            self.done = true;
+        }
+        frame
+    }
+}
+
+///
+/// Read a header line without its newline.
+///
+/// # Returns
+///
+/// * `None` when the stream ends before the line starts
+///
+fn read_line<R: Read>(reader: &mut R) -> Result<Option<String>, Error> {
+    let mut line = Vec::new();
+    let mut byte = [0u8];
+    loop {
+        match reader.read(&mut byte) {
+            Ok(0) if line.is_empty() => return Ok(None),
+            Ok(0) => return Err(Error::decode("Truncated Y4M header")),
+            Ok(_) if byte[0] == b'\n' => break,
+            Ok(_) if line.len() == MAX_HEADER => {
+                return Err(Error::decode("Y4M header is too long"));
+            }
+            Ok(_) => line.push(byte[0]),
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
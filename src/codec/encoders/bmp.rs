@@ -0,0 +1,103 @@
+use std::io;
+
+use crate::codec::quantize;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::image::Image;
+
+const FILE_HEADER_SIZE: usize = 14;
+const INFO_HEADER_SIZE: usize = 40;
+const V4_HEADER_SIZE: usize = 108;
+
+///
+/// Encode an image as a Windows bitmap.
+///
+/// Images without alpha are written as bottom-up 24-bit BGR.
+/// Images with alpha are written as 32-bit BGRA with a
+/// `BITMAPV4HEADER`, so that readers know the fourth byte is alpha.
+///
+pub fn encode(image: &Image) -> Result<Vec<u8>, Error> {
+    let colorspace = image.colorspace();
+    let shape = image.shape();
+    if shape.ndim != colorspace.channels() {
+        return Err(Error::ColorSpaceError(format!(
+            "Shape has {} channels but {:?} requires {}",
+            shape.ndim,
+            colorspace,
+            colorspace.channels()
+        )));
+    }
+
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 || width > i32::MAX as usize || height > i32::MAX as usize {
+        return Err(Error::ImageEncodeError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("BMP cannot store a {width}x{height} image"),
+        )));
+    }
+
+    let alpha = matches!(
+        colorspace,
+        ColorSpace::RGBA | ColorSpace::BGRA | ColorSpace::GRAYA
+    );
+    let (bytes_per_pixel, header_size) = if alpha {
+        (4, V4_HEADER_SIZE)
+    } else {
+        (3, INFO_HEADER_SIZE)
+    };
+    let stride = (width * bytes_per_pixel).div_ceil(4) * 4;
+    let image_size = stride * height;
+    let pixel_offset = FILE_HEADER_SIZE + header_size;
+    let file_size = pixel_offset + image_size;
+    if file_size > u32::MAX as usize {
+        return Err(Error::ImageEncodeError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("A {width}x{height} image exceeds the 4 GiB BMP limit"),
+        )));
+    }
+
+    let mut out = Vec::with_capacity(file_size);
+
+    // BITMAPFILEHEADER
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&(file_size as u32).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(pixel_offset as u32).to_le_bytes());
+
+    // BITMAPINFOHEADER, the start of every later header version.
+    out.extend_from_slice(&(header_size as u32).to_le_bytes());
+    out.extend_from_slice(&(width as i32).to_le_bytes());
+    out.extend_from_slice(&(height as i32).to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&(bytes_per_pixel as u16 * 8).to_le_bytes());
+    // BI_BITFIELDS for 32-bit, BI_RGB for 24-bit.
+    out.extend_from_slice(&(if alpha { 3u32 } else { 0 }).to_le_bytes());
+    out.extend_from_slice(&(image_size as u32).to_le_bytes());
+    // 2835 pixels per metre is 72 DPI.
+    out.extend_from_slice(&2835u32.to_le_bytes());
+    out.extend_from_slice(&2835u32.to_le_bytes());
+    out.extend_from_slice(&[0; 8]);
+
+    if alpha {
+        for mask in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000] {
+            out.extend_from_slice(&mask.to_le_bytes());
+        }
+        out.extend_from_slice(b"BGRs");
+        // Endpoints and gamma, unused with the sRGB colour space.
+        out.extend_from_slice(&[0; 48]);
+    }
+
+    let pixels = quantize::rgba_pixels(image);
+    let padding = stride - width * bytes_per_pixel;
+    for row in pixels.chunks_exact(width).rev() {
+        for &[r, g, b, a] in row {
+            out.extend_from_slice(&[b, g, r]);
+            if alpha {
+                out.push(a);
+            }
+        }
+        out.extend(std::iter::repeat_n(0, padding));
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+
+use crate::codec::lzw::MAX_CODES;
+
+///
+/// Packs variable width codes from the least significant bit.
+///
+struct CodeWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16, size: u32) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.out.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push(self.buffer as u8);
+        }
+        self.out
+    }
+}
+
+///
+/// Compress colour indices into GIF image data, before it is
+/// split into sub-blocks.
+///
+/// Every index must be below `1 << min_code_size`. The code table
+/// is cleared as soon as it fills up, as giflib does.
+///
+pub(crate) fn compress(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    let mut writer = CodeWriter {
+        out: Vec::with_capacity(indices.len() / 2),
+        buffer: 0,
+        bits: 0,
+    };
+    let mut size = min_code_size as u32 + 1;
+    let mut next = end + 1;
+    // Maps a known string's code plus one more index to its own code.
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+
+    writer.write(clear, size);
+
+    let Some((&first, rest)) = indices.split_first() else {
+        writer.write(end, size);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+
+    for &index in rest {
+        if let Some(&code) = table.get(&(prefix, index)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix, size);
+        if (next as usize) < MAX_CODES {
+            table.insert((prefix, index), next);
+            // The decoder adds this entry one code later, and widens
+            // its codes once the entry fills the current width.
+            if next == 1 << size {
+                size += 1;
+            }
+            next += 1;
+        } else {
+            writer.write(clear, size);
+            table.clear();
+            size = min_code_size as u32 + 1;
+            next = end + 1;
+        }
+        prefix = index as u16;
+    }
+
+    writer.write(prefix, size);
+    writer.write(end, size);
+    writer.finish()
+}
@@ -0,0 +1,258 @@
+mod lzw;
+mod options;
+
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use crate::codec::decoders::gif::{
+    Frame, APPLICATION, EXTENSION, GRAPHIC_CONTROL, IMAGE_DESCRIPTOR, TRAILER,
+};
+use crate::codec::quantize::{self, Rgba};
+use crate::error::Error;
+use crate::image::Image;
+
+pub use options::{Dithering, EncodeOptions};
+
+/// Pixels with less alpha than this are written as transparent.
+const ALPHA_THRESHOLD: u8 = 128;
+
+///
+/// A frame reduced to a palette of at most 256 colours.
+///
+struct IndexedFrame {
+    palette: Vec<Rgba>,
+    indices: Vec<u8>,
+    /// The index after the colours, present when any pixel is transparent.
+    transparent: Option<u8>,
+    delay: Duration,
+}
+
+impl IndexedFrame {
+    ///
+    /// Exponent `n` of the colour table size `2^(n+1)`, as stored
+    /// in the packed fields of the screen and image descriptors.
+    ///
+    fn table_bits(&self) -> u8 {
+        let entries = self.palette.len() + self.transparent.is_some() as usize;
+        (0..7).find(|&n| entries <= 2 << n).unwrap_or(7)
+    }
+
+    fn write_table(&self, out: &mut Vec<u8>) {
+        out.extend(self.palette.iter().flat_map(|color| &color[..3]));
+        let written = self.palette.len();
+        out.resize(out.len() + ((2 << self.table_bits()) - written) * 3, 0);
+    }
+}
+
+pub fn encode(image: &Image) -> Result<Vec<u8>, Error> {
+    encode_with_options(image, &EncodeOptions::default())
+}
+
+///
+/// Encode a single image as a GIF89a file.
+///
+/// Images with more than 256 colours are quantised. Pixels with
+/// alpha below 128 become transparent, others are made opaque.
+///
+pub fn encode_with_options(image: &Image, options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    write(&[(image, Duration::ZERO)], options)
+}
+
+///
+/// Encode a sequence of images of the same size as an animated GIF.
+///
+/// Every frame covers the whole screen with its own palette and is
+/// shown for its `delay`, rounded to hundredths of a second.
+///
+pub fn encode_animation(frames: &[Frame], options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let frames: Vec<_> = frames
+        .iter()
+        .map(|frame| (&frame.image, frame.delay))
+        .collect();
+    write(&frames, options)
+}
+
+fn invalid_input(message: String) -> Error {
+    Error::ImageEncodeError(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+fn write(frames: &[(&Image, Duration)], options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let Some(&(first, _)) = frames.first() else {
+        return Err(invalid_input("A GIF needs at least one frame".to_string()));
+    };
+    let (width, height) = (first.width(), first.height());
+    if width == 0 || height == 0 || width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(invalid_input(format!(
+            "GIF cannot store a {width}x{height} image"
+        )));
+    }
+
+    let mut indexed = Vec::with_capacity(frames.len());
+    for &(image, delay) in frames {
+        let colorspace = image.colorspace();
+        let shape = image.shape();
+        if shape.ndim != colorspace.channels() {
+            return Err(Error::ColorSpaceError(format!(
+                "Shape has {} channels but {:?} requires {}",
+                shape.ndim,
+                colorspace,
+                colorspace.channels()
+            )));
+        }
+        if (image.width(), image.height()) != (width, height) {
+            return Err(invalid_input(format!(
+                "Frame of {}x{} in a {width}x{height} animation",
+                image.width(),
+                image.height()
+            )));
+        }
+        indexed.push(index_frame(image, delay, options.dithering));
+    }
+
+    let mut out = b"GIF89a".to_vec();
+    out.extend_from_slice(&(width as u16).to_le_bytes());
+    out.extend_from_slice(&(height as u16).to_le_bytes());
+    // The first palette is global, with 8 bits of colour resolution.
+    out.extend_from_slice(&[0xF0 | indexed[0].table_bits(), 0, 0]);
+    indexed[0].write_table(&mut out);
+
+    if let (true, Some(count)) = (indexed.len() > 1, options.loop_count) {
+        out.extend_from_slice(&[EXTENSION, APPLICATION, 11]);
+        out.extend_from_slice(b"NETSCAPE2.0");
+        out.extend_from_slice(&[3, 1]);
+        out.extend_from_slice(&count.to_le_bytes());
+        out.push(0);
+    }
+
+    for (i, frame) in indexed.iter().enumerate() {
+        // Frames are drawn over the last one, so clear it first when
+        // the next frame has transparent pixels to show through.
+        let clear = indexed
+            .get(i + 1)
+            .is_some_and(|next| next.transparent.is_some());
+        let delay = ((frame.delay.as_millis() + 5) / 10).min(u16::MAX as u128) as u16;
+        if indexed.len() > 1 || frame.transparent.is_some() || delay > 0 {
+            let disposal = if clear { 2 } else { 1 };
+            out.extend_from_slice(&[
+                EXTENSION,
+                GRAPHIC_CONTROL,
+                4,
+                disposal << 2 | frame.transparent.is_some() as u8,
+            ]);
+            out.extend_from_slice(&delay.to_le_bytes());
+            out.extend_from_slice(&[frame.transparent.unwrap_or(0), 0]);
+        }
+
+        out.push(IMAGE_DESCRIPTOR);
+        out.extend_from_slice(&[0, 0, 0, 0]);
+        out.extend_from_slice(&(width as u16).to_le_bytes());
+        out.extend_from_slice(&(height as u16).to_le_bytes());
+        if i == 0 {
+            out.push(0);
+        } else {
+            out.push(0x80 | frame.table_bits());
+            frame.write_table(&mut out);
+        }
+
+        let min_code_size = (frame.table_bits() + 1).max(2);
+        out.push(min_code_size);
+        for block in lzw::compress(&frame.indices, min_code_size).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+    }
+
+    out.push(TRAILER);
+    Ok(out)
+}
+
+///
+/// Quantise the opaque pixels of `image` to at most 256 colours,
+/// keeping one entry back for transparency when it is needed.
+///
+fn index_frame(image: &Image, delay: Duration, dithering: Dithering) -> IndexedFrame {
+    let pixels: Vec<Option<Rgba>> = quantize::rgba_pixels(image)
+        .into_iter()
+        .map(|[r, g, b, a]| (a >= ALPHA_THRESHOLD).then_some([r, g, b, 255]))
+        .collect();
+    let opaque: Vec<Rgba> = pixels.iter().flatten().copied().collect();
+    let has_transparency = opaque.len() < pixels.len();
+
+    let quantized = quantize::quantize(&opaque, 256 - has_transparency as usize);
+    let transparent = has_transparency.then_some(quantized.palette.len() as u8);
+    let palette = quantized.palette;
+
+    let indices = match dithering {
+        Dithering::None => {
+            let mut colors = quantized.indices.into_iter();
+            pixels
+                .iter()
+                .map(|pixel| match pixel {
+                    Some(_) => colors.next().unwrap_or(0),
+                    None => transparent.unwrap_or(0),
+                })
+                .collect()
+        }
+        Dithering::FloydSteinberg => {
+            floyd_steinberg(&pixels, image.width(), &palette, transparent.unwrap_or(0))
+        }
+    };
+
+    IndexedFrame {
+        palette,
+        indices,
+        transparent,
+        delay,
+    }
+}
+
+///
+/// Map pixels to `palette`, diffusing each one's error to the right
+/// (7/16) and to the row below (3/16, 5/16 and 1/16). Transparent
+/// pixels (`None`) take `transparent` and neither give nor receive error.
+///
+fn floyd_steinberg(
+    pixels: &[Option<Rgba>],
+    width: usize,
+    palette: &[Rgba],
+    transparent: u8,
+) -> Vec<u8> {
+    // Errors in sixteenths, padded by one pixel on either side.
+    let mut current = vec![[0i32; 3]; width + 2];
+    let mut below = vec![[0i32; 3]; width + 2];
+    let mut lookup: HashMap<Rgba, u8> = HashMap::new();
+    let mut indices = Vec::with_capacity(pixels.len());
+
+    for row in pixels.chunks_exact(width) {
+        for (x, pixel) in row.iter().enumerate() {
+            let Some(pixel) = pixel else {
+                indices.push(transparent);
+                continue;
+            };
+
+            let mut target = [0, 0, 0, 255u8];
+            for channel in 0..3 {
+                let value = pixel[channel] as i32 + (current[x + 1][channel] + 8).div_euclid(16);
+                target[channel] = value.clamp(0, 255) as u8;
+            }
+            let index = *lookup
+                .entry(target)
+                .or_insert_with(|| quantize::nearest(palette, &target));
+            indices.push(index);
+
+            let chosen = palette.get(index as usize).copied().unwrap_or(target);
+            for channel in 0..3 {
+                let error = target[channel] as i32 - chosen[channel] as i32;
+                current[x + 2][channel] += error * 7;
+                below[x][channel] += error * 3;
+                below[x + 1][channel] += error * 5;
+                below[x + 2][channel] += error;
+            }
+        }
+        std::mem::swap(&mut current, &mut below);
+        below.fill([0; 3]);
+    }
+    indices
+}
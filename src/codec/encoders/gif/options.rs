@@ -0,0 +1,48 @@
+///
+/// How colours missing from a frame's palette are approximated.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dithering {
+    /// Map every pixel to its nearest palette entry. Smooth
+    /// gradients show bands, but flat areas compress best.
+    None,
+    /// Spread each pixel's error over its unvisited neighbours
+    /// with the Floyd-Steinberg weights.
+    FloydSteinberg,
+}
+
+///
+/// Settings for `encode_with_options` and `encode_animation`.
+///
+/// The default maps pixels to the nearest palette colour without
+/// dithering, and loops animations forever.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub dithering: Dithering,
+    /// Written as a `NETSCAPE2.0` extension for animations: `None`
+    /// plays once, `Some(0)` loops forever and `Some(n)` repeats
+    /// `n` times. Ignored for single frames.
+    pub loop_count: Option<u16>,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            dithering: Dithering::None,
+            loop_count: Some(0),
+        }
+    }
+}
+
+impl EncodeOptions {
+    ///
+    /// Photographic content, where dithering hides banding.
+    ///
+    pub fn dithered() -> Self {
+        EncodeOptions {
+            dithering: Dithering::FloydSteinberg,
+            ..EncodeOptions::default()
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::io;
+
+use crate::codec::decoders::hdr::{FORMAT, MAGIC, RLE_WIDTHS};
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::image::{FloatImage, Image};
+
+/// Longest run or literal in a run length encoded channel.
+const MAX_RUN: usize = 127;
+const MAX_LITERAL: usize = 128;
+/// Shorter runs are cheaper to write as part of a literal.
+const MIN_RUN: usize = 4;
+
+///
+/// Pack linear RGB into three 8-bit mantissas sharing the
+/// exponent of the brightest channel. Negative and NaN
+/// values are written as 0.
+///
+pub(crate) fn rgb_to_rgbe(rgb: [f32; 3]) -> [u8; 4] {
+    let max = rgb[0].max(rgb[1]).max(rgb[2]);
+    if max.is_nan() || max <= 1e-32 {
+        return [0; 4];
+    }
+
+    // The exponent that puts `max` in 0.5 - 1.0.
+    let mut exponent = max.log2().floor() as i32 + 1;
+    let mut scale = 2f32.powi(8 - exponent);
+    // `log2` can round up just below a power of two.
+    if max * scale < 128.0 {
+        exponent -= 1;
+        scale *= 2.0;
+    }
+    if (max * scale).round() >= 256.0 {
+        exponent += 1;
+        scale *= 0.5;
+    }
+    if exponent + 128 < 1 {
+        return [0; 4];
+    }
+
+    let [r, g, b] = rgb.map(|value| (value.max(0.0) * scale).round().min(255.0) as u8);
+    [r, g, b, (exponent + 128).min(255) as u8]
+}
+
+///
+/// Encode an image as Radiance RGBE (`.hdr`).
+///
+/// The samples are scaled to 0.0 - 1.0, so this stores no more than
+/// the 8-bit image does. Use `encode_float` for high dynamic range
+/// data.
+///
+pub fn encode(image: &Image) -> Result<Vec<u8>, Error> {
+    encode_float(&FloatImage::from_image(image))
+}
+
+///
+/// Encode a floating point image as Radiance RGBE (`.hdr`).
+///
+/// Greyscale is expanded to RGB and alpha is dropped. Scanlines of
+/// 8 to 32767 pixels are run length encoded, others are written flat.
+///
+/// # Arguments
+///
+/// * `image` - Linear radiance, where 1.0 is nominal white
+///
+pub fn encode_float(image: &FloatImage) -> Result<Vec<u8>, Error> {
+    let colorspace = image.colorspace();
+    let shape = image.shape();
+    if shape.ndim != colorspace.channels() {
+        return Err(Error::ColorSpaceError(format!(
+            "Shape has {} channels but {:?} requires {}",
+            shape.ndim,
+            colorspace,
+            colorspace.channels()
+        )));
+    }
+
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 {
+        return Err(Error::ImageEncodeError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("HDR cannot store a {width}x{height} image"),
+        )));
+    }
+
+    let pixels: Vec<[u8; 4]> = image
+        .slice(0, image.size())
+        .chunks_exact(colorspace.channels())
+        .map(|p| {
+            rgb_to_rgbe(match colorspace {
+                ColorSpace::RGB | ColorSpace::RGBA => [p[0], p[1], p[2]],
+                ColorSpace::BGR | ColorSpace::BGRA => [p[2], p[1], p[0]],
+                ColorSpace::GRAY | ColorSpace::GRAYA => [p[0]; 3],
+            })
+        })
+        .collect();
+
+    let mut out = Vec::with_capacity(64 + pixels.len() * 4);
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(format!("\nFORMAT={FORMAT}\n\n-Y {height} +X {width}\n").as_bytes());
+
+    for row in pixels.chunks_exact(width) {
+        if RLE_WIDTHS.contains(&width) {
+            out.extend_from_slice(&[2, 2, (width >> 8) as u8, width as u8]);
+            for channel in 0..4 {
+                let values: Vec<u8> = row.iter().map(|pixel| pixel[channel]).collect();
+                encode_channel(&values, &mut out);
+            }
+        } else {
+            row.iter().for_each(|pixel| out.extend_from_slice(pixel));
+        }
+    }
+    Ok(out)
+}
+
+///
+/// Run length encode one channel of a scanline: a count above 128
+/// repeats the following byte `count - 128` times, otherwise `count`
+/// literal bytes follow.
+///
+fn encode_channel(values: &[u8], out: &mut Vec<u8>) {
+    let mut literal_start = 0;
+    let mut x = 0;
+    while x < values.len() {
+        let run = values[x..]
+            .iter()
+            .take(MAX_RUN)
+            .take_while(|&&value| value == values[x])
+            .count();
+        if run < MIN_RUN {
+            x += run;
+            continue;
+        }
+
+        write_literals(&values[literal_start..x], out);
+        out.extend_from_slice(&[128 + run as u8, values[x]]);
+        x += run;
+        literal_start = x;
+    }
+    write_literals(&values[literal_start..], out);
+}
+
+fn write_literals(values: &[u8], out: &mut Vec<u8>) {
+    for chunk in values.chunks(MAX_LITERAL) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::OnceLock;
+
+///
+/// cos((2x + 1)uπ / 16) scaled by C(u) / 2, indexed as [x][u].
+///
+fn cosines() -> &'static [[f32; 8]; 8] {
+    static TABLE: OnceLock<[[f32; 8]; 8]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [[0.0; 8]; 8];
+        for (x, row) in table.iter_mut().enumerate() {
+            for (u, value) in row.iter_mut().enumerate() {
+                let scale = if u == 0 {
+                    std::f32::consts::FRAC_1_SQRT_2
+                } else {
+                    1.0
+                };
+                let angle = (2 * x + 1) as f32 * u as f32 * std::f32::consts::PI / 16.0;
+                *value = scale * angle.cos() / 2.0;
+            }
+        }
+        table
+    })
+}
+
+///
+/// Forward DCT of an 8x8 block of level shifted samples,
+/// returning coefficients in natural (row major) order.
+///
+pub(crate) fn fdct_block(samples: &[f32; 64]) -> [f32; 64] {
+    let table = cosines();
+
+    // Rows first: tmp[y][u] = sum_x C[x][u] * f[y][x]
+    let mut tmp = [[0f32; 8]; 8];
+    for (y, row) in tmp.iter_mut().enumerate() {
+        for (u, value) in row.iter_mut().enumerate() {
+            *value = (0..8).map(|x| table[x][u] * samples[y * 8 + x]).sum();
+        }
+    }
+
+    // Then columns: F[v][u] = sum_y C[y][v] * tmp[y][u]
+    let mut out = [0f32; 64];
+    for v in 0..8 {
+        for u in 0..8 {
+            out[v * 8 + u] = (0..8).map(|y| table[y][v] * tmp[y][u]).sum();
+        }
+    }
+    out
+}
@@ -0,0 +1,187 @@
+// Typical tables for 8-bit luminance and chrominance, section K.3 of T.81.
+
+const LUMA_DC_COUNTS: [u8; 16] = [0, 1, 5, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0, 0, 0];
+const CHROMA_DC_COUNTS: [u8; 16] = [0, 3, 1, 1, 1, 1, 1, 1, 1, 1, 1, 0, 0, 0, 0, 0];
+const DC_VALUES: [u8; 12] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+const LUMA_AC_COUNTS: [u8; 16] = [0, 2, 1, 3, 3, 2, 4, 3, 5, 5, 4, 4, 0, 0, 1, 0x7D];
+const LUMA_AC_VALUES: [u8; 162] = [
+    0x01, 0x02, 0x03, 0x00, 0x04, 0x11, 0x05, 0x12, 0x21, 0x31, 0x41, 0x06, 0x13, 0x51, 0x61, 0x07,
+    0x22, 0x71, 0x14, 0x32, 0x81, 0x91, 0xA1, 0x08, 0x23, 0x42, 0xB1, 0xC1, 0x15, 0x52, 0xD1, 0xF0,
+    0x24, 0x33, 0x62, 0x72, 0x82, 0x09, 0x0A, 0x16, 0x17, 0x18, 0x19, 0x1A, 0x25, 0x26, 0x27, 0x28,
+    0x29, 0x2A, 0x34, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48, 0x49,
+    0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68, 0x69,
+    0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x83, 0x84, 0x85, 0x86, 0x87, 0x88, 0x89,
+    0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5, 0xA6, 0xA7,
+    0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3, 0xC4, 0xC5,
+    0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA, 0xE1, 0xE2,
+    0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF1, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+    0xF9, 0xFA,
+];
+
+const CHROMA_AC_COUNTS: [u8; 16] = [0, 2, 1, 2, 4, 4, 3, 4, 7, 5, 4, 4, 0, 1, 2, 0x77];
+const CHROMA_AC_VALUES: [u8; 162] = [
+    0x00, 0x01, 0x02, 0x03, 0x11, 0x04, 0x05, 0x21, 0x31, 0x06, 0x12, 0x41, 0x51, 0x07, 0x61, 0x71,
+    0x13, 0x22, 0x32, 0x81, 0x08, 0x14, 0x42, 0x91, 0xA1, 0xB1, 0xC1, 0x09, 0x23, 0x33, 0x52, 0xF0,
+    0x15, 0x62, 0x72, 0xD1, 0x0A, 0x16, 0x24, 0x34, 0xE1, 0x25, 0xF1, 0x17, 0x18, 0x19, 0x1A, 0x26,
+    0x27, 0x28, 0x29, 0x2A, 0x35, 0x36, 0x37, 0x38, 0x39, 0x3A, 0x43, 0x44, 0x45, 0x46, 0x47, 0x48,
+    0x49, 0x4A, 0x53, 0x54, 0x55, 0x56, 0x57, 0x58, 0x59, 0x5A, 0x63, 0x64, 0x65, 0x66, 0x67, 0x68,
+    0x69, 0x6A, 0x73, 0x74, 0x75, 0x76, 0x77, 0x78, 0x79, 0x7A, 0x82, 0x83, 0x84, 0x85, 0x86, 0x87,
+    0x88, 0x89, 0x8A, 0x92, 0x93, 0x94, 0x95, 0x96, 0x97, 0x98, 0x99, 0x9A, 0xA2, 0xA3, 0xA4, 0xA5,
+    0xA6, 0xA7, 0xA8, 0xA9, 0xAA, 0xB2, 0xB3, 0xB4, 0xB5, 0xB6, 0xB7, 0xB8, 0xB9, 0xBA, 0xC2, 0xC3,
+    0xC4, 0xC5, 0xC6, 0xC7, 0xC8, 0xC9, 0xCA, 0xD2, 0xD3, 0xD4, 0xD5, 0xD6, 0xD7, 0xD8, 0xD9, 0xDA,
+    0xE2, 0xE3, 0xE4, 0xE5, 0xE6, 0xE7, 0xE8, 0xE9, 0xEA, 0xF2, 0xF3, 0xF4, 0xF5, 0xF6, 0xF7, 0xF8,
+    0xF9, 0xFA,
+];
+
+///
+/// A Huffman table ready for writing: the `DHT` description
+/// (`counts` and `values`) and the code assigned to each symbol.
+///
+#[derive(Debug, Clone)]
+pub(crate) struct HuffmanCode {
+    pub counts: [u8; 16],
+    pub values: Vec<u8>,
+    /// (code, length) per symbol, length zero for unused symbols.
+    codes: [(u16, u8); 256],
+}
+
+impl HuffmanCode {
+    pub fn new(counts: [u8; 16], values: Vec<u8>) -> Self {
+        let mut codes = [(0, 0); 256];
+        let mut code = 0u16;
+        let mut index = 0;
+        for (length, &count) in counts.iter().enumerate() {
+            for _ in 0..count {
+                codes[values[index] as usize] = (code, length as u8 + 1);
+                code += 1;
+                index += 1;
+            }
+            code <<= 1;
+        }
+
+        HuffmanCode {
+            counts,
+            values,
+            codes,
+        }
+    }
+
+    pub fn luma_dc() -> Self {
+        HuffmanCode::new(LUMA_DC_COUNTS, DC_VALUES.to_vec())
+    }
+
+    pub fn chroma_dc() -> Self {
+        HuffmanCode::new(CHROMA_DC_COUNTS, DC_VALUES.to_vec())
+    }
+
+    pub fn luma_ac() -> Self {
+        HuffmanCode::new(LUMA_AC_COUNTS, LUMA_AC_VALUES.to_vec())
+    }
+
+    pub fn chroma_ac() -> Self {
+        HuffmanCode::new(CHROMA_AC_COUNTS, CHROMA_AC_VALUES.to_vec())
+    }
+
+    ///
+    /// Optimal table for the given symbol frequencies, with code
+    /// lengths limited to 16 bits (section K.2 of T.81).
+    ///
+    pub fn optimal(frequencies: &[u32; 256]) -> Self {
+        // One extra symbol with the lowest frequency reserves the
+        // all-ones code, which JPEG forbids.
+        let mut frequency: Vec<u64> = frequencies.iter().map(|&f| f as u64).collect();
+        frequency.push(1);
+        let mut code_size = [0usize; 257];
+        let mut others = [usize::MAX; 257];
+
+        // Repeatedly merge the two least frequent trees.
+        while let Some(first) = least_frequent(&frequency, None) {
+            let Some(second) = least_frequent(&frequency, Some(first)) else {
+                break;
+            };
+
+            frequency[first] += frequency[second];
+            frequency[second] = 0;
+
+            let mut node = first;
+            code_size[node] += 1;
+            while others[node] != usize::MAX {
+                node = others[node];
+                code_size[node] += 1;
+            }
+            others[node] = second;
+
+            let mut node = second;
+            code_size[node] += 1;
+            while others[node] != usize::MAX {
+                node = others[node];
+                code_size[node] += 1;
+            }
+        }
+
+        let mut bits = [0usize; 33];
+        for &size in code_size.iter().filter(|&&size| size > 0) {
+            bits[size.min(32)] += 1;
+        }
+
+        // Shorten codes longer than 16 bits, moving a pair of leaves
+        // up one level and a shorter leaf down.
+        for length in (17..=32).rev() {
+            while bits[length] > 0 {
+                let mut shorter = length - 2;
+                while bits[shorter] == 0 {
+                    shorter -= 1;
+                }
+                bits[length] -= 2;
+                bits[length - 1] += 1;
+                bits[shorter + 1] += 2;
+                bits[shorter] -= 1;
+            }
+        }
+
+        // Drop the reserved symbol, which has the longest code.
+        let mut longest = 16;
+        while bits[longest] == 0 {
+            longest -= 1;
+        }
+        bits[longest] -= 1;
+
+        let mut counts = [0u8; 16];
+        for (length, count) in counts.iter_mut().enumerate() {
+            *count = bits[length + 1] as u8;
+        }
+
+        let mut values = Vec::new();
+        for size in 1..=32 {
+            for (symbol, &symbol_size) in code_size[..256].iter().enumerate() {
+                if symbol_size == size {
+                    values.push(symbol as u8);
+                }
+            }
+        }
+
+        HuffmanCode::new(counts, values)
+    }
+
+    pub fn code(&self, symbol: u8) -> (u16, u8) {
+        self.codes[symbol as usize]
+    }
+}
+
+///
+/// Index of the smallest non-zero frequency, preferring the
+/// highest index on ties, as libjpeg does.
+///
+fn least_frequent(frequency: &[u64], exclude: Option<usize>) -> Option<usize> {
+    let mut best: Option<usize> = None;
+    for (index, &value) in frequency.iter().enumerate() {
+        if value == 0 || Some(index) == exclude {
+            continue;
+        }
+        if best.is_none_or(|best| value <= frequency[best]) {
+            best = Some(index);
+        }
+    }
+    best
+}
@@ -0,0 +1,385 @@
+mod dct;
+mod huffman;
+mod options;
+
+use std::io;
+
+use crate::codec::decoders::jpeg::ZIGZAG;
+use crate::codec::quantize;
+use crate::codec::yuv::sample_plane;
+use crate::color::{rgb_to_ycbcr, ColorSpace};
+use crate::error::Error;
+use crate::image::Image;
+
+use huffman::HuffmanCode;
+
+pub use options::{ChromaSubsampling, EncodeOptions};
+
+// Example quantization tables from section K.1 of T.81, natural order.
+
+const LUMA_QUANTIZATION: [u16; 64] = [
+    16, 11, 10, 16, 24, 40, 51, 61, 12, 12, 14, 19, 26, 58, 60, 55, 14, 13, 16, 24, 40, 57, 69, 56,
+    14, 17, 22, 29, 51, 87, 80, 62, 18, 22, 37, 56, 68, 109, 103, 77, 24, 35, 55, 64, 81, 104, 113,
+    92, 49, 64, 78, 87, 103, 121, 120, 101, 72, 92, 95, 98, 112, 100, 103, 99,
+];
+
+const CHROMA_QUANTIZATION: [u16; 64] = [
+    17, 18, 24, 47, 99, 99, 99, 99, 18, 21, 26, 66, 99, 99, 99, 99, 24, 26, 56, 99, 99, 99, 99, 99,
+    47, 66, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+    99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99, 99,
+];
+
+///
+/// Scale a base quantization table to `quality` (1 to 100)
+/// with the libjpeg formula.
+///
+fn quantization_table(base: &[u16; 64], quality: u8) -> [u16; 64] {
+    let quality = quality.clamp(1, 100) as u32;
+    let scale = if quality < 50 {
+        5000 / quality
+    } else {
+        200 - quality * 2
+    };
+    base.map(|value| ((value as u32 * scale + 50) / 100).clamp(1, 255) as u16)
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    /// Index of the quantization and Huffman tables (0 luma, 1 chroma).
+    table: usize,
+    blocks_w: usize,
+    /// Quantized coefficients in zigzag order, blocks in raster order.
+    blocks: Vec<[i16; 64]>,
+}
+
+///
+/// Appends bits most significant first, stuffing a zero byte after
+/// every 0xFF.
+///
+struct BitWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    count: u32,
+}
+
+impl BitWriter {
+    fn new() -> Self {
+        BitWriter {
+            out: Vec::new(),
+            buffer: 0,
+            count: 0,
+        }
+    }
+
+    fn write(&mut self, value: u16, bits: u8) {
+        let bits = bits as u32;
+        self.buffer = (self.buffer << bits) | (value as u32 & ((1 << bits) - 1));
+        self.count += bits;
+        while self.count >= 8 {
+            self.count -= 8;
+            let byte = (self.buffer >> self.count) as u8;
+            self.out.push(byte);
+            if byte == 0xFF {
+                self.out.push(0x00);
+            }
+        }
+    }
+
+    ///
+    /// Pad the final byte with one bits.
+    ///
+    fn finish(mut self) -> Vec<u8> {
+        if self.count > 0 {
+            let padding = 8 - self.count as u8;
+            self.write((1 << padding) - 1, padding);
+        }
+        self.out
+    }
+}
+
+///
+/// Number of bits needed for the magnitude of `value` (its category).
+///
+fn category(value: i32) -> u8 {
+    (32 - value.unsigned_abs().leading_zeros()) as u8
+}
+
+///
+/// Bits following a category symbol: negative values are
+/// sent as their one's complement (F.1.2.1 of T.81).
+///
+fn magnitude_bits(value: i32) -> u16 {
+    if value < 0 {
+        (value - 1) as u16
+    } else {
+        value as u16
+    }
+}
+
+///
+/// Produce the (symbol, extra bits) sequence of one block.
+/// `emit` receives whether the symbol uses the AC table.
+///
+fn block_symbols(block: &[i16; 64], prediction: &mut i32, mut emit: impl FnMut(bool, u8, u16, u8)) {
+    let difference = block[0] as i32 - *prediction;
+    *prediction = block[0] as i32;
+    let size = category(difference);
+    emit(false, size, magnitude_bits(difference), size);
+
+    let mut run = 0;
+    for &coefficient in &block[1..] {
+        if coefficient == 0 {
+            run += 1;
+            continue;
+        }
+        while run > 15 {
+            // ZRL
+            emit(true, 0xF0, 0, 0);
+            run -= 16;
+        }
+        let size = category(coefficient as i32);
+        emit(
+            true,
+            (run << 4) | size,
+            magnitude_bits(coefficient as i32),
+            size,
+        );
+        run = 0;
+    }
+    if run > 0 {
+        // EOB
+        emit(true, 0x00, 0, 0);
+    }
+}
+
+fn segment(out: &mut Vec<u8>, marker: u8, payload: &[u8]) {
+    out.extend_from_slice(&[0xFF, marker]);
+    out.extend_from_slice(&(payload.len() as u16 + 2).to_be_bytes());
+    out.extend_from_slice(payload);
+}
+
+pub fn encode(image: &Image) -> Result<Vec<u8>, Error> {
+    encode_with_options(image, &EncodeOptions::default())
+}
+
+///
+/// Encode an image as a baseline JFIF file.
+///
+/// Greyscale images are written with a single component, colour
+/// images as YCbCr. Alpha channels are dropped.
+///
+pub fn encode_with_options(image: &Image, options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let colorspace = image.colorspace();
+    let shape = image.shape();
+    if shape.ndim != colorspace.channels() {
+        return Err(Error::ColorSpaceError(format!(
+            "Shape has {} channels but {:?} requires {}",
+            shape.ndim,
+            colorspace,
+            colorspace.channels()
+        )));
+    }
+
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 || width > u16::MAX as usize || height > u16::MAX as usize {
+        return Err(Error::ImageEncodeError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("JPEG cannot store a {width}x{height} image"),
+        )));
+    }
+
+    // Full resolution planes and their sampling factors.
+    let grayscale = matches!(colorspace, ColorSpace::GRAY | ColorSpace::GRAYA);
+    let (planes, factors): (Vec<Vec<u8>>, Vec<(usize, usize)>) = if grayscale {
+        let channels = colorspace.channels();
+        let data = image.slice(0, image.size());
+        let luma = data.iter().step_by(channels).copied().collect();
+        (vec![luma], vec![(1, 1)])
+    } else {
+        let pixels = quantize::rgba_pixels(image);
+        let mut planes: Vec<Vec<u8>> = (0..3).map(|_| Vec::with_capacity(pixels.len())).collect();
+        for [r, g, b, _] in pixels {
+            for (plane, value) in planes.iter_mut().zip(rgb_to_ycbcr(r, g, b)) {
+                plane.push(value);
+            }
+        }
+        (
+            planes,
+            vec![options.subsampling.luma_factors(), (1, 1), (1, 1)],
+        )
+    };
+
+    let (h_max, v_max) = factors[0];
+    let (mcus_x, mcus_y) = (width.div_ceil(8 * h_max), height.div_ceil(8 * v_max));
+    let quantization = [
+        quantization_table(&LUMA_QUANTIZATION, options.quality),
+        quantization_table(&CHROMA_QUANTIZATION, options.quality),
+    ];
+
+    let mut components = Vec::with_capacity(planes.len());
+    for (index, (plane, &(h, v))) in planes.iter().zip(&factors).enumerate() {
+        let (blocks_w, blocks_h) = (mcus_x * h, mcus_y * v);
+        let samples = sample_plane(
+            plane,
+            (width, height),
+            (h_max / h, v_max / v),
+            (blocks_w * 8, blocks_h * 8),
+        );
+
+        let table = index.min(1);
+        let mut blocks = Vec::with_capacity(blocks_w * blocks_h);
+        let mut block = [0f32; 64];
+        for block_y in 0..blocks_h {
+            for block_x in 0..blocks_w {
+                for y in 0..8 {
+                    let row = (block_y * 8 + y) * blocks_w * 8 + block_x * 8;
+                    for x in 0..8 {
+                        block[y * 8 + x] = samples[row + x] - 128.0;
+                    }
+                }
+
+                let coefficients = dct::fdct_block(&block);
+                let mut quantized = [0i16; 64];
+                for (k, &position) in ZIGZAG.iter().enumerate() {
+                    let step = quantization[table][position] as f32;
+                    quantized[k] = (coefficients[position] / step).round() as i16;
+                }
+                blocks.push(quantized);
+            }
+        }
+
+        components.push(Component {
+            id: index as u8 + 1,
+            h,
+            v,
+            table,
+            blocks_w,
+            blocks,
+        });
+    }
+
+    // Walk the blocks in MCU order, as they are written to the scan.
+    let for_each_block = |f: &mut dyn FnMut(&Component, &[i16; 64], usize)| {
+        for mcu_y in 0..mcus_y {
+            for mcu_x in 0..mcus_x {
+                for (index, component) in components.iter().enumerate() {
+                    for y in 0..component.v {
+                        for x in 0..component.h {
+                            let block_y = mcu_y * component.v + y;
+                            let block_x = mcu_x * component.h + x;
+                            let block = &component.blocks[block_y * component.blocks_w + block_x];
+                            f(component, block, index);
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    let table_count = if grayscale { 1 } else { 2 };
+    let (dc_tables, ac_tables) = if options.optimize_huffman {
+        let mut dc_frequencies = [[0u32; 256]; 2];
+        let mut ac_frequencies = [[0u32; 256]; 2];
+        let mut predictions = vec![0; components.len()];
+        for_each_block(&mut |component, block, index| {
+            block_symbols(block, &mut predictions[index], |ac, symbol, _, _| {
+                let frequencies = if ac {
+                    &mut ac_frequencies
+                } else {
+                    &mut dc_frequencies
+                };
+                frequencies[component.table][symbol as usize] += 1;
+            });
+        });
+        // Greyscale images never use the chroma tables.
+        let optimal = |frequencies: &[u32; 256], unused: fn() -> HuffmanCode| {
+            if frequencies.iter().any(|&count| count > 0) {
+                HuffmanCode::optimal(frequencies)
+            } else {
+                unused()
+            }
+        };
+        (
+            [
+                optimal(&dc_frequencies[0], HuffmanCode::luma_dc),
+                optimal(&dc_frequencies[1], HuffmanCode::chroma_dc),
+            ],
+            [
+                optimal(&ac_frequencies[0], HuffmanCode::luma_ac),
+                optimal(&ac_frequencies[1], HuffmanCode::chroma_ac),
+            ],
+        )
+    } else {
+        (
+            [HuffmanCode::luma_dc(), HuffmanCode::chroma_dc()],
+            [HuffmanCode::luma_ac(), HuffmanCode::chroma_ac()],
+        )
+    };
+
+    let mut writer = BitWriter::new();
+    let mut predictions = vec![0; components.len()];
+    for_each_block(&mut |component, block, index| {
+        let (dc_table, ac_table) = (&dc_tables[component.table], &ac_tables[component.table]);
+        block_symbols(block, &mut predictions[index], |ac, symbol, bits, size| {
+            let (code, length) = if ac {
+                ac_table.code(symbol)
+            } else {
+                dc_table.code(symbol)
+            };
+            writer.write(code, length);
+            writer.write(bits, size);
+        });
+    });
+    let scan = writer.finish();
+
+    let mut out = vec![0xFF, 0xD8];
+    // JFIF 1.01, no units, 1:1 aspect ratio, no thumbnail
+    segment(
+        &mut out,
+        0xE0,
+        &[b'J', b'F', b'I', b'F', 0, 1, 1, 0, 0, 1, 0, 1, 0, 0],
+    );
+
+    for (index, table) in quantization.iter().take(table_count).enumerate() {
+        let mut payload = vec![index as u8];
+        payload.extend(ZIGZAG.iter().map(|&position| table[position] as u8));
+        segment(&mut out, 0xDB, &payload);
+    }
+
+    let mut frame = vec![8];
+    frame.extend_from_slice(&(height as u16).to_be_bytes());
+    frame.extend_from_slice(&(width as u16).to_be_bytes());
+    frame.push(components.len() as u8);
+    for component in &components {
+        frame.extend_from_slice(&[
+            component.id,
+            ((component.h as u8) << 4) | component.v as u8,
+            component.table as u8,
+        ]);
+    }
+    segment(&mut out, 0xC0, &frame);
+
+    for index in 0..table_count {
+        for (class, table) in [(0u8, &dc_tables[index]), (1, &ac_tables[index])] {
+            let mut payload = vec![(class << 4) | index as u8];
+            payload.extend_from_slice(&table.counts);
+            payload.extend_from_slice(&table.values);
+            segment(&mut out, 0xC4, &payload);
+        }
+    }
+
+    let mut header = vec![components.len() as u8];
+    for component in &components {
+        let table = component.table as u8;
+        header.extend_from_slice(&[component.id, (table << 4) | table]);
+    }
+    // Full spectral range, no successive approximation
+    header.extend_from_slice(&[0, 63, 0]);
+    segment(&mut out, 0xDA, &header);
+
+    out.extend_from_slice(&scan);
+    out.extend_from_slice(&[0xFF, 0xD9]);
+    Ok(out)
+}
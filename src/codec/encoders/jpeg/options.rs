@@ -0,0 +1,73 @@
+///
+/// Resolution of the Cb and Cr planes relative to luma.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChromaSubsampling {
+    /// Full resolution chroma.
+    Yuv444,
+    /// Half horizontal resolution.
+    Yuv422,
+    /// Half horizontal and vertical resolution.
+    Yuv420,
+}
+
+impl ChromaSubsampling {
+    ///
+    /// Luma sampling factors (horizontal, vertical); chroma is always 1x1.
+    ///
+    pub(crate) fn luma_factors(&self) -> (usize, usize) {
+        match self {
+            ChromaSubsampling::Yuv444 => (1, 1),
+            ChromaSubsampling::Yuv422 => (2, 1),
+            ChromaSubsampling::Yuv420 => (2, 2),
+        }
+    }
+}
+
+///
+/// Settings for `encode_with_options`.
+///
+/// The default matches libjpeg's `cjpeg`: quality 75 with 4:2:0
+/// subsampling and the standard Huffman tables.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// Between 1 (smallest) and 100 (best), scaling the
+    /// quantization tables as libjpeg does.
+    pub quality: u8,
+    /// Ignored for greyscale images.
+    pub subsampling: ChromaSubsampling,
+    /// Build Huffman tables from the image's own statistics. Saves
+    /// a few percent of the file size for a second pass over the data.
+    pub optimize_huffman: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            quality: 75,
+            subsampling: ChromaSubsampling::Yuv420,
+            optimize_huffman: false,
+        }
+    }
+}
+
+impl EncodeOptions {
+    pub fn with_quality(quality: u8) -> Self {
+        EncodeOptions {
+            quality,
+            ..EncodeOptions::default()
+        }
+    }
+
+    ///
+    /// Small previews: modest quality with optimised Huffman tables.
+    ///
+    pub fn preview() -> Self {
+        EncodeOptions {
+            quality: 60,
+            subsampling: ChromaSubsampling::Yuv420,
+            optimize_huffman: true,
+        }
+    }
+}
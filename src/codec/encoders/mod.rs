@@ -1,5 +1,12 @@
-mod jpeg;
-mod png;
+pub mod bmp;
+pub mod gif;
+pub mod hdr;
+pub mod jpeg;
+pub mod netpbm;
+pub mod png;
+pub mod qoi;
+pub mod tiff;
+pub mod y4m;
 
 use super::Codex;
 use crate::image::Image;
@@ -12,7 +19,17 @@ impl Encoder for Image {
     fn encode(&self, codec: Codex) -> Result<Vec<u8>, crate::error::Error> {
         match codec {
             Codex::PNG => png::encode(self),
-            Codex::JPG => todo!(),
+            Codex::JPG => jpeg::encode(self),
+            Codex::BMP => bmp::encode(self),
+            Codex::PBM | Codex::PGM | Codex::PPM | Codex::PAM => netpbm::encode(self, codec),
+            Codex::QOI => qoi::encode(self),
+            Codex::GIF => gif::encode(self),
+            Codex::TIFF => tiff::encode(self),
+            Codex::HDR => hdr::encode(self),
+            Codex::Y4M => y4m::encode(self),
+            Codex::WEBP => Err(crate::error::Error::NotImplemented(
+                "WebP encoding".to_string(),
+            )),
         }
     }
 }
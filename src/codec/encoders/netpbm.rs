@@ -0,0 +1,206 @@
+use std::io;
+
+use crate::codec::quantize;
+use crate::codec::Codex;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::image::Image;
+
+/// Plain (ASCII) rasters are wrapped to this many characters per line.
+const LINE_LENGTH: usize = 70;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// The compact "raw" form, `P4` to `P7`.
+    Binary,
+    /// The human readable "plain" form, `P1` to `P3`.
+    /// PAM has no ASCII form.
+    Ascii,
+}
+
+///
+/// Settings for `encode_with_options`.
+///
+/// The default writes binary files with a `maxval` of 255.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub encoding: Encoding,
+    /// Largest sample value, 255 for 8-bit and 65535 for 16-bit
+    /// output. Samples are rescaled from 0..=255. Ignored for PBM.
+    pub maxval: u16,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            encoding: Encoding::Binary,
+            maxval: 255,
+        }
+    }
+}
+
+impl EncodeOptions {
+    ///
+    /// Plain files at 8 bits, convenient for inspecting by eye.
+    ///
+    pub fn ascii() -> Self {
+        EncodeOptions {
+            encoding: Encoding::Ascii,
+            ..EncodeOptions::default()
+        }
+    }
+
+    ///
+    /// Binary files with 16-bit samples.
+    ///
+    pub fn sixteen_bit() -> Self {
+        EncodeOptions {
+            maxval: u16::MAX,
+            ..EncodeOptions::default()
+        }
+    }
+}
+
+pub fn encode(image: &Image, codec: Codex) -> Result<Vec<u8>, Error> {
+    encode_with_options(image, codec, &EncodeOptions::default())
+}
+
+///
+/// Encode an image as PBM, PGM, PPM or PAM.
+///
+/// PGM and PBM store luminance, with PBM thresholding at 50%.
+/// PPM stores RGB. Alpha is dropped by every format except PAM,
+/// which keeps the image's own channels.
+///
+pub fn encode_with_options(
+    image: &Image,
+    codec: Codex,
+    options: &EncodeOptions,
+) -> Result<Vec<u8>, Error> {
+    let colorspace = image.colorspace();
+    let shape = image.shape();
+    if shape.ndim != colorspace.channels() {
+        return Err(Error::ColorSpaceError(format!(
+            "Shape has {} channels but {:?} requires {}",
+            shape.ndim,
+            colorspace,
+            colorspace.channels()
+        )));
+    }
+
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 {
+        return Err(Error::ImageEncodeError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Netpbm cannot store a {width}x{height} image"),
+        )));
+    }
+
+    let ascii = options.encoding == Encoding::Ascii;
+    let maxval = options.maxval.max(1);
+    let pixels = quantize::rgba_pixels(image);
+    let luma = |&[r, g, b, _]: &[u8; 4]| {
+        ((299 * r as u32 + 587 * g as u32 + 114 * b as u32 + 500) / 1000) as u8
+    };
+
+    let mut out = Vec::new();
+    let samples: Vec<u8> = match codec {
+        Codex::PBM => {
+            let magic = if ascii { "P1" } else { "P4" };
+            out.extend_from_slice(format!("{magic}\n{width} {height}\n").as_bytes());
+            // 1 is black.
+            let bits: Vec<u8> = pixels.iter().map(|p| (luma(p) < 128) as u8).collect();
+            if ascii {
+                write_plain_bits(&mut out, &bits);
+            } else {
+                for row in bits.chunks_exact(width) {
+                    out.extend(row.chunks(8).map(|byte| {
+                        byte.iter()
+                            .enumerate()
+                            .fold(0u8, |acc, (i, &bit)| acc | bit << (7 - i))
+                    }));
+                }
+            }
+            return Ok(out);
+        }
+        Codex::PGM => {
+            let magic = if ascii { "P2" } else { "P5" };
+            out.extend_from_slice(format!("{magic}\n{width} {height}\n{maxval}\n").as_bytes());
+            pixels.iter().map(luma).collect()
+        }
+        Codex::PPM => {
+            let magic = if ascii { "P3" } else { "P6" };
+            out.extend_from_slice(format!("{magic}\n{width} {height}\n{maxval}\n").as_bytes());
+            pixels.iter().flat_map(|&[r, g, b, _]| [r, g, b]).collect()
+        }
+        Codex::PAM => {
+            if ascii {
+                return Err(Error::UnsupportedFormat(
+                    "PAM has no ASCII encoding".to_string(),
+                ));
+            }
+            let (depth, tuple_type, samples): (usize, &str, Vec<u8>) = match colorspace {
+                ColorSpace::GRAY => (1, "GRAYSCALE", pixels.iter().map(luma).collect()),
+                ColorSpace::GRAYA => (
+                    2,
+                    "GRAYSCALE_ALPHA",
+                    pixels.iter().flat_map(|p| [luma(p), p[3]]).collect(),
+                ),
+                ColorSpace::RGB | ColorSpace::BGR => (
+                    3,
+                    "RGB",
+                    pixels.iter().flat_map(|&[r, g, b, _]| [r, g, b]).collect(),
+                ),
+                ColorSpace::RGBA | ColorSpace::BGRA => (4, "RGB_ALPHA", pixels.concat()),
+            };
+            out.extend_from_slice(
+                format!(
+                    "P7\nWIDTH {width}\nHEIGHT {height}\nDEPTH {depth}\nMAXVAL {maxval}\nTUPLTYPE {tuple_type}\nENDHDR\n"
+                )
+                .as_bytes(),
+            );
+            samples
+        }
+        other => {
+            return Err(Error::UnsupportedFormat(format!(
+                "{other:?} is not a Netpbm format"
+            )));
+        }
+    };
+
+    let scale = |value: u8| ((value as u32 * maxval as u32 + 127) / 255) as u16;
+    if ascii {
+        write_plain_samples(&mut out, samples.iter().map(|&value| scale(value)));
+    } else if maxval > 255 {
+        out.extend(samples.iter().flat_map(|&value| scale(value).to_be_bytes()));
+    } else {
+        out.extend(samples.iter().map(|&value| scale(value) as u8));
+    }
+
+    Ok(out)
+}
+
+fn write_plain_bits(out: &mut Vec<u8>, bits: &[u8]) {
+    for line in bits.chunks(LINE_LENGTH) {
+        out.extend(line.iter().map(|&bit| b'0' + bit));
+        out.push(b'\n');
+    }
+}
+
+fn write_plain_samples(out: &mut Vec<u8>, samples: impl Iterator<Item = u16>) {
+    let mut line_length = 0;
+    for value in samples {
+        let text = value.to_string();
+        if line_length > 0 && line_length + 1 + text.len() > LINE_LENGTH {
+            out.push(b'\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            out.push(b' ');
+            line_length += 1;
+        }
+        out.extend_from_slice(text.as_bytes());
+        line_length += text.len();
+    }
+    out.push(b'\n');
+}
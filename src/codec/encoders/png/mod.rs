@@ -0,0 +1,258 @@
+mod options;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+
+use crate::codec::decoders::png::{filter_row, FilterType};
+use crate::codec::quantize;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::image::Image;
+use std::io::{self, Write};
+
+pub use options::{BitDepth, ColorMode, EncodeOptions, FilterStrategy};
+
+///
+/// The IHDR colour type matching a `ColorSpace`.
+/// BGR layouts are written as RGB after swizzling.
+///
+fn color_type(colorspace: ColorSpace) -> u8 {
+    match colorspace {
+        ColorSpace::GRAY => 0,
+        ColorSpace::RGB | ColorSpace::BGR => 2,
+        ColorSpace::GRAYA => 4,
+        ColorSpace::RGBA | ColorSpace::BGRA => 6,
+    }
+}
+
+///
+/// Copy row `y` of the image into `out` in PNG channel order.
+///
+fn scanline(image: &Image, y: usize, out: &mut Vec<u8>) {
+    let channels = image.colorspace().channels();
+    let row_size = image.width() * channels;
+    let row = image.slice(y * row_size, (y + 1) * row_size);
+
+    match image.colorspace() {
+        ColorSpace::BGR | ColorSpace::BGRA => {
+            for pixel in row.chunks_exact(channels) {
+                out.extend_from_slice(&[pixel[2], pixel[1], pixel[0]]);
+                out.extend_from_slice(&pixel[3..]);
+            }
+        }
+        _ => out.extend_from_slice(row),
+    }
+}
+
+pub fn encode(image: &Image) -> Result<Vec<u8>, crate::error::Error> {
+    encode_with_options(image, &EncodeOptions::default())
+}
+
+pub fn encode_with_options(image: &Image, options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let colorspace = image.colorspace();
+    let shape = image.shape();
+    if shape.ndim != colorspace.channels() {
+        return Err(Error::ColorSpaceError(format!(
+            "Shape has {} channels but {:?} requires {}",
+            shape.ndim,
+            colorspace,
+            colorspace.channels()
+        )));
+    }
+
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 {
+        return Err(Error::ImageEncodeError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("PNG cannot store a {width}x{height} image"),
+        )));
+    }
+
+    if options.color_mode == ColorMode::Indexed {
+        return encode_indexed(image, options);
+    }
+
+    let bit_depth = match options.bit_depth {
+        BitDepth::Eight => 8,
+        BitDepth::Sixteen => 16,
+    };
+    let bpp = colorspace.channels() * bit_depth / 8;
+
+    let mut rows = Vec::with_capacity(image.height());
+    for y in 0..image.height() {
+        // Take one row of all channels.
+        let mut row = Vec::with_capacity(image.width() * bpp);
+        scanline(image, y, &mut row);
+
+        if options.bit_depth == BitDepth::Sixteen {
+            row = row
+                .iter()
+                .flat_map(|&value| (value as u16 * 257).to_be_bytes())
+                .collect();
+        }
+        rows.push(row);
+    }
+
+    let mut png_data = start_png(image, bit_depth as u8, color_type(colorspace));
+    png_data.extend(create_chunk(b"IDAT", &compress(&rows, bpp, options)?));
+    png_data.extend(create_chunk(b"IEND", &[]));
+
+    Ok(png_data)
+}
+
+///
+/// Write the image as colour type 3 with a `PLTE` chunk, and a
+/// `tRNS` chunk when any palette entry is not fully opaque.
+///
+fn encode_indexed(image: &Image, options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let quantized = quantize::quantize(&quantize::rgba_pixels(image), 256);
+
+    // Translucent entries go first so `tRNS` can stop at the last of them.
+    let mut order: Vec<usize> = (0..quantized.palette.len()).collect();
+    order.sort_by_key(|&index| quantized.palette[index][3] == 255);
+    let mut remap = vec![0u8; order.len()];
+    for (new, &old) in order.iter().enumerate() {
+        remap[old] = new as u8;
+    }
+    let palette: Vec<[u8; 4]> = order
+        .iter()
+        .map(|&index| quantized.palette[index])
+        .collect();
+
+    let bit_depth = match palette.len() {
+        0..=2 => 1,
+        3..=4 => 2,
+        5..=16 => 4,
+        _ => 8,
+    };
+
+    let rows: Vec<Vec<u8>> = quantized
+        .indices
+        .chunks_exact(image.width())
+        .map(|indices| {
+            let mut row = vec![0u8; (indices.len() * bit_depth).div_ceil(8)];
+            for (i, &index) in indices.iter().enumerate() {
+                let bit = i * bit_depth;
+                row[bit / 8] |= remap[index as usize] << (8 - bit_depth - bit % 8);
+            }
+            row
+        })
+        .collect();
+
+    let plte: Vec<u8> = palette
+        .iter()
+        .flat_map(|color| &color[..3])
+        .copied()
+        .collect();
+    let trns: Vec<u8> = palette
+        .iter()
+        .map(|color| color[3])
+        .take_while(|&alpha| alpha != 255)
+        .collect();
+
+    // Filtering rarely helps palette images, so rows are stored unfiltered.
+    let options = EncodeOptions {
+        filter: FilterStrategy::Fixed(FilterType::None),
+        ..*options
+    };
+
+    let mut png_data = start_png(image, bit_depth as u8, 3);
+    png_data.extend(create_chunk(b"PLTE", &plte));
+    if !trns.is_empty() {
+        png_data.extend(create_chunk(b"tRNS", &trns));
+    }
+    png_data.extend(create_chunk(b"IDAT", &compress(&rows, 1, &options)?));
+    png_data.extend(create_chunk(b"IEND", &[]));
+
+    Ok(png_data)
+}
+
+fn create_chunk(chunk_type: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::new();
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(chunk_type);
+    chunk.extend_from_slice(data);
+    let checksum = crc32fast::hash(&chunk[4..]);
+    chunk.extend_from_slice(&checksum.to_be_bytes());
+    chunk
+}
+
+///
+/// The PNG signature followed by the IHDR chunk.
+///
+fn start_png(image: &Image, bit_depth: u8, color_type: u8) -> Vec<u8> {
+    let png_signature = b"\x89PNG\r\n\x1a\n";
+    let mut png_data = Vec::new();
+    png_data.extend_from_slice(png_signature);
+
+    let ihdr = [
+        (image.width() >> 24) as u8,
+        (image.width() >> 16) as u8,
+        (image.width() >> 8) as u8,
+        image.width() as u8,
+        (image.height() >> 24) as u8,
+        (image.height() >> 16) as u8,
+        (image.height() >> 8) as u8,
+        image.height() as u8,
+        bit_depth,  // Bit depth
+        color_type, // Color type
+        0,          // Compresison method
+        0,          // Filter method
+        0,          // Interlace method
+    ];
+    png_data.extend(create_chunk(b"IHDR", &ihdr));
+    png_data
+}
+
+///
+/// Filter every scanline according to `options.filter`
+/// and zlib compress the result.
+///
+fn compress(rows: &[Vec<u8>], bpp: usize, options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let stride = rows.first().map_or(0, |row| row.len());
+    let mut raw_data = Vec::with_capacity((1 + stride) * rows.len());
+    let mut previous = vec![0; stride];
+    let mut candidate = Vec::with_capacity(stride + 1);
+
+    for row in rows {
+        match options.filter {
+            FilterStrategy::Fixed(filter) => {
+                filter_row(filter, bpp, &previous, row, &mut raw_data);
+            }
+            FilterStrategy::Adaptive => {
+                let mut best = Vec::new();
+                let mut best_score = u64::MAX;
+                for filter in FILTERS {
+                    candidate.clear();
+                    filter_row(filter, bpp, &previous, row, &mut candidate);
+
+                    // Treat each byte as signed, so small negative residuals score low.
+                    let score: u64 = candidate[1..]
+                        .iter()
+                        .map(|&value| (value as i8).unsigned_abs() as u64)
+                        .sum();
+                    if score < best_score {
+                        best_score = score;
+                        std::mem::swap(&mut best, &mut candidate);
+                    }
+                }
+                raw_data.extend_from_slice(&best);
+            }
+        }
+        previous.copy_from_slice(row);
+    }
+
+    let level = Compression::new(options.compression_level.min(9));
+    let mut encoder = ZlibEncoder::new(Vec::new(), level);
+    encoder.write_all(&raw_data)?;
+
+    Ok(encoder.finish()?)
+}
+
+const FILTERS: [FilterType; 5] = [
+    FilterType::None,
+    FilterType::Sub,
+    FilterType::Up,
+    FilterType::Average,
+    FilterType::Paeth,
+];
@@ -0,0 +1,93 @@
+use crate::codec::decoders::png::FilterType;
+
+///
+/// How the scanline filter is chosen for each row.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterStrategy {
+    /// Use the same filter for every row. `FilterType::None`
+    /// is the fastest, at the cost of larger files.
+    Fixed(FilterType),
+    /// Try every filter on each row and keep the one with the
+    /// minimum sum of absolute differences, as libpng does.
+    Adaptive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitDepth {
+    Eight,
+    /// Each 8-bit sample is widened to 16 bits (v * 257).
+    Sixteen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Write the image's own channels.
+    Truecolor,
+    /// Write a palette of up to 256 colours (with `tRNS` for alpha)
+    /// and pack the indices at the smallest bit depth that fits.
+    /// Images with more colours are quantised. `bit_depth` is ignored.
+    Indexed,
+}
+
+///
+/// Settings for `encode_with_options`.
+///
+/// The default matches libpng: adaptive filtering at zlib level 6
+/// with 8 bits per channel.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub filter: FilterStrategy,
+    /// zlib compression level between 0 (store) and 9 (smallest).
+    pub compression_level: u32,
+    pub bit_depth: BitDepth,
+    pub color_mode: ColorMode,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            filter: FilterStrategy::Adaptive,
+            compression_level: 6,
+            bit_depth: BitDepth::Eight,
+            color_mode: ColorMode::Truecolor,
+        }
+    }
+}
+
+impl EncodeOptions {
+    ///
+    /// Favour encode speed over file size.
+    ///
+    pub fn fast() -> Self {
+        EncodeOptions {
+            filter: FilterStrategy::Fixed(FilterType::None),
+            compression_level: 1,
+            bit_depth: BitDepth::Eight,
+            color_mode: ColorMode::Truecolor,
+        }
+    }
+
+    ///
+    /// Favour file size over encode speed.
+    ///
+    pub fn best() -> Self {
+        EncodeOptions {
+            filter: FilterStrategy::Adaptive,
+            compression_level: 9,
+            bit_depth: BitDepth::Eight,
+            color_mode: ColorMode::Truecolor,
+        }
+    }
+
+    ///
+    /// Small palette images, suited to thumbnails and UI assets.
+    ///
+    pub fn indexed() -> Self {
+        EncodeOptions {
+            color_mode: ColorMode::Indexed,
+            ..EncodeOptions::best()
+        }
+    }
+}
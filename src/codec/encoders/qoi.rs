@@ -0,0 +1,104 @@
+use std::io;
+
+use crate::codec::decoders::qoi::{
+    hash, END_MARKER, HEADER_SIZE, MAGIC, MAX_PIXELS, OP_DIFF, OP_INDEX, OP_LUMA, OP_RGB, OP_RGBA,
+    OP_RUN,
+};
+use crate::codec::quantize;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::image::Image;
+
+///
+/// Encode an image as QOI.
+///
+/// Images with alpha are written with four channels, everything
+/// else with three. Greyscale is expanded to RGB. The header
+/// marks the samples as sRGB with linear alpha.
+///
+pub fn encode(image: &Image) -> Result<Vec<u8>, Error> {
+    let colorspace = image.colorspace();
+    let shape = image.shape();
+    if shape.ndim != colorspace.channels() {
+        return Err(Error::ColorSpaceError(format!(
+            "Shape has {} channels but {:?} requires {}",
+            shape.ndim,
+            colorspace,
+            colorspace.channels()
+        )));
+    }
+
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 || width * height > MAX_PIXELS {
+        return Err(Error::ImageEncodeError(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("QOI cannot store a {width}x{height} image"),
+        )));
+    }
+
+    let channels: u8 = match colorspace {
+        ColorSpace::RGBA | ColorSpace::BGRA | ColorSpace::GRAYA => 4,
+        ColorSpace::RGB | ColorSpace::BGR | ColorSpace::GRAY => 3,
+    };
+
+    let pixels = quantize::rgba_pixels(image);
+    let mut out = Vec::with_capacity(HEADER_SIZE + pixels.len() * 2 + END_MARKER.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&(width as u32).to_be_bytes());
+    out.extend_from_slice(&(height as u32).to_be_bytes());
+    out.extend_from_slice(&[channels, 0]);
+
+    let mut index = [[0u8; 4]; 64];
+    let mut previous = [0, 0, 0, 255u8];
+    let mut run = 0u8;
+
+    for (i, &pixel) in pixels.iter().enumerate() {
+        if pixel == previous {
+            run += 1;
+            if run == 62 || i == pixels.len() - 1 {
+                out.push(OP_RUN | (run - 1));
+                run = 0;
+            }
+            continue;
+        }
+
+        if run > 0 {
+            out.push(OP_RUN | (run - 1));
+            run = 0;
+        }
+
+        let slot = hash(pixel);
+        if index[slot] == pixel {
+            out.push(OP_INDEX | slot as u8);
+        } else {
+            index[slot] = pixel;
+
+            if pixel[3] == previous[3] {
+                let [dr, dg, db] =
+                    [0, 1, 2].map(|channel| pixel[channel].wrapping_sub(previous[channel]) as i8);
+                let (dr_dg, db_dg) = (dr.wrapping_sub(dg), db.wrapping_sub(dg));
+
+                if (-2..=1).contains(&dr) && (-2..=1).contains(&dg) && (-2..=1).contains(&db) {
+                    out.push(
+                        OP_DIFF | ((dr + 2) as u8) << 4 | ((dg + 2) as u8) << 2 | (db + 2) as u8,
+                    );
+                } else if (-32..=31).contains(&dg)
+                    && (-8..=7).contains(&dr_dg)
+                    && (-8..=7).contains(&db_dg)
+                {
+                    out.push(OP_LUMA | (dg + 32) as u8);
+                    out.push(((dr_dg + 8) as u8) << 4 | (db_dg + 8) as u8);
+                } else {
+                    out.extend_from_slice(&[OP_RGB, pixel[0], pixel[1], pixel[2]]);
+                }
+            } else {
+                out.push(OP_RGBA);
+                out.extend_from_slice(&pixel);
+            }
+        }
+        previous = pixel;
+    }
+
+    out.extend_from_slice(&END_MARKER);
+    Ok(out)
+}
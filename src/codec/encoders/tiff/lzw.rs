@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use crate::codec::lzw::{CLEAR, END, MAX_CODES};
+
+///
+/// Packs codes from the most significant bit, tracking the
+/// current code width and the next free table entry.
+///
+struct CodeWriter {
+    out: Vec<u8>,
+    buffer: u32,
+    bits: u32,
+    size: u32,
+    next: u16,
+}
+
+impl CodeWriter {
+    fn write(&mut self, code: u16) {
+        self.buffer = self.buffer << self.size | code as u32;
+        self.bits += self.size;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.out.push((self.buffer >> self.bits) as u8);
+        }
+    }
+
+    ///
+    /// Account for the table entry made after a code. Widens one
+    /// code early, and clears the full table, at the same points as
+    /// libtiff so that every reader stays in step.
+    ///
+    /// # Returns
+    ///
+    /// * Whether the table was cleared
+    ///
+    fn add_entry(&mut self) -> bool {
+        self.next += 1;
+        if self.next as usize == MAX_CODES - 2 {
+            self.write(CLEAR);
+            self.size = 9;
+            self.next = END + 1;
+            return true;
+        }
+        if self.next > (1 << self.size) - 1 {
+            self.size += 1;
+        }
+        false
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push((self.buffer << (8 - self.bits)) as u8);
+        }
+        self.out
+    }
+}
+
+///
+/// Compress a strip or tile with TIFF LZW.
+///
+pub(crate) fn compress(data: &[u8]) -> Vec<u8> {
+    let mut writer = CodeWriter {
+        out: Vec::with_capacity(data.len() / 2),
+        buffer: 0,
+        bits: 0,
+        size: 9,
+        next: END + 1,
+    };
+    // Maps a known string's code plus one more byte to its own code.
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+
+    writer.write(CLEAR);
+
+    let Some((&first, rest)) = data.split_first() else {
+        writer.write(END);
+        return writer.finish();
+    };
+    let mut prefix = first as u16;
+
+    for &byte in rest {
+        if let Some(&code) = table.get(&(prefix, byte)) {
+            prefix = code;
+            continue;
+        }
+
+        writer.write(prefix);
+        table.insert((prefix, byte), writer.next);
+        if writer.add_entry() {
+            table.clear();
+        }
+        prefix = byte as u16;
+    }
+
+    writer.write(prefix);
+    // The decoder makes one more entry on reading the last code.
+    writer.add_entry();
+    writer.write(END);
+    writer.finish()
+}
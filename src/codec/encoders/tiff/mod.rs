@@ -0,0 +1,391 @@
+mod lzw;
+mod options;
+
+use flate2::write::ZlibEncoder;
+
+use std::io::{self, Write};
+
+use crate::codec::decoders::tiff::{
+    Compression, HORIZONTAL_PREDICTOR, PHOTOMETRIC_BLACK_IS_ZERO, PHOTOMETRIC_RGB,
+    TAG_BITS_PER_SAMPLE, TAG_COMPRESSION, TAG_EXTRA_SAMPLES, TAG_IMAGE_LENGTH, TAG_IMAGE_WIDTH,
+    TAG_PAGE_NUMBER, TAG_PHOTOMETRIC, TAG_PLANAR_CONFIGURATION, TAG_PREDICTOR, TAG_RESOLUTION_UNIT,
+    TAG_ROWS_PER_STRIP, TAG_SAMPLES_PER_PIXEL, TAG_STRIP_BYTE_COUNTS, TAG_STRIP_OFFSETS,
+    TAG_TILE_BYTE_COUNTS, TAG_TILE_LENGTH, TAG_TILE_OFFSETS, TAG_TILE_WIDTH, TAG_X_RESOLUTION,
+    TAG_Y_RESOLUTION, UNASSOCIATED_ALPHA,
+};
+use crate::codec::quantize;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::image::Image;
+
+pub use options::{BitDepth, EncodeOptions, Layout};
+
+/// Field types used by the writer.
+const SHORT: u16 = 3;
+const LONG: u16 = 4;
+const RATIONAL: u16 = 5;
+
+///
+/// An IFD entry to write, with its value already serialised
+/// in little-endian order.
+///
+struct Field {
+    tag: u16,
+    field_type: u16,
+    count: u32,
+    value: Vec<u8>,
+}
+
+impl Field {
+    fn shorts(tag: u16, values: &[u16]) -> Self {
+        Field {
+            tag,
+            field_type: SHORT,
+            count: values.len() as u32,
+            value: values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+        }
+    }
+
+    fn longs(tag: u16, values: &[u32]) -> Self {
+        Field {
+            tag,
+            field_type: LONG,
+            count: values.len() as u32,
+            value: values
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect(),
+        }
+    }
+
+    fn rational(tag: u16, numerator: u32, denominator: u32) -> Self {
+        let mut value = numerator.to_le_bytes().to_vec();
+        value.extend_from_slice(&denominator.to_le_bytes());
+        Field {
+            tag,
+            field_type: RATIONAL,
+            count: 1,
+            value,
+        }
+    }
+}
+
+pub fn encode(image: &Image) -> Result<Vec<u8>, Error> {
+    encode_with_options(image, &EncodeOptions::default())
+}
+
+///
+/// Encode an image as a single page little-endian TIFF.
+///
+/// Greyscale and RGB images are written with their own channels,
+/// BGR layouts as RGB, and alpha as an unassociated extra sample.
+///
+pub fn encode_with_options(image: &Image, options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    write(&[image], options)
+}
+
+///
+/// Encode images as the pages of one TIFF file, in order.
+/// Pages may differ in size and `ColorSpace`.
+///
+pub fn encode_pages(images: &[Image], options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let images: Vec<&Image> = images.iter().collect();
+    write(&images, options)
+}
+
+fn invalid_input(message: String) -> Error {
+    Error::ImageEncodeError(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+fn write(images: &[&Image], options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    if images.is_empty() {
+        return Err(invalid_input("A TIFF needs at least one page".to_string()));
+    }
+    match options.layout {
+        Layout::Strips { rows_per_strip: 0 } => {
+            return Err(invalid_input("Strips need at least one row".to_string()));
+        }
+        Layout::Tiles { width, height }
+            if width == 0 || height == 0 || width % 16 != 0 || height % 16 != 0 =>
+        {
+            return Err(invalid_input(format!(
+                "Tiles of {width}x{height} are not multiples of 16"
+            )));
+        }
+        _ => {}
+    }
+
+    let mut out = b"II*\0".to_vec();
+    // Where the offset of the next IFD goes.
+    let mut link = out.len();
+    out.extend_from_slice(&[0; 4]);
+
+    for (number, image) in images.iter().enumerate() {
+        let mut fields = write_page(image, options, &mut out)?;
+        if images.len() > 1 {
+            fields.push(Field::shorts(
+                TAG_PAGE_NUMBER,
+                &[number as u16, images.len() as u16],
+            ));
+        }
+
+        if out.len() % 2 == 1 {
+            out.push(0);
+        }
+        let offset = offset_of(&out, image)?;
+        out[link..link + 4].copy_from_slice(&offset.to_le_bytes());
+        link = write_ifd(&mut out, fields);
+    }
+    Ok(out)
+}
+
+///
+/// The current end of `out` as a TIFF offset, which is limited to 4GB.
+///
+fn offset_of(out: &[u8], image: &Image) -> Result<u32, Error> {
+    u32::try_from(out.len()).map_err(|_| {
+        invalid_input(format!(
+            "A {}x{} page does not fit in a TIFF file",
+            image.width(),
+            image.height()
+        ))
+    })
+}
+
+///
+/// Append the compressed strips or tiles of `image` to `out`.
+///
+/// # Returns
+///
+/// * The fields describing the page, except its page number
+///
+fn write_page(
+    image: &Image,
+    options: &EncodeOptions,
+    out: &mut Vec<u8>,
+) -> Result<Vec<Field>, Error> {
+    let colorspace = image.colorspace();
+    let shape = image.shape();
+    if shape.ndim != colorspace.channels() {
+        return Err(Error::ColorSpaceError(format!(
+            "Shape has {} channels but {:?} requires {}",
+            shape.ndim,
+            colorspace,
+            colorspace.channels()
+        )));
+    }
+    let (width, height) = (image.width(), image.height());
+    if width == 0 || height == 0 || width > u32::MAX as usize || height > u32::MAX as usize {
+        return Err(invalid_input(format!(
+            "TIFF cannot store a {width}x{height} image"
+        )));
+    }
+
+    // Which RGBA channels to write, in order.
+    let (photometric, channels): (u16, &[usize]) = match colorspace {
+        ColorSpace::GRAY => (PHOTOMETRIC_BLACK_IS_ZERO, &[0]),
+        ColorSpace::GRAYA => (PHOTOMETRIC_BLACK_IS_ZERO, &[0, 3]),
+        ColorSpace::RGB | ColorSpace::BGR => (PHOTOMETRIC_RGB, &[0, 1, 2]),
+        ColorSpace::RGBA | ColorSpace::BGRA => (PHOTOMETRIC_RGB, &[0, 1, 2, 3]),
+    };
+    let samples = channels.len();
+    let has_alpha = matches!(
+        colorspace,
+        ColorSpace::GRAYA | ColorSpace::RGBA | ColorSpace::BGRA
+    );
+
+    let (bits, widen): (u16, fn(u8) -> u16) = match options.bit_depth {
+        BitDepth::Eight => (8, |value| value as u16),
+        BitDepth::Sixteen => (16, |value| value as u16 * 257),
+    };
+    let predictor = matches!(options.compression, Compression::Lzw | Compression::Deflate);
+
+    let (chunk_width, chunk_height, tiled) = match options.layout {
+        Layout::Strips { rows_per_strip } => (width, rows_per_strip.min(height), false),
+        Layout::Tiles { width, height } => (width, height, true),
+    };
+    let across = width.div_ceil(chunk_width);
+    let down = height.div_ceil(chunk_height);
+
+    let pixels = quantize::rgba_pixels(image);
+    let mut offsets = Vec::with_capacity(across * down);
+    let mut byte_counts = Vec::with_capacity(across * down);
+    let mut row = vec![0u16; chunk_width * samples];
+
+    for chunk_row in 0..down {
+        for chunk_column in 0..across {
+            let (top, left) = (chunk_row * chunk_height, chunk_column * chunk_width);
+            // Tiles are padded with zeros past the edges of the image.
+            let rows = if tiled {
+                chunk_height
+            } else {
+                chunk_height.min(height - top)
+            };
+            let columns = chunk_width.min(width - left);
+
+            let mut chunk = Vec::with_capacity(rows * row.len() * bits as usize / 8);
+            for y in top..top + rows {
+                row.fill(0);
+                if y < height {
+                    for (x, pixel) in pixels[y * width + left..][..columns].iter().enumerate() {
+                        for (sample, &channel) in channels.iter().enumerate() {
+                            row[x * samples + sample] = widen(pixel[channel]);
+                        }
+                    }
+                }
+                if predictor {
+                    for i in (samples..row.len()).rev() {
+                        row[i] = row[i].wrapping_sub(row[i - samples]);
+                    }
+                }
+                match options.bit_depth {
+                    BitDepth::Eight => chunk.extend(row.iter().map(|&value| value as u8)),
+                    BitDepth::Sixteen => chunk.extend(row.iter().flat_map(|v| v.to_le_bytes())),
+                }
+            }
+
+            let compressed = compress(options.compression, &chunk, row.len() * bits as usize / 8)?;
+            // Word aligned, as TIFF 6.0 recommends.
+            if out.len() % 2 == 1 {
+                out.push(0);
+            }
+            offsets.push(offset_of(out, image)?);
+            byte_counts.push(compressed.len() as u32);
+            out.extend_from_slice(&compressed);
+        }
+    }
+
+    let mut fields = vec![
+        Field::longs(TAG_IMAGE_WIDTH, &[width as u32]),
+        Field::longs(TAG_IMAGE_LENGTH, &[height as u32]),
+        Field::shorts(TAG_BITS_PER_SAMPLE, &vec![bits; samples]),
+        Field::shorts(TAG_COMPRESSION, &[options.compression.tag()]),
+        Field::shorts(TAG_PHOTOMETRIC, &[photometric]),
+        Field::shorts(TAG_SAMPLES_PER_PIXEL, &[samples as u16]),
+        Field::rational(TAG_X_RESOLUTION, 72, 1),
+        Field::rational(TAG_Y_RESOLUTION, 72, 1),
+        Field::shorts(TAG_PLANAR_CONFIGURATION, &[1]),
+        // Inches.
+        Field::shorts(TAG_RESOLUTION_UNIT, &[2]),
+    ];
+    if tiled {
+        fields.extend([
+            Field::longs(TAG_TILE_WIDTH, &[chunk_width as u32]),
+            Field::longs(TAG_TILE_LENGTH, &[chunk_height as u32]),
+            Field::longs(TAG_TILE_OFFSETS, &offsets),
+            Field::longs(TAG_TILE_BYTE_COUNTS, &byte_counts),
+        ]);
+    } else {
+        fields.extend([
+            Field::longs(TAG_STRIP_OFFSETS, &offsets),
+            Field::longs(TAG_ROWS_PER_STRIP, &[chunk_height as u32]),
+            Field::longs(TAG_STRIP_BYTE_COUNTS, &byte_counts),
+        ]);
+    }
+    if predictor {
+        fields.push(Field::shorts(TAG_PREDICTOR, &[HORIZONTAL_PREDICTOR as u16]));
+    }
+    if has_alpha {
+        fields.push(Field::shorts(
+            TAG_EXTRA_SAMPLES,
+            &[UNASSOCIATED_ALPHA as u16],
+        ));
+    }
+    Ok(fields)
+}
+
+fn compress(compression: Compression, chunk: &[u8], row_bytes: usize) -> Result<Vec<u8>, Error> {
+    Ok(match compression {
+        Compression::None => chunk.to_vec(),
+        Compression::PackBits => {
+            // Rows are packed separately.
+            let mut out = Vec::with_capacity(chunk.len() + chunk.len() / 64);
+            for row in chunk.chunks(row_bytes) {
+                pack_bits(row, &mut out);
+            }
+            out
+        }
+        Compression::Lzw => lzw::compress(chunk),
+        Compression::Deflate => {
+            let mut encoder = ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(chunk)?;
+            encoder.finish()?
+        }
+    })
+}
+
+///
+/// PackBits: runs of two or more bytes as `1 - length` then the
+/// byte, everything else as `length - 1` then up to 128 literals.
+///
+fn pack_bits(row: &[u8], out: &mut Vec<u8>) {
+    let run_at = |i: usize| {
+        row[i..]
+            .iter()
+            .take(128)
+            .take_while(|&&byte| byte == row[i])
+            .count()
+    };
+
+    let mut i = 0;
+    while i < row.len() {
+        let run = run_at(i);
+        if run >= 2 {
+            out.extend_from_slice(&[(1 - run as i32) as u8, row[i]]);
+            i += run;
+            continue;
+        }
+
+        // Literals stop where a run of three would save a byte.
+        let start = i;
+        while i < row.len() && i - start < 128 && (i == start || run_at(i) < 3) {
+            i += 1;
+        }
+        out.push((i - start - 1) as u8);
+        out.extend_from_slice(&row[start..i]);
+    }
+}
+
+///
+/// Append an IFD, sorted by tag with values that do not fit in
+/// an entry after it.
+///
+/// # Returns
+///
+/// * The position of its (zero) next IFD offset
+///
+fn write_ifd(out: &mut Vec<u8>, mut fields: Vec<Field>) -> usize {
+    fields.sort_by_key(|field| field.tag);
+
+    let start = out.len();
+    let mut overflow = start + 2 + fields.len() * 12 + 4;
+    out.extend_from_slice(&(fields.len() as u16).to_le_bytes());
+    let mut values = Vec::new();
+    for field in &fields {
+        out.extend_from_slice(&field.tag.to_le_bytes());
+        out.extend_from_slice(&field.field_type.to_le_bytes());
+        out.extend_from_slice(&field.count.to_le_bytes());
+        if field.value.len() <= 4 {
+            let mut inline = field.value.clone();
+            inline.resize(4, 0);
+            out.extend_from_slice(&inline);
+        } else {
+            out.extend_from_slice(&(overflow as u32).to_le_bytes());
+            values.extend_from_slice(&field.value);
+            overflow += field.value.len();
+            // Values start on word boundaries.
+            if overflow % 2 == 1 {
+                values.push(0);
+                overflow += 1;
+            }
+        }
+    }
+
+    let link = out.len();
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&values);
+    link
+}
@@ -0,0 +1,66 @@
+use crate::codec::decoders::tiff::Compression;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitDepth {
+    Eight,
+    /// Each 8-bit sample is widened to 16 bits (v * 257).
+    Sixteen,
+}
+
+///
+/// How the pixels of each page are divided up.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// Bands of whole rows, read by every TIFF reader.
+    Strips { rows_per_strip: usize },
+    /// Rectangles whose sides are multiples of 16, which let
+    /// viewers of very large images load only what they show.
+    Tiles { width: usize, height: usize },
+}
+
+///
+/// Settings for `encode_with_options` and `encode_pages`.
+///
+/// The default writes 8-bit LZW compressed strips of 32 rows.
+/// LZW and Deflate data is written with the horizontal predictor.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub compression: Compression,
+    pub bit_depth: BitDepth,
+    pub layout: Layout,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            compression: Compression::Lzw,
+            bit_depth: BitDepth::Eight,
+            layout: Layout::Strips { rows_per_strip: 32 },
+        }
+    }
+}
+
+impl EncodeOptions {
+    ///
+    /// Uncompressed strips, for readers with no decompression at all.
+    ///
+    pub fn uncompressed() -> Self {
+        EncodeOptions {
+            compression: Compression::None,
+            ..EncodeOptions::default()
+        }
+    }
+
+    ///
+    /// Deflate compressed strips with 16-bit samples.
+    ///
+    pub fn sixteen_bit() -> Self {
+        EncodeOptions {
+            compression: Compression::Deflate,
+            bit_depth: BitDepth::Sixteen,
+            ..EncodeOptions::default()
+        }
+    }
+}
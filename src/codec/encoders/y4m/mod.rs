@@ -0,0 +1,129 @@
+mod options;
+
+use std::io::{self, Write};
+
+use crate::codec::decoders::y4m::{Chroma, FRAME, MAGIC};
+use crate::codec::{quantize, yuv};
+use crate::color::ColorRange;
+use crate::error::Error;
+use crate::image::Image;
+
+pub use options::EncodeOptions;
+
+pub fn encode(image: &Image) -> Result<Vec<u8>, Error> {
+    encode_with_options(image, &EncodeOptions::default())
+}
+
+///
+/// Encode an image as a Y4M stream of one frame.
+///
+pub fn encode_with_options(image: &Image, options: &EncodeOptions) -> Result<Vec<u8>, Error> {
+    let mut writer = FrameWriter::new(Vec::new(), image.width(), image.height(), options)?;
+    writer.write_frame(image)?;
+    Ok(writer.into_inner())
+}
+
+fn invalid_input(message: String) -> Error {
+    Error::ImageEncodeError(io::Error::new(io::ErrorKind::InvalidInput, message))
+}
+
+///
+/// Streams images of one size out as YUV4MPEG2 frames, e.g. to
+/// stdout for another program to encode.
+///
+/// Images of any `ColorSpace` are converted to BT.601 YCbCr. Alpha
+/// is kept only by `Chroma::Yuva444` streams, colour is dropped by
+/// `Chroma::Mono` ones.
+///
+#[derive(Debug)]
+pub struct FrameWriter<W: Write> {
+    writer: W,
+    width: usize,
+    height: usize,
+    options: EncodeOptions,
+}
+
+impl<W: Write> FrameWriter<W> {
+    ///
+    /// Write the stream header.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - Where the stream goes. Each frame is written with a
+    ///   few large writes, so buffering is optional.
+    /// * `width`, `height` - The size every frame must have
+    ///
+    pub fn new(
+        mut writer: W,
+        width: usize,
+        height: usize,
+        options: &EncodeOptions,
+    ) -> Result<Self, Error> {
+        if width == 0 || height == 0 {
+            return Err(invalid_input(format!(
+                "Y4M cannot store a {width}x{height} image"
+            )));
+        }
+
+        let (numerator, denominator) = options.frame_rate;
+        let range = match options.color_range {
+            ColorRange::Limited => "LIMITED",
+            ColorRange::Full => "FULL",
+        };
+        writeln!(
+            writer,
+            "{MAGIC} W{width} H{height} F{numerator}:{denominator} Ip A1:1 C{} XCOLORRANGE={range}",
+            options.chroma.name()
+        )?;
+
+        Ok(FrameWriter {
+            writer,
+            width,
+            height,
+            options: *options,
+        })
+    }
+
+    pub fn write_frame(&mut self, image: &Image) -> Result<(), Error> {
+        let colorspace = image.colorspace();
+        let shape = image.shape();
+        if shape.ndim != colorspace.channels() {
+            return Err(Error::ColorSpaceError(format!(
+                "Shape has {} channels but {:?} requires {}",
+                shape.ndim,
+                colorspace,
+                colorspace.channels()
+            )));
+        }
+        let (width, height) = (self.width, self.height);
+        if (image.width(), image.height()) != (width, height) {
+            return Err(invalid_input(format!(
+                "Frame is {}x{} but the stream is {width}x{height}",
+                image.width(),
+                image.height()
+            )));
+        }
+
+        let range = self.options.color_range;
+        let chroma = self.options.chroma;
+        let pixels = quantize::rgba_pixels(image);
+        let planes = yuv::from_rgba(&pixels, (width, height), chroma.factors(), range);
+
+        self.writer.write_all(FRAME.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.writer.write_all(&planes.luma)?;
+        if chroma != Chroma::Mono {
+            self.writer.write_all(&planes.cb)?;
+            self.writer.write_all(&planes.cr)?;
+        }
+        if chroma == Chroma::Yuva444 {
+            let alpha: Vec<u8> = pixels.iter().map(|pixel| pixel[3]).collect();
+            self.writer.write_all(&alpha)?;
+        }
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
@@ -0,0 +1,26 @@
+use crate::codec::decoders::y4m::Chroma;
+use crate::color::ColorRange;
+
+///
+/// Settings for `encode_with_options` and `FrameWriter`.
+///
+/// The default is 4:2:0 limited range video at 25 frames per
+/// second, which every player and encoder accepts.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub chroma: Chroma,
+    /// Frames per second as a fraction, e.g. `(30000, 1001)`.
+    pub frame_rate: (u32, u32),
+    pub color_range: ColorRange,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            chroma: Chroma::Yuv420,
+            frame_rate: (25, 1),
+            color_range: ColorRange::Limited,
+        }
+    }
+}
@@ -0,0 +1,267 @@
+use log::debug;
+
+use std::collections::HashSet;
+
+use super::decoders::webp;
+use super::ifd::{self, ByteOrder};
+use super::{detect, Codex};
+use crate::error::Error;
+
+pub use super::ifd::Value;
+
+// Tags pointing at sub-IFDs.
+const EXIF_POINTER: u16 = 0x8769;
+const GPS_POINTER: u16 = 0x8825;
+const INTEROP_POINTER: u16 = 0xA005;
+
+pub const TAG_MAKE: u16 = 0x010F;
+pub const TAG_MODEL: u16 = 0x0110;
+pub const TAG_ORIENTATION: u16 = 0x0112;
+pub const TAG_SOFTWARE: u16 = 0x0131;
+pub const TAG_DATE_TIME: u16 = 0x0132;
+pub const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+
+///
+/// How the stored pixels must be transformed to display the image
+/// upright, from the EXIF `Orientation` tag.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Orientation {
+    /// 1: already upright.
+    Normal = 1,
+    /// 2: mirrored left to right.
+    FlipHorizontal = 2,
+    /// 3: upside down.
+    Rotate180 = 3,
+    /// 4: mirrored top to bottom.
+    FlipVertical = 4,
+    /// 5: mirrored along the main diagonal.
+    Transpose = 5,
+    /// 6: needs a quarter turn clockwise.
+    Rotate90 = 6,
+    /// 7: mirrored along the anti-diagonal.
+    Transverse = 7,
+    /// 8: needs a quarter turn anticlockwise.
+    Rotate270 = 8,
+}
+
+impl TryFrom<u32> for Orientation {
+    type Error = Error;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Orientation::Normal),
+            2 => Ok(Orientation::FlipHorizontal),
+            3 => Ok(Orientation::Rotate180),
+            4 => Ok(Orientation::FlipVertical),
+            5 => Ok(Orientation::Transpose),
+            6 => Ok(Orientation::Rotate90),
+            7 => Ok(Orientation::Transverse),
+            8 => Ok(Orientation::Rotate270),
+            _ => Err(Error::decode(format!("Invalid orientation {value}"))),
+        }
+    }
+}
+
+///
+/// The directory a field was read from.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ifd {
+    /// IFD0, describing the main image.
+    Primary,
+    /// IFD1, describing the embedded thumbnail.
+    Thumbnail,
+    Exif,
+    Gps,
+    Interop,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub ifd: Ifd,
+    pub tag: u16,
+    pub value: Value,
+}
+
+///
+/// EXIF metadata, as found in a JPEG `APP1` segment or a PNG `eXIf` chunk.
+///
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Exif {
+    /// Every field, in file order. Sub-IFD pointers are followed
+    /// rather than listed.
+    pub fields: Vec<Field>,
+}
+
+impl Exif {
+    ///
+    /// Parse the TIFF structure holding the EXIF fields.
+    ///
+    /// # Arguments
+    ///
+    /// * `data` - Starting at the `II` / `MM` byte order mark
+    ///
+    pub fn parse(data: &[u8]) -> Result<Self, Error> {
+        let (order, offset) = ifd::parse_header(data)?;
+
+        let mut exif = Exif::default();
+        let mut visited = HashSet::new();
+        let next = exif.read_directory(data, order, offset, Ifd::Primary, &mut visited)?;
+        if next != 0 {
+            // A broken thumbnail directory should not lose the main fields.
+            if let Err(error) = exif.read_directory(data, order, next, Ifd::Thumbnail, &mut visited)
+            {
+                debug!("Ignoring thumbnail IFD: {error}");
+            }
+        }
+        Ok(exif)
+    }
+
+    fn read_directory(
+        &mut self,
+        data: &[u8],
+        order: ByteOrder,
+        offset: u32,
+        directory: Ifd,
+        visited: &mut HashSet<u32>,
+    ) -> Result<u32, Error> {
+        if !visited.insert(offset) {
+            return Err(Error::decode("IFD loop detected"));
+        }
+
+        let (entries, next) = ifd::read_ifd(data, order, offset)?;
+        for entry in entries {
+            let sub_directory = match entry.tag {
+                EXIF_POINTER => Some(Ifd::Exif),
+                GPS_POINTER => Some(Ifd::Gps),
+                INTEROP_POINTER => Some(Ifd::Interop),
+                _ => None,
+            };
+
+            match (sub_directory, entry.value.as_u32()) {
+                (Some(sub_directory), Some(offset)) => {
+                    self.read_directory(data, order, offset, sub_directory, visited)?;
+                }
+                _ => self.fields.push(Field {
+                    ifd: directory,
+                    tag: entry.tag,
+                    value: entry.value,
+                }),
+            }
+        }
+        Ok(next)
+    }
+
+    ///
+    /// The value of `tag` in the `ifd` directory.
+    ///
+    pub fn get(&self, ifd: Ifd, tag: u16) -> Option<&Value> {
+        self.fields
+            .iter()
+            .find(|field| field.ifd == ifd && field.tag == tag)
+            .map(|field| &field.value)
+    }
+
+    pub fn orientation(&self) -> Option<Orientation> {
+        let value = self.get(Ifd::Primary, TAG_ORIENTATION)?.as_u32()?;
+        Orientation::try_from(value).ok()
+    }
+
+    pub fn make(&self) -> Option<&str> {
+        self.get(Ifd::Primary, TAG_MAKE)?.as_str()
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.get(Ifd::Primary, TAG_MODEL)?.as_str()
+    }
+
+    pub fn software(&self) -> Option<&str> {
+        self.get(Ifd::Primary, TAG_SOFTWARE)?.as_str()
+    }
+
+    ///
+    /// When the picture was taken, falling back to when the file
+    /// was last changed. Formatted `YYYY:MM:DD HH:MM:SS`.
+    ///
+    pub fn date_time(&self) -> Option<&str> {
+        self.get(Ifd::Exif, TAG_DATE_TIME_ORIGINAL)
+            .or_else(|| self.get(Ifd::Primary, TAG_DATE_TIME))?
+            .as_str()
+    }
+}
+
+///
+/// Find and parse the EXIF block of an encoded image without
+/// decoding its pixels.
+///
+/// # Returns
+///
+/// * `None` when the format has no EXIF block or the file has none
+///
+pub fn read_exif(data: &[u8]) -> Result<Option<Exif>, Error> {
+    let payload = match detect(data)? {
+        Codex::JPG => jpeg_exif(data),
+        Codex::PNG => png_exif(data),
+        // The file is itself the TIFF structure EXIF borrows.
+        Codex::TIFF => Some(data),
+        Codex::WEBP => webp_exif(data),
+        Codex::BMP
+        | Codex::PBM
+        | Codex::PGM
+        | Codex::PPM
+        | Codex::PAM
+        | Codex::QOI
+        | Codex::GIF
+        | Codex::HDR
+        | Codex::Y4M => None,
+    };
+    payload.map(Exif::parse).transpose()
+}
+
+///
+/// The TIFF structure of the first `Exif` APP1 segment.
+///
+fn jpeg_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut position = 2;
+    while position + 4 <= data.len() && data[position] == 0xFF {
+        let marker = data[position + 1];
+        if marker == 0xFF {
+            position += 1;
+            continue;
+        }
+        // Metadata segments precede the image data.
+        if matches!(marker, 0xD9 | 0xDA) {
+            break;
+        }
+
+        let length = u16::from_be_bytes([data[position + 2], data[position + 3]]) as usize;
+        let segment = data.get(position + 4..position + 2 + length)?;
+        if marker == 0xE1 && segment.starts_with(b"Exif\0\0") {
+            return Some(&segment[6..]);
+        }
+        position += 2 + length;
+    }
+    None
+}
+
+fn png_exif(data: &[u8]) -> Option<&[u8]> {
+    let mut position = 8;
+    while position + 12 <= data.len() {
+        let length = u32::from_be_bytes(data[position..position + 4].try_into().ok()?) as usize;
+        let chunk_type = &data[position + 4..position + 8];
+        let chunk = data.get(position + 8..position + 8 + length)?;
+        match chunk_type {
+            b"eXIf" => return Some(chunk),
+            b"IEND" => break,
+            _ => position += 12 + length,
+        }
+    }
+    None
+}
+
+fn webp_exif(data: &[u8]) -> Option<&[u8]> {
+    let (_, chunk) = webp::chunks(data).ok()?.find(|(id, _)| id == b"EXIF")?;
+    // Some writers keep the JPEG APP1 prefix.
+    Some(chunk.strip_prefix(b"Exif\0\0").unwrap_or(chunk))
+}
@@ -0,0 +1,241 @@
+//! Image File Directories, the tag structure shared by TIFF and EXIF.
+
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ByteOrder {
+    Little,
+    Big,
+}
+
+impl ByteOrder {
+    pub fn u16(&self, data: &[u8], offset: usize) -> Result<u16, Error> {
+        let bytes = data
+            .get(offset..offset + 2)
+            .ok_or_else(|| Error::decode("IFD offset out of bounds"))?;
+        let bytes = [bytes[0], bytes[1]];
+        Ok(match self {
+            ByteOrder::Little => u16::from_le_bytes(bytes),
+            ByteOrder::Big => u16::from_be_bytes(bytes),
+        })
+    }
+
+    pub fn u32(&self, data: &[u8], offset: usize) -> Result<u32, Error> {
+        let bytes = data
+            .get(offset..offset + 4)
+            .ok_or_else(|| Error::decode("IFD offset out of bounds"))?;
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        Ok(match self {
+            ByteOrder::Little => u32::from_le_bytes(bytes),
+            ByteOrder::Big => u32::from_be_bytes(bytes),
+        })
+    }
+
+    pub fn u64(&self, data: &[u8], offset: usize) -> Result<u64, Error> {
+        let (first, second) = (
+            self.u32(data, offset)? as u64,
+            self.u32(data, offset + 4)? as u64,
+        );
+        Ok(match self {
+            ByteOrder::Little => second << 32 | first,
+            ByteOrder::Big => first << 32 | second,
+        })
+    }
+}
+
+///
+/// The value of an IFD entry, decoded according to its field type.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Byte(Vec<u8>),
+    Ascii(String),
+    Short(Vec<u16>),
+    Long(Vec<u32>),
+    /// Numerator and denominator pairs.
+    Rational(Vec<(u32, u32)>),
+    SByte(Vec<i8>),
+    Undefined(Vec<u8>),
+    SShort(Vec<i16>),
+    SLong(Vec<i32>),
+    SRational(Vec<(i32, i32)>),
+    Float(Vec<f32>),
+    Double(Vec<f64>),
+}
+
+impl Value {
+    ///
+    /// The first element of an unsigned integer value.
+    ///
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_u32_vec()?.first().copied()
+    }
+
+    ///
+    /// Every element of an unsigned integer value.
+    ///
+    pub fn as_u32_vec(&self) -> Option<Vec<u32>> {
+        match self {
+            Value::Byte(values) => Some(values.iter().map(|&v| v as u32).collect()),
+            Value::Short(values) => Some(values.iter().map(|&v| v as u32).collect()),
+            Value::Long(values) => Some(values.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Ascii(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    ///
+    /// The first element of a rational value as a float.
+    ///
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Rational(values) => values
+                .first()
+                .map(|&(numerator, denominator)| numerator as f64 / denominator as f64),
+            Value::SRational(values) => values
+                .first()
+                .map(|&(numerator, denominator)| numerator as f64 / denominator as f64),
+            Value::Float(values) => values.first().map(|&value| value as f64),
+            Value::Double(values) => values.first().copied(),
+            _ => self.as_u32().map(|value| value as f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Entry {
+    pub tag: u16,
+    pub value: Value,
+}
+
+///
+/// Read the byte order mark and the offset of the first IFD.
+///
+/// # Arguments
+///
+/// * `data` - The TIFF structure, starting at its `II` or `MM` header
+///
+pub(crate) fn parse_header(data: &[u8]) -> Result<(ByteOrder, u32), Error> {
+    let order = match data.get(0..2) {
+        Some(b"II") => ByteOrder::Little,
+        Some(b"MM") => ByteOrder::Big,
+        _ => return Err(Error::decode("Invalid TIFF byte order mark")),
+    };
+    if order.u16(data, 2)? != 42 {
+        return Err(Error::decode("Invalid TIFF magic number"));
+    }
+    Ok((order, order.u32(data, 4)?))
+}
+
+///
+/// Read every entry of the IFD at `offset`.
+///
+/// # Returns
+///
+/// * The entries, in file order, and the offset of the next IFD
+///   (zero for the last one)
+///
+pub(crate) fn read_ifd(
+    data: &[u8],
+    order: ByteOrder,
+    offset: u32,
+) -> Result<(Vec<Entry>, u32), Error> {
+    let offset = offset as usize;
+    let count = order.u16(data, offset)? as usize;
+
+    let mut entries = Vec::with_capacity(count);
+    for i in 0..count {
+        let position = offset + 2 + i * 12;
+        let tag = order.u16(data, position)?;
+        let field_type = order.u16(data, position + 2)?;
+        let count = order.u32(data, position + 4)? as usize;
+
+        let Some(size) = type_size(field_type) else {
+            // Unknown types must be skipped, as TIFF 6.0 requires.
+            continue;
+        };
+        let length = size
+            .checked_mul(count)
+            .ok_or_else(|| Error::decode("IFD entry is too large"))?;
+        let start = if length <= 4 {
+            position + 8
+        } else {
+            order.u32(data, position + 8)? as usize
+        };
+        let bytes = data
+            .get(start..start + length)
+            .ok_or_else(|| Error::decode(format!("Value of tag {tag} is out of bounds")))?;
+
+        entries.push(Entry {
+            tag,
+            value: read_value(bytes, order, field_type, count)?,
+        });
+    }
+
+    let next = order.u32(data, offset + 2 + count * 12).unwrap_or(0);
+    Ok((entries, next))
+}
+
+fn type_size(field_type: u16) -> Option<usize> {
+    match field_type {
+        1 | 2 | 6 | 7 => Some(1),
+        3 | 8 => Some(2),
+        4 | 9 | 11 => Some(4),
+        5 | 10 | 12 => Some(8),
+        _ => None,
+    }
+}
+
+fn read_value(
+    bytes: &[u8],
+    order: ByteOrder,
+    field_type: u16,
+    count: usize,
+) -> Result<Value, Error> {
+    let u16s = |bytes: &[u8]| -> Result<Vec<u16>, Error> {
+        (0..count).map(|i| order.u16(bytes, i * 2)).collect()
+    };
+    let u32s = |bytes: &[u8]| -> Result<Vec<u32>, Error> {
+        (0..count).map(|i| order.u32(bytes, i * 4)).collect()
+    };
+    let pairs = |bytes: &[u8]| -> Result<Vec<(u32, u32)>, Error> {
+        (0..count)
+            .map(|i| Ok((order.u32(bytes, i * 8)?, order.u32(bytes, i * 8 + 4)?)))
+            .collect()
+    };
+
+    Ok(match field_type {
+        1 => Value::Byte(bytes.to_vec()),
+        2 => {
+            // NUL terminated, possibly padded with more NULs.
+            let end = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+            Value::Ascii(String::from_utf8_lossy(&bytes[..end]).into_owned())
+        }
+        3 => Value::Short(u16s(bytes)?),
+        4 => Value::Long(u32s(bytes)?),
+        5 => Value::Rational(pairs(bytes)?),
+        6 => Value::SByte(bytes.iter().map(|&b| b as i8).collect()),
+        7 => Value::Undefined(bytes.to_vec()),
+        8 => Value::SShort(u16s(bytes)?.into_iter().map(|v| v as i16).collect()),
+        9 => Value::SLong(u32s(bytes)?.into_iter().map(|v| v as i32).collect()),
+        10 => Value::SRational(
+            pairs(bytes)?
+                .into_iter()
+                .map(|(n, d)| (n as i32, d as i32))
+                .collect(),
+        ),
+        11 => Value::Float(u32s(bytes)?.into_iter().map(f32::from_bits).collect()),
+        12 => Value::Double(
+            (0..count)
+                .map(|i| order.u64(bytes, i * 8).map(f64::from_bits))
+                .collect::<Result<_, _>>()?,
+        ),
+        _ => return Err(Error::decode(format!("Unknown field type {field_type}"))),
+    })
+}
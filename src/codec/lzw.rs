@@ -0,0 +1,157 @@
+use crate::error::Error;
+
+/// Codes are at most 12 bits wide.
+pub(crate) const MAX_CODES: usize = 4096;
+/// Clear and end codes after 8-bit literals, as TIFF always uses.
+pub(crate) const CLEAR: u16 = 256;
+pub(crate) const END: u16 = 257;
+
+///
+/// Which end of each byte variable width codes are packed from.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum BitOrder {
+    /// GIF
+    Lsb,
+    /// TIFF
+    Msb,
+}
+
+struct CodeReader<'a> {
+    data: &'a [u8],
+    order: BitOrder,
+    position: usize,
+    buffer: u32,
+    bits: u32,
+}
+
+impl CodeReader<'_> {
+    fn read(&mut self, size: u32) -> Option<u16> {
+        while self.bits < size {
+            let byte = *self.data.get(self.position)? as u32;
+            self.position += 1;
+            self.buffer = match self.order {
+                BitOrder::Lsb => self.buffer | byte << self.bits,
+                BitOrder::Msb => self.buffer << 8 | byte,
+            };
+            self.bits += 8;
+        }
+        let mask = (1 << size) - 1;
+        self.bits -= size;
+        let code = match self.order {
+            BitOrder::Lsb => {
+                let code = self.buffer & mask;
+                self.buffer >>= size;
+                code
+            }
+            BitOrder::Msb => (self.buffer >> self.bits) & mask,
+        };
+        Some(code as u16)
+    }
+}
+
+///
+/// Decompress variable width LZW codes into bytes.
+///
+/// # Arguments
+///
+/// * `data` - The packed codes
+/// * `min_code_size` - Bits per literal. Codes start one bit wider,
+///   after the clear and end codes.
+/// * `expected` - Decoding stops after this many bytes, and data
+///   ending early yields fewer.
+/// * `order` - How codes are packed into bytes
+/// * `early_change` - Widen codes one entry before the table needs
+///   it, as TIFF does
+///
+pub(crate) fn decompress(
+    data: &[u8],
+    min_code_size: u8,
+    expected: usize,
+    order: BitOrder,
+    early_change: bool,
+) -> Result<Vec<u8>, Error> {
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    // Each code is its prefix code plus one final byte.
+    let mut prefix = [0u16; MAX_CODES];
+    let mut suffix = [0u8; MAX_CODES];
+    let mut first = [0u8; MAX_CODES];
+    let mut length = [0u16; MAX_CODES];
+    for code in 0..clear {
+        suffix[code as usize] = code as u8;
+        first[code as usize] = code as u8;
+        length[code as usize] = 1;
+    }
+
+    let mut reader = CodeReader {
+        data,
+        order,
+        position: 0,
+        buffer: 0,
+        bits: 0,
+    };
+    let mut size = min_code_size as u32 + 1;
+    let mut next = end + 1;
+    let mut previous: Option<u16> = None;
+    // A forged `expected` must not reserve more than the data could
+    // plausibly hold; the buffer still grows if it expands further.
+    let mut out = Vec::with_capacity(expected.min(data.len().saturating_mul(8)));
+
+    while out.len() < expected {
+        let Some(code) = reader.read(size) else {
+            break;
+        };
+        if code == clear {
+            size = min_code_size as u32 + 1;
+            next = end + 1;
+            previous = None;
+            continue;
+        }
+        if code == end {
+            break;
+        }
+
+        match previous {
+            None if code >= clear => {
+                return Err(Error::decode(format!("Invalid first LZW code {code}")));
+            }
+            Some(_) if code > next => {
+                return Err(Error::decode(format!("Invalid LZW code {code}")));
+            }
+            Some(previous) if (next as usize) < MAX_CODES => {
+                // A code equal to `next` repeats the previous string
+                // followed by its own first byte.
+                let last = if code == next {
+                    first[previous as usize]
+                } else {
+                    first[code as usize]
+                };
+                let entry = next as usize;
+                prefix[entry] = previous;
+                suffix[entry] = last;
+                first[entry] = first[previous as usize];
+                length[entry] = length[previous as usize] + 1;
+                next += 1;
+                if next as usize + early_change as usize >= 1 << size && size < 12 {
+                    size += 1;
+                }
+            }
+            _ => {}
+        }
+
+        // Write the string back to front by following the prefixes.
+        let start = out.len();
+        out.resize(start + length[code as usize] as usize, 0);
+        let mut current = code as usize;
+        for slot in out[start..].iter_mut().rev() {
+            *slot = suffix[current];
+            current = prefix[current] as usize;
+        }
+        previous = Some(code);
+    }
+
+    out.truncate(expected);
+    Ok(out)
+}
@@ -1,8 +1,83 @@
 pub mod decoders;
 pub mod encoders;
+pub mod exif;
+mod ifd;
+mod lzw;
+mod quantize;
+pub mod raw;
+mod yuv;
+
+use crate::error::Error;
 
 #[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Codex {
     PNG,
     JPG,
+    BMP,
+    PBM,
+    PGM,
+    PPM,
+    PAM,
+    QOI,
+    GIF,
+    TIFF,
+    WEBP,
+    HDR,
+    Y4M,
+}
+
+///
+/// Identify the format of an encoded image from its leading bytes.
+///
+/// # Arguments
+///
+/// * `data` - The start of the file. The first 16 bytes are enough
+///   for every supported format.
+///
+/// # Returns
+///
+/// * The matching `Codex`, otherwise `Error::UnsupportedFormat`
+///
+pub fn detect(data: &[u8]) -> Result<Codex, Error> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Ok(Codex::PNG)
+    } else if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Ok(Codex::JPG)
+    } else if data.starts_with(b"BM") {
+        Ok(Codex::BMP)
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Ok(Codex::GIF)
+    } else if data.starts_with(b"II*\0") || data.starts_with(b"MM\0*") {
+        Ok(Codex::TIFF)
+    } else if data.starts_with(b"RIFF") && data.get(8..12) == Some(b"WEBP") {
+        Ok(Codex::WEBP)
+    } else if data.starts_with(b"#?RADIANCE") || data.starts_with(b"#?RGBE") {
+        Ok(Codex::HDR)
+    } else if data.starts_with(b"YUV4MPEG2 ") {
+        Ok(Codex::Y4M)
+    } else if data.starts_with(b"qoif") {
+        Ok(Codex::QOI)
+    } else if let Some(codex) = netpbm_codex(data) {
+        Ok(codex)
+    } else {
+        let prefix: Vec<String> = data.iter().take(8).map(|b| format!("{b:02X}")).collect();
+        Err(Error::UnsupportedFormat(format!(
+            "Unrecognised signature {}",
+            prefix.join(" ")
+        )))
+    }
+}
+
+///
+/// The `Codex` of a Netpbm magic number, `P1` to `P7`.
+///
+fn netpbm_codex(data: &[u8]) -> Option<Codex> {
+    match data.get(0..2)? {
+        b"P1" | b"P4" => Some(Codex::PBM),
+        b"P2" | b"P5" => Some(Codex::PGM),
+        b"P3" | b"P6" => Some(Codex::PPM),
+        b"P7" => Some(Codex::PAM),
+        _ => None,
+    }
 }
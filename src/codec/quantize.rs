@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::color::ColorSpace;
+use crate::image::Image;
+
+pub(crate) type Rgba = [u8; 4];
+
+///
+/// Result of reducing an image to a palette.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Quantized {
+    /// At most `max_colors` RGBA entries.
+    pub palette: Vec<Rgba>,
+    /// One palette index per pixel, row major.
+    pub indices: Vec<u8>,
+}
+
+///
+/// Read every pixel of `image` as RGBA, regardless of its `ColorSpace`.
+///
+pub(crate) fn rgba_pixels(image: &Image) -> Vec<Rgba> {
+    let channels = image.colorspace().channels();
+    image
+        .slice(0, image.size())
+        .chunks_exact(channels)
+        .map(|p| match image.colorspace() {
+            ColorSpace::RGB => [p[0], p[1], p[2], 255],
+            ColorSpace::RGBA => [p[0], p[1], p[2], p[3]],
+            ColorSpace::BGR => [p[2], p[1], p[0], 255],
+            ColorSpace::BGRA => [p[2], p[1], p[0], p[3]],
+            ColorSpace::GRAY => [p[0], p[0], p[0], 255],
+            ColorSpace::GRAYA => [p[0], p[0], p[0], p[1]],
+        })
+        .collect()
+}
+
+///
+/// Build a palette of at most `max_colors` (<= 256) entries.
+///
+/// When the pixels use no more than `max_colors` distinct colours
+/// the palette is exact. Otherwise the colours are reduced with
+/// the median cut algorithm and each pixel is mapped to its
+/// nearest palette entry.
+///
+pub(crate) fn quantize(pixels: &[Rgba], max_colors: usize) -> Quantized {
+    let max_colors = max_colors.clamp(1, 256);
+
+    let mut histogram: HashMap<Rgba, usize> = HashMap::new();
+    for pixel in pixels {
+        *histogram.entry(*pixel).or_default() += 1;
+    }
+
+    let mut colors: Vec<(Rgba, usize)> = histogram.into_iter().collect();
+    // Keep the output deterministic regardless of hashing order.
+    colors.sort_unstable();
+
+    let palette = if colors.len() <= max_colors {
+        colors.iter().map(|(color, _)| *color).collect()
+    } else {
+        median_cut(colors, max_colors)
+    };
+
+    let mut lookup: HashMap<Rgba, u8> = HashMap::new();
+    let indices = pixels
+        .iter()
+        .map(|pixel| {
+            *lookup
+                .entry(*pixel)
+                .or_insert_with(|| nearest(&palette, pixel))
+        })
+        .collect();
+
+    Quantized { palette, indices }
+}
+
+///
+/// Index of the palette entry closest to `color` (squared RGBA distance).
+///
+pub(crate) fn nearest(palette: &[Rgba], color: &Rgba) -> u8 {
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, entry)| distance(entry, color))
+        .map_or(0, |(index, _)| index as u8)
+}
+
+fn distance(a: &Rgba, b: &Rgba) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(&x, &y)| (x as i32 - y as i32).pow(2) as u32)
+        .sum()
+}
+
+///
+/// Repeatedly split the box with the widest channel range at the
+/// weighted median of that channel, then average each box.
+///
+fn median_cut(colors: Vec<(Rgba, usize)>, max_colors: usize) -> Vec<Rgba> {
+    let mut boxes = vec![colors];
+
+    while boxes.len() < max_colors {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(index, colors)| {
+                let (channel, range) = widest_channel(colors);
+                (index, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range);
+
+        let Some((index, channel, _)) = widest else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|(color, _)| color[channel]);
+
+        let total: usize = colors.iter().map(|(_, count)| count).sum();
+        let mut seen = 0;
+        let mut split = colors.len() - 1;
+        for (i, (_, count)) in colors.iter().enumerate() {
+            seen += count;
+            if seen * 2 >= total {
+                split = i + 1;
+                break;
+            }
+        }
+        let split = split.clamp(1, colors.len() - 1);
+
+        let upper = colors.split_off(split);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|colors| average(colors)).collect()
+}
+
+fn widest_channel(colors: &[(Rgba, usize)]) -> (usize, u8) {
+    (0..4)
+        .map(|channel| {
+            let (min, max) = colors.iter().fold((255, 0), |(min, max), (color, _)| {
+                (color[channel].min(min), color[channel].max(max))
+            });
+            (channel, max - min)
+        })
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+fn average(colors: &[(Rgba, usize)]) -> Rgba {
+    let total: usize = colors.iter().map(|(_, count)| count).sum();
+    let mut sums = [0usize; 4];
+    for (color, count) in colors {
+        for channel in 0..4 {
+            sums[channel] += color[channel] as usize * count;
+        }
+    }
+    sums.map(|sum| ((sum + total / 2) / total) as u8)
+}
@@ -0,0 +1,201 @@
+use crate::color::{ColorRange, ColorSpace};
+
+///
+/// Memory layout of the pixels of a raw frame, as delivered by
+/// cameras and capture cards. Multi-byte words are little-endian.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// One byte of grey per pixel.
+    Gray8,
+    /// Bytes R, G, B.
+    Rgb24,
+    /// Bytes B, G, R.
+    Bgr24,
+    /// Bytes R, G, B, A.
+    Rgba32,
+    /// Bytes B, G, R, A.
+    Bgra32,
+    /// 16-bit words of 5 bits red (high), 6 green and 5 blue.
+    Rgb565,
+    /// 32-bit words of 10 bits red (bits 20 - 29), green (10 - 19)
+    /// and blue (0 - 9), with the top 2 bits unused.
+    X2Rgb10,
+    /// 4:2:2 YCbCr, bytes Y0, Cb, Y1, Cr for each pair of pixels.
+    Yuyv,
+    /// 4:2:2 YCbCr with 10-bit samples, three to a 32-bit word.
+    /// Each 16 bytes hold 6 pixels as Cb Y Cr Y, Cb Y Cr Y, Cb Y Cr Y.
+    V210,
+    /// 4:2:0 YCbCr: a luma plane, then a plane of interleaved Cb, Cr.
+    Nv12,
+    /// 4:2:0 YCbCr: luma, Cb and Cr planes.
+    I420,
+}
+
+impl PixelFormat {
+    pub fn planes(&self) -> usize {
+        match self {
+            PixelFormat::Nv12 => 2,
+            PixelFormat::I420 => 3,
+            _ => 1,
+        }
+    }
+
+    ///
+    /// Bytes needed for one row of a plane, without padding.
+    ///
+    /// # Arguments
+    ///
+    /// * `width` - The width of the image in pixels
+    /// * `plane` - Index of the plane, 0 for luma or packed pixels
+    ///
+    pub fn row_bytes(&self, width: usize, plane: usize) -> usize {
+        match (self, plane) {
+            (PixelFormat::Gray8, _) => width,
+            (PixelFormat::Rgb24 | PixelFormat::Bgr24, _) => width * 3,
+            (PixelFormat::Rgba32 | PixelFormat::Bgra32 | PixelFormat::X2Rgb10, _) => width * 4,
+            (PixelFormat::Rgb565, _) => width * 2,
+            (PixelFormat::Yuyv, _) => width.div_ceil(2) * 4,
+            (PixelFormat::V210, _) => width.div_ceil(6) * 16,
+            (PixelFormat::Nv12 | PixelFormat::I420, 0) => width,
+            (PixelFormat::Nv12, _) => width.div_ceil(2) * 2,
+            (PixelFormat::I420, _) => width.div_ceil(2),
+        }
+    }
+
+    ///
+    /// Number of rows in a plane.
+    ///
+    pub fn plane_rows(&self, height: usize, plane: usize) -> usize {
+        match (self, plane) {
+            (PixelFormat::Nv12 | PixelFormat::I420, 1..) => height.div_ceil(2),
+            _ => height,
+        }
+    }
+
+    ///
+    /// The `ColorSpace` of imported images.
+    ///
+    pub fn colorspace(&self) -> ColorSpace {
+        match self {
+            PixelFormat::Gray8 => ColorSpace::GRAY,
+            PixelFormat::Bgr24 => ColorSpace::BGR,
+            PixelFormat::Rgba32 => ColorSpace::RGBA,
+            PixelFormat::Bgra32 => ColorSpace::BGRA,
+            _ => ColorSpace::RGB,
+        }
+    }
+}
+
+///
+/// Describes a raw frame: its pixel format, size and the stride
+/// of each plane. Planes are stored one after another.
+///
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BufferLayout {
+    pub format: PixelFormat,
+    pub width: usize,
+    pub height: usize,
+    /// Bytes from the start of one row to the next, per plane.
+    pub strides: Vec<usize>,
+    /// The range of YCbCr samples. Ignored by RGB and grey formats.
+    pub color_range: ColorRange,
+}
+
+impl BufferLayout {
+    ///
+    /// Rows without padding, and limited range YCbCr.
+    ///
+    pub fn packed(format: PixelFormat, width: usize, height: usize) -> Self {
+        Self::aligned(format, width, height, 1)
+    }
+
+    ///
+    /// Rows padded to a multiple of `alignment` bytes, as many
+    /// drivers allocate them. V210 is usually aligned to 128.
+    ///
+    pub fn aligned(format: PixelFormat, width: usize, height: usize, alignment: usize) -> Self {
+        let alignment = alignment.max(1);
+        let strides = (0..format.planes())
+            .map(|plane| format.row_bytes(width, plane).next_multiple_of(alignment))
+            .collect();
+        BufferLayout {
+            format,
+            width,
+            height,
+            strides,
+            color_range: ColorRange::Limited,
+        }
+    }
+
+    ///
+    /// Offset of each plane in a buffer holding them all.
+    ///
+    pub fn offsets(&self) -> Vec<usize> {
+        let mut offset = 0;
+        (0..self.format.planes())
+            .map(|plane| {
+                let start = offset;
+                offset += self.plane_size(plane);
+                start
+            })
+            .collect()
+    }
+
+    ///
+    /// Size of a buffer holding every plane, including the
+    /// padding after the last row.
+    ///
+    pub fn size(&self) -> usize {
+        (0..self.format.planes())
+            .map(|plane| self.plane_size(plane))
+            .sum()
+    }
+
+    fn plane_size(&self, plane: usize) -> usize {
+        self.strides[plane] * self.format.plane_rows(self.height, plane)
+    }
+
+    ///
+    /// Bytes of a plane that must be present: the padding after
+    /// its last row may be missing.
+    ///
+    pub(crate) fn min_plane_size(&self, plane: usize) -> usize {
+        let rows = self.format.plane_rows(self.height, plane);
+        self.strides[plane] * (rows - 1) + self.format.row_bytes(self.width, plane)
+    }
+
+    ///
+    /// Why the layout cannot describe a frame, if it cannot.
+    ///
+    pub(crate) fn check(&self) -> Result<(), String> {
+        // Rows of any format fit 16 bytes per pixel.
+        if self.width == 0 || self.height == 0 || self.width.checked_mul(16).is_none() {
+            return Err(format!("Invalid size {}x{}", self.width, self.height));
+        }
+        let planes = self.format.planes();
+        if self.strides.len() != planes {
+            return Err(format!(
+                "{:?} needs {planes} strides, got {}",
+                self.format,
+                self.strides.len()
+            ));
+        }
+        for (plane, &stride) in self.strides.iter().enumerate() {
+            let row_bytes = self.format.row_bytes(self.width, plane);
+            if stride < row_bytes {
+                return Err(format!(
+                    "Stride {stride} of plane {plane} is below the {row_bytes} bytes of a row"
+                ));
+            }
+        }
+        let size = (0..planes).try_fold(0usize, |size, plane| {
+            let rows = self.format.plane_rows(self.height, plane);
+            self.strides[plane].checked_mul(rows)?.checked_add(size)
+        });
+        match size {
+            Some(_) => Ok(()),
+            None => Err("Frame is too large".to_string()),
+        }
+    }
+}
@@ -0,0 +1,335 @@
+mod layout;
+
+use std::io;
+
+use crate::codec::quantize::{self, Rgba};
+use crate::codec::yuv::{self, YuvPlanes};
+use crate::color::ColorRange;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::image::Image;
+
+pub use layout::{BufferLayout, PixelFormat};
+
+///
+/// Convert a raw frame to an `Image`.
+///
+/// Packed RGB and grey formats keep their channels, see
+/// `PixelFormat::colorspace`; everything else becomes
+/// `ColorSpace::RGB`. Subsampled chroma is interpolated as if
+/// sited between the luma samples it covers.
+///
+/// # Arguments
+///
+/// * `data` - Every plane, one after another as `layout.offsets()`
+///   gives. The padding after the last row may be missing.
+/// * `layout` - The format, size and strides of the frame
+///
+pub fn import(data: &[u8], layout: &BufferLayout) -> Result<Image, Error> {
+    layout.check().map_err(Error::decode)?;
+    let planes: Vec<&[u8]> = layout
+        .offsets()
+        .into_iter()
+        .map(|offset| data.get(offset..).unwrap_or_default())
+        .collect();
+    import_planes(&planes, layout)
+}
+
+///
+/// Same as `import`, for frames whose planes are in separate buffers.
+///
+pub fn import_planes(planes: &[&[u8]], layout: &BufferLayout) -> Result<Image, Error> {
+    layout.check().map_err(Error::decode)?;
+    if planes.len() != layout.format.planes() {
+        return Err(Error::decode(format!(
+            "{:?} has {} planes, got {}",
+            layout.format,
+            layout.format.planes(),
+            planes.len()
+        )));
+    }
+    for (plane, data) in planes.iter().enumerate() {
+        let size = layout.min_plane_size(plane);
+        if data.len() < size {
+            return Err(Error::decode(format!(
+                "Plane {plane} holds {} bytes but needs {size}",
+                data.len()
+            )));
+        }
+    }
+
+    let (width, height) = (layout.width, layout.height);
+    let rows = |plane: usize| {
+        let stride = layout.strides[plane];
+        let row_bytes = layout.format.row_bytes(width, plane);
+        let count = layout.format.plane_rows(height, plane);
+        (0..count).map(move |y| &planes[plane][y * stride..y * stride + row_bytes])
+    };
+
+    let range = layout.color_range;
+    let data: Vec<u8> = match layout.format {
+        PixelFormat::Gray8
+        | PixelFormat::Rgb24
+        | PixelFormat::Bgr24
+        | PixelFormat::Rgba32
+        | PixelFormat::Bgra32 => rows(0).flatten().copied().collect(),
+        PixelFormat::Rgb565 => rows(0)
+            .flat_map(|row| row.chunks_exact(2))
+            .flat_map(|word| {
+                let word = u16::from_le_bytes([word[0], word[1]]);
+                [
+                    expand(word >> 11 & 0x1F, 5),
+                    expand(word >> 5 & 0x3F, 6),
+                    expand(word & 0x1F, 5),
+                ]
+            })
+            .collect(),
+        PixelFormat::X2Rgb10 => rows(0)
+            .flat_map(|row| row.chunks_exact(4))
+            .flat_map(|word| {
+                let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+                [20, 10, 0].map(|shift| expand((word >> shift & 0x3FF) as u16, 10))
+            })
+            .collect(),
+        PixelFormat::Yuyv | PixelFormat::V210 => {
+            let mut planes = YuvPlanes::default();
+            for row in rows(0) {
+                let quads = match layout.format {
+                    // Reordered to match the Cb Y Cr Y of V210.
+                    PixelFormat::Yuyv => row
+                        .chunks_exact(4)
+                        .map(|q| [q[1], q[0], q[3], q[2]])
+                        .collect(),
+                    _ => v210_quads(row),
+                };
+                for (x, [cb, y0, cr, y1]) in quads.into_iter().take(width.div_ceil(2)).enumerate() {
+                    planes.luma.push(y0);
+                    if 2 * x + 1 < width {
+                        planes.luma.push(y1);
+                    }
+                    planes.cb.push(cb);
+                    planes.cr.push(cr);
+                }
+            }
+            rgb_data(&planes, (width, height), (2, 1), range)
+        }
+        PixelFormat::Nv12 => {
+            let (cb, cr) = rows(1)
+                .flat_map(|row| row.chunks_exact(2))
+                .map(|pair| (pair[0], pair[1]))
+                .unzip();
+            let planes = YuvPlanes {
+                luma: rows(0).flatten().copied().collect(),
+                cb,
+                cr,
+            };
+            rgb_data(&planes, (width, height), (2, 2), range)
+        }
+        PixelFormat::I420 => {
+            let planes = YuvPlanes {
+                luma: rows(0).flatten().copied().collect(),
+                cb: rows(1).flatten().copied().collect(),
+                cr: rows(2).flatten().copied().collect(),
+            };
+            rgb_data(&planes, (width, height), (2, 2), range)
+        }
+    };
+
+    let colorspace = layout.format.colorspace();
+    let shape = Shape::new(width, height, Some(colorspace.channels()));
+    Ok(Image::from_data(data, shape, colorspace))
+}
+
+///
+/// Convert an `Image` to a raw frame.
+///
+/// Images of any `ColorSpace` are converted to the layout's format.
+/// Alpha is dropped by formats without it, and row padding is
+/// filled with zeros.
+///
+/// # Returns
+///
+/// * A buffer of `layout.size()` bytes, with the planes at
+///   `layout.offsets()`
+///
+pub fn export(image: &Image, layout: &BufferLayout) -> Result<Vec<u8>, Error> {
+    let invalid_input = |message: String| {
+        Error::ImageEncodeError(io::Error::new(io::ErrorKind::InvalidInput, message))
+    };
+    layout.check().map_err(invalid_input)?;
+    let colorspace = image.colorspace();
+    let shape = image.shape();
+    if shape.ndim != colorspace.channels() {
+        return Err(Error::ColorSpaceError(format!(
+            "Shape has {} channels but {:?} requires {}",
+            shape.ndim,
+            colorspace,
+            colorspace.channels()
+        )));
+    }
+    let (width, height) = (layout.width, layout.height);
+    if (image.width(), image.height()) != (width, height) {
+        return Err(invalid_input(format!(
+            "Image is {}x{} but the layout is {width}x{height}",
+            image.width(),
+            image.height()
+        )));
+    }
+
+    let pixels = quantize::rgba_pixels(image);
+    let range = layout.color_range;
+    // The bytes of each row of each plane, without padding.
+    let rows: Vec<Vec<Vec<u8>>> = match layout.format {
+        PixelFormat::Gray8
+        | PixelFormat::Rgb24
+        | PixelFormat::Bgr24
+        | PixelFormat::Rgba32
+        | PixelFormat::Bgra32
+        | PixelFormat::Rgb565
+        | PixelFormat::X2Rgb10 => {
+            let packed = pixels.chunks_exact(width).map(|row| {
+                row.iter()
+                    .flat_map(|&pixel| pack(pixel, layout.format))
+                    .collect()
+            });
+            vec![packed.collect()]
+        }
+        PixelFormat::Yuyv | PixelFormat::V210 => {
+            let planes = yuv::from_rgba(&pixels, (width, height), (2, 1), range);
+            let chroma_width = width.div_ceil(2);
+            let packed = (0..height).map(|y| {
+                let quads = (0..chroma_width).map(|x| {
+                    let luma = &planes.luma[y * width..(y + 1) * width];
+                    let chroma = y * chroma_width + x;
+                    // An odd last pixel is repeated to fill its pair.
+                    let y1 = luma[(2 * x + 1).min(width - 1)];
+                    [planes.cb[chroma], luma[2 * x], planes.cr[chroma], y1]
+                });
+                match layout.format {
+                    PixelFormat::Yuyv => quads
+                        .flat_map(|[cb, y0, cr, y1]| [y0, cb, y1, cr])
+                        .collect(),
+                    _ => v210_row(quads.collect()),
+                }
+            });
+            vec![packed.collect()]
+        }
+        PixelFormat::Nv12 | PixelFormat::I420 => {
+            let planes = yuv::from_rgba(&pixels, (width, height), (2, 2), range);
+            let chroma_width = width.div_ceil(2);
+            let luma = planes
+                .luma
+                .chunks_exact(width)
+                .map(<[u8]>::to_vec)
+                .collect();
+            let cb = planes.cb.chunks_exact(chroma_width);
+            let cr = planes.cr.chunks_exact(chroma_width);
+            match layout.format {
+                PixelFormat::Nv12 => {
+                    let interleaved = cb
+                        .zip(cr)
+                        .map(|(cb, cr)| cb.iter().zip(cr).flat_map(|(&b, &r)| [b, r]).collect())
+                        .collect();
+                    vec![luma, interleaved]
+                }
+                _ => vec![
+                    luma,
+                    cb.map(<[u8]>::to_vec).collect(),
+                    cr.map(<[u8]>::to_vec).collect(),
+                ],
+            }
+        }
+    };
+
+    let mut out = vec![0; layout.size()];
+    for (plane, offset) in layout.offsets().into_iter().enumerate() {
+        let stride = layout.strides[plane];
+        for (y, row) in rows[plane].iter().enumerate() {
+            let start = offset + y * stride;
+            out[start..start + row.len()].copy_from_slice(row);
+        }
+    }
+    Ok(out)
+}
+
+///
+/// Scale a sample of `bits` bits to 8 bits, rounding.
+///
+fn expand(value: u16, bits: u32) -> u8 {
+    let max = (1u32 << bits) - 1;
+    ((value as u32 * 255 + max / 2) / max) as u8
+}
+
+///
+/// Scale an 8-bit sample to `bits` bits, rounding.
+///
+fn reduce(value: u8, bits: u32) -> u32 {
+    let max = (1u32 << bits) - 1;
+    (value as u32 * max + 127) / 255
+}
+
+fn pack([r, g, b, a]: Rgba, format: PixelFormat) -> Vec<u8> {
+    match format {
+        PixelFormat::Gray8 => vec![ColorRange::Full.rgb_to_ycbcr(r, g, b)[0]],
+        PixelFormat::Rgb24 => vec![r, g, b],
+        PixelFormat::Bgr24 => vec![b, g, r],
+        PixelFormat::Rgba32 => vec![r, g, b, a],
+        PixelFormat::Bgra32 => vec![b, g, r, a],
+        PixelFormat::Rgb565 => {
+            let word = reduce(r, 5) << 11 | reduce(g, 6) << 5 | reduce(b, 5);
+            (word as u16).to_le_bytes().to_vec()
+        }
+        PixelFormat::X2Rgb10 => {
+            let word = reduce(r, 10) << 20 | reduce(g, 10) << 10 | reduce(b, 10);
+            word.to_le_bytes().to_vec()
+        }
+        _ => unreachable!("{format:?} is not a packed RGB format"),
+    }
+}
+
+///
+/// Unpack a row of V210 to 8-bit Cb, Y, Cr, Y groups. Each 10-bit
+/// sample keeps its top 8 bits, rounded, as limited range YCbCr
+/// puts black at 64 and white at 940.
+///
+fn v210_quads(row: &[u8]) -> Vec<[u8; 4]> {
+    let samples: Vec<u8> = row
+        .chunks_exact(4)
+        .map(|word| u32::from_le_bytes([word[0], word[1], word[2], word[3]]))
+        .flat_map(|word| [0, 10, 20].map(|shift| word >> shift & 0x3FF))
+        .map(|sample| ((sample + 2) >> 2).min(255) as u8)
+        .collect();
+    samples
+        .chunks_exact(4)
+        .map(|quad| [quad[0], quad[1], quad[2], quad[3]])
+        .collect()
+}
+
+///
+/// Pack 8-bit Cb, Y, Cr, Y groups into a row of V210, padding
+/// the last block of 6 pixels with zeros.
+///
+fn v210_row(quads: Vec<[u8; 4]>) -> Vec<u8> {
+    let mut samples: Vec<u32> = quads
+        .iter()
+        .flatten()
+        .map(|&sample| (sample as u32) << 2)
+        .collect();
+    samples.resize(samples.len().next_multiple_of(12), 0);
+    samples
+        .chunks_exact(3)
+        .flat_map(|three| (three[0] | three[1] << 10 | three[2] << 20).to_le_bytes())
+        .collect()
+}
+
+fn rgb_data(
+    planes: &YuvPlanes,
+    size: (usize, usize),
+    factors: (usize, usize),
+    range: ColorRange,
+) -> Vec<u8> {
+    yuv::to_rgb(planes, size, factors, range)
+        .into_iter()
+        .flatten()
+        .collect()
+}
@@ -0,0 +1,150 @@
+use crate::codec::quantize::Rgba;
+use crate::color::ColorRange;
+
+///
+/// YCbCr as three planes of tightly packed rows, with the chroma
+/// planes subsampled.
+///
+#[derive(Debug, Clone, Default)]
+pub(crate) struct YuvPlanes {
+    pub luma: Vec<u8>,
+    pub cb: Vec<u8>,
+    pub cr: Vec<u8>,
+}
+
+///
+/// Size of a chroma plane when `factors` (horizontal, vertical)
+/// luma samples share each chroma sample.
+///
+pub(crate) fn chroma_size(
+    (width, height): (usize, usize),
+    (h, v): (usize, usize),
+) -> (usize, usize) {
+    (width.div_ceil(h), height.div_ceil(v))
+}
+
+///
+/// Upsample the chroma, interpolating between sample centres,
+/// and convert every pixel to RGB.
+///
+pub(crate) fn to_rgb(
+    planes: &YuvPlanes,
+    (width, height): (usize, usize),
+    factors: (usize, usize),
+    range: ColorRange,
+) -> Vec<[u8; 3]> {
+    let size = chroma_size((width, height), factors);
+    let [cb, cr] = [&planes.cb, &planes.cr]
+        .map(|plane| upsample(plane, size.0, size, (1, 1), factors, (width, height)));
+    (0..width * height)
+        .map(|i| range.ycbcr_to_rgb(planes.luma[i], cb[i], cr[i]))
+        .collect()
+}
+
+///
+/// Convert pixels to YCbCr, averaging the chroma over
+/// `factors` areas. Alpha is ignored.
+///
+pub(crate) fn from_rgba(
+    pixels: &[Rgba],
+    (width, height): (usize, usize),
+    factors: (usize, usize),
+    range: ColorRange,
+) -> YuvPlanes {
+    let mut full = [0, 1, 2].map(|_| Vec::with_capacity(pixels.len()));
+    for &[r, g, b, _] in pixels {
+        for (plane, value) in full.iter_mut().zip(range.rgb_to_ycbcr(r, g, b)) {
+            plane.push(value);
+        }
+    }
+
+    let [luma, cb, cr] = full;
+    if factors == (1, 1) {
+        return YuvPlanes { luma, cb, cr };
+    }
+    let size = chroma_size((width, height), factors);
+    let [cb, cr] = [cb, cr].map(|plane| {
+        sample_plane(&plane, (width, height), factors, size)
+            .into_iter()
+            .map(|value| value.round() as u8)
+            .collect()
+    });
+    YuvPlanes { luma, cb, cr }
+}
+
+///
+/// Resample a component plane to the full image size.
+///
+/// Subsampled components are interpolated linearly between the
+/// centres of neighbouring samples (libjpeg's "fancy" upsampling),
+/// replicating edge samples.
+///
+pub(crate) fn upsample(
+    plane: &[u8],
+    stride: usize,
+    (plane_width, plane_height): (usize, usize),
+    (h, v): (usize, usize),
+    (h_max, v_max): (usize, usize),
+    (width, height): (usize, usize),
+) -> Vec<u8> {
+    let mut out = Vec::with_capacity(width * height);
+
+    if h == h_max && v == v_max {
+        for y in 0..height {
+            out.extend_from_slice(&plane[y * stride..y * stride + width]);
+        }
+        return out;
+    }
+
+    let source = |position: usize, factor: usize, max: usize, size: usize| {
+        let centre = (position as f32 + 0.5) * factor as f32 / max as f32 - 0.5;
+        let centre = centre.clamp(0.0, (size - 1) as f32);
+        let low = centre.floor() as usize;
+        (low, (low + 1).min(size - 1), centre - low as f32)
+    };
+
+    let columns: Vec<(usize, usize, f32)> = (0..width)
+        .map(|x| source(x, h, h_max, plane_width))
+        .collect();
+
+    for y in 0..height {
+        let (y0, y1, fy) = source(y, v, v_max, plane_height);
+        let (row0, row1) = (&plane[y0 * stride..], &plane[y1 * stride..]);
+        for &(x0, x1, fx) in &columns {
+            let top = row0[x0] as f32 * (1.0 - fx) + row0[x1] as f32 * fx;
+            let bottom = row1[x0] as f32 * (1.0 - fx) + row1[x1] as f32 * fx;
+            let value = top * (1.0 - fy) + bottom * fy;
+            out.push(value.round() as u8);
+        }
+    }
+
+    out
+}
+
+///
+/// Sample a full resolution plane down to `out_width` x `out_height`,
+/// averaging `h_factor` x `v_factor` areas. Edge pixels are replicated
+/// into any padding, such as JPEG's whole MCUs.
+///
+pub(crate) fn sample_plane(
+    plane: &[u8],
+    (width, height): (usize, usize),
+    (h_factor, v_factor): (usize, usize),
+    (out_width, out_height): (usize, usize),
+) -> Vec<f32> {
+    let area = (h_factor * v_factor) as f32;
+    let mut out = Vec::with_capacity(out_width * out_height);
+    for y in 0..out_height {
+        for x in 0..out_width {
+            let mut sum = 0u32;
+            for dy in 0..v_factor {
+                let row = (y * v_factor + dy).min(height - 1) * width;
+                for dx in 0..h_factor {
+                    sum += plane[row + (x * h_factor + dx).min(width - 1)] as u32;
+                }
+            }
+            out.push(sum as f32 / area);
+        }
+    }
+    out
+}
@@ -9,6 +9,8 @@ pub enum ColorSpace {
     RGBA,
     BGR,
     BGRA,
+    GRAY,
+    GRAYA,
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -30,6 +32,8 @@ impl ColorSpace {
             ColorSpace::RGBA => 4,
             ColorSpace::BGR => 3,
             ColorSpace::BGRA => 4,
+            ColorSpace::GRAY => 1,
+            ColorSpace::GRAYA => 2,
         }
     }
 
@@ -48,6 +52,77 @@ impl ColorSpace {
     }
 }
 
+///
+/// Convert full range (JFIF) BT.601 YCbCr to RGB.
+///
+pub fn ycbcr_to_rgb(y: u8, cb: u8, cr: u8) -> [u8; 3] {
+    ColorRange::Full.ycbcr_to_rgb(y, cb, cr)
+}
+
+///
+/// Convert RGB to full range (JFIF) BT.601 YCbCr.
+///
+pub fn rgb_to_ycbcr(r: u8, g: u8, b: u8) -> [u8; 3] {
+    ColorRange::Full.rgb_to_ycbcr(r, g, b)
+}
+
+///
+/// The range of the samples of BT.601 YCbCr.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorRange {
+    /// Luma in 16 - 235 and chroma in 16 - 240, as in most video.
+    #[default]
+    Limited,
+    /// Every sample in 0 - 255, as in JPEG.
+    Full,
+}
+
+impl ColorRange {
+    ///
+    /// Scale of luma and chroma relative to full range, and the
+    /// offset of black.
+    ///
+    fn scale(&self) -> (f32, f32, f32) {
+        match self {
+            ColorRange::Limited => (219.0 / 255.0, 224.0 / 255.0, 16.0),
+            ColorRange::Full => (1.0, 1.0, 0.0),
+        }
+    }
+
+    pub fn ycbcr_to_rgb(&self, y: u8, cb: u8, cr: u8) -> [u8; 3] {
+        let (luma_scale, chroma_scale, black) = self.scale();
+        let y = (y as f32 - black) / luma_scale;
+        let cb = (cb as f32 - 128.0) / chroma_scale;
+        let cr = (cr as f32 - 128.0) / chroma_scale;
+
+        let r = y + 1.402 * cr;
+        let g = y - 0.344_136 * cb - 0.714_136 * cr;
+        let b = y + 1.772 * cb;
+
+        [r, g, b].map(|value| value.round().clamp(0.0, 255.0) as u8)
+    }
+
+    pub fn rgb_to_ycbcr(&self, r: u8, g: u8, b: u8) -> [u8; 3] {
+        let (luma_scale, chroma_scale, black) = self.scale();
+        let (r, g, b) = (r as f32, g as f32, b as f32);
+
+        let y = (0.299 * r + 0.587 * g + 0.114 * b) * luma_scale + black;
+        let cb = (-0.168_736 * r - 0.331_264 * g + 0.5 * b) * chroma_scale + 128.0;
+        let cr = (0.5 * r - 0.418_688 * g - 0.081_312 * b) * chroma_scale + 128.0;
+
+        [y, cb, cr].map(|value| value.round().clamp(0.0, 255.0) as u8)
+    }
+
+    ///
+    /// Convert luma alone to a grey level.
+    ///
+    pub fn luma_to_gray(&self, y: u8) -> u8 {
+        let (luma_scale, _, black) = self.scale();
+        ((y as f32 - black) / luma_scale).round().clamp(0.0, 255.0) as u8
+    }
+}
+
 impl Color {
     pub fn new(red: u8, green: u8, blue: u8, alpha: f32) -> Self {
         let alpha = (alpha * 255.0) as u8;
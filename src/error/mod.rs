@@ -1,4 +1,4 @@
-use std::io::{self, ErrorKind};
+use std::io;
 
 #[derive(Debug)]
 #[allow(clippy::enum_variant_names)]
@@ -10,6 +10,14 @@ pub enum Error {
     InvalidChannel(String),
     NotImplemented(String),
     ColorSpaceError(String),
+    UnsupportedFormat(String),
+}
+
+impl Error {
+    /// Shorthand for an `ImageDecodeError` caused by malformed input data.
+    pub(crate) fn decode(message: impl Into<String>) -> Self {
+        Error::ImageDecodeError(io::Error::new(io::ErrorKind::InvalidData, message.into()))
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -19,9 +27,10 @@ impl std::fmt::Display for Error {
             Error::ImageDecodeError(error) => write!(f, "Image decode error: {}", error),
             Error::ImageEncodeError(error) => write!(f, "Image encode error: {}", error),
             Error::IndexOutOfBounds(details) => write!(f, "Index out of bounds: {}", details),
-            Error::InvalidChannel(_) => todo!(),
-            Error::NotImplemented(_) => todo!(),
-            Error::ColorSpaceError(_) => todo!(),
+            Error::InvalidChannel(details) => write!(f, "Invalid channel: {}", details),
+            Error::NotImplemented(details) => write!(f, "Not implemented: {}", details),
+            Error::ColorSpaceError(details) => write!(f, "Color space error: {}", details),
+            Error::UnsupportedFormat(details) => write!(f, "Unsupported format: {}", details),
         }
     }
 }
@@ -32,10 +41,11 @@ impl std::error::Error for Error {
             Error::IOError(error) => Some(error),
             Error::ImageDecodeError(error) => Some(error),
             Error::ImageEncodeError(error) => Some(error),
-            Error::IndexOutOfBounds(_) => None,
-            Error::InvalidChannel(_) => todo!(),
-            Error::NotImplemented(_) => todo!(),
-            Error::ColorSpaceError(_) => todo!(),
+            Error::IndexOutOfBounds(_)
+            | Error::InvalidChannel(_)
+            | Error::NotImplemented(_)
+            | Error::ColorSpaceError(_)
+            | Error::UnsupportedFormat(_) => None,
         }
     }
 }
@@ -52,10 +62,11 @@ impl From<Error> for io::Error {
             Error::IOError(error) => error,
             Error::ImageDecodeError(error) => error,
             Error::ImageEncodeError(error) => error,
-            Error::IndexOutOfBounds(details) => io::Error::new(ErrorKind::Other, details),
-            Error::InvalidChannel(_) => todo!(),
-            Error::NotImplemented(_) => todo!(),
-            Error::ColorSpaceError(_) => todo!(),
+            Error::IndexOutOfBounds(details)
+            | Error::InvalidChannel(details)
+            | Error::NotImplemented(details)
+            | Error::ColorSpaceError(details)
+            | Error::UnsupportedFormat(details) => io::Error::other(details),
         }
     }
 }
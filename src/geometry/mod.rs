@@ -19,7 +19,7 @@ pub use shape::Shape;
 /// # Returns
 ///
 /// * usize containing Index if within bounds,
-///     otherwise Error
+///   otherwise Error
 ///
 pub fn get_index_from_xywh(
     x: usize,
@@ -49,7 +49,7 @@ pub fn get_index_from_xywh(
 /// # Returns
 ///
 /// * usize containing Index if within bounds,
-///     otherwise Error
+///   otherwise Error
 ///
 pub fn get_index_from_xyshape(x: usize, y: usize, shape: &Shape) -> Result<usize, Error> {
     get_index_from_xywh(x, y, shape.width, shape.height, shape.ndim)
@@ -66,7 +66,7 @@ pub fn get_index_from_xyshape(x: usize, y: usize, shape: &Shape) -> Result<usize
 /// # Returns
 ///
 /// * usize containing Index if within bounds,
-///     otherwise Error
+///   otherwise Error
 ///
 pub fn get_index_from_point_and_shape(point: Point, shape: &Shape) -> Result<usize, Error> {
     get_index_from_xyshape(point.x, point.y, shape)
@@ -48,7 +48,7 @@ impl Point {
     ///
     /// # Arguments
     /// * `rect` - The Rect in which this point needs
-    ///     to be relocated
+    ///   to be relocated
     /// * `angle` - The angle of rotation.
     ///
     /// # Returns
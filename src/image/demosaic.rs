@@ -0,0 +1,180 @@
+use super::Image;
+use crate::color::ColorSpace;
+use crate::error::Error;
+use crate::geometry::Shape;
+use crate::traits::*;
+
+/// Samples past each edge that the 5x5 kernels reach.
+const BORDER: usize = 2;
+
+/// A 5x5 kernel centred on the pixel, with weights summing to 16.
+type Kernel = [[i32; 5]; 5];
+
+/// Bilinear: the average of the nearest samples of the colour.
+const BILINEAR_GREEN: Kernel = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 4, 0, 0],
+    [0, 4, 0, 4, 0],
+    [0, 0, 4, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+const BILINEAR_ROW: Kernel = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 8, 0, 8, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+const BILINEAR_COLUMN: Kernel = [
+    [0, 0, 0, 0, 0],
+    [0, 0, 8, 0, 0],
+    [0, 0, 0, 0, 0],
+    [0, 0, 8, 0, 0],
+    [0, 0, 0, 0, 0],
+];
+const BILINEAR_DIAGONAL: Kernel = [
+    [0, 0, 0, 0, 0],
+    [0, 4, 0, 4, 0],
+    [0, 0, 0, 0, 0],
+    [0, 4, 0, 4, 0],
+    [0, 0, 0, 0, 0],
+];
+
+/// Malvar-He-Cutler: the same averages plus a share of the
+/// Laplacian of the colour sampled at the pixel. These are the
+/// paper's filters, doubled.
+const MALVAR_GREEN: Kernel = [
+    [0, 0, -2, 0, 0],
+    [0, 0, 4, 0, 0],
+    [-2, 4, 8, 4, -2],
+    [0, 0, 4, 0, 0],
+    [0, 0, -2, 0, 0],
+];
+const MALVAR_ROW: Kernel = [
+    [0, 0, 1, 0, 0],
+    [0, -2, 0, -2, 0],
+    [-2, 8, 10, 8, -2],
+    [0, -2, 0, -2, 0],
+    [0, 0, 1, 0, 0],
+];
+const MALVAR_COLUMN: Kernel = [
+    [0, 0, -2, 0, 0],
+    [0, -2, 8, -2, 0],
+    [1, 0, 10, 0, 1],
+    [0, -2, 8, -2, 0],
+    [0, 0, -2, 0, 0],
+];
+const MALVAR_DIAGONAL: Kernel = [
+    [0, 0, -3, 0, 0],
+    [0, 4, 0, 4, 0],
+    [-3, 0, 12, 0, -3],
+    [0, 4, 0, 4, 0],
+    [0, 0, -3, 0, 0],
+];
+
+///
+/// Mirror an index outside `0..size` back inside, without repeating
+/// the edge sample, so it lands on a sample of the same colour.
+///
+fn reflect(index: isize, size: usize) -> usize {
+    let last = size as isize - 1;
+    let mut index = index;
+    while index < 0 || index > last {
+        index = if index < 0 { -index } else { 2 * last - index };
+    }
+    index as usize
+}
+
+impl Demosaicable<DemosaicParams> for Image {
+    ///
+    /// Interpolate the missing colours of a single channel Bayer
+    /// mosaic, turning it into an RGB image of the same size.
+    ///
+    /// Samples beyond the edges are mirrored, which keeps the
+    /// pattern intact, so border pixels are interpolated the same
+    /// way as the rest.
+    ///
+    fn demosaic(&mut self, params: &DemosaicParams) -> Result<(), Error> {
+        if self.colorspace != ColorSpace::GRAY || self.shape.ndim != 1 {
+            return Err(Error::ColorSpaceError(format!(
+                "A Bayer mosaic has one channel, got {:?}",
+                self.colorspace
+            )));
+        }
+        let (width, height) = (self.width(), self.height());
+        if width < 2 || height < 2 {
+            return Err(Error::IndexOutOfBounds(format!(
+                "A Bayer mosaic needs at least 2x2 pixels, got {width}x{height}"
+            )));
+        }
+
+        let padded_width = width + 2 * BORDER;
+        let padded: Vec<i32> = (0..height + 2 * BORDER)
+            .flat_map(|y| {
+                let y = reflect(y as isize - BORDER as isize, height);
+                (0..padded_width).map(move |x| (y, reflect(x as isize - BORDER as isize, width)))
+            })
+            .map(|(y, x)| self.data[y * width + x] as i32)
+            .collect();
+
+        let [green, row, column, diagonal] = match params.method {
+            DemosaicMethod::Bilinear => [
+                BILINEAR_GREEN,
+                BILINEAR_ROW,
+                BILINEAR_COLUMN,
+                BILINEAR_DIAGONAL,
+            ],
+            DemosaicMethod::MalvarHeCutler => {
+                [MALVAR_GREEN, MALVAR_ROW, MALVAR_COLUMN, MALVAR_DIAGONAL]
+            }
+        }
+        .map(|kernel| {
+            // Offsets into `padded` from the window's top left, and weights.
+            let mut taps = Vec::new();
+            for (dy, weights) in kernel.iter().enumerate() {
+                for (dx, &weight) in weights.iter().enumerate() {
+                    if weight != 0 {
+                        taps.push((dy * padded_width + dx, weight));
+                    }
+                }
+            }
+            taps
+        });
+
+        let pattern = params.pattern;
+        let mut data = Vec::with_capacity(width * height * 3);
+        for y in 0..height {
+            for x in 0..width {
+                let window = y * padded_width + x;
+                let sampled = pattern.channel(x, y);
+                let mut rgb = [0u8; 3];
+                for (channel, value) in rgb.iter_mut().enumerate() {
+                    if channel == sampled {
+                        *value = self.data[y * width + x];
+                        continue;
+                    }
+                    let taps = if channel == 1 {
+                        &green
+                    } else if sampled != 1 {
+                        &diagonal
+                    } else if pattern.channel(x + 1, y) == channel {
+                        &row
+                    } else {
+                        &column
+                    };
+                    let sum: i32 = taps
+                        .iter()
+                        .map(|&(offset, weight)| padded[window + offset] * weight)
+                        .sum();
+                    *value = ((sum + 8) >> 4).clamp(0, 255) as u8;
+                }
+                data.extend_from_slice(&rgb);
+            }
+        }
+
+        self.data = data;
+        self.shape = Shape::new(width, height, Some(3));
+        self.colorspace = ColorSpace::RGB;
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+use super::Image;
+use crate::error::Error;
+use crate::traits::*;
+
+impl Flippable<FlipType> for Image {
+    fn flip(&mut self, value: FlipType) -> Result<(), Error> {
+        let channels = self.colorspace.channels();
+        let row_size = self.width() * channels;
+
+        match value {
+            FlipType::Horizontal => {
+                for row in self.data.chunks_exact_mut(row_size) {
+                    for x in 0..self.shape.width / 2 {
+                        let mirrored = self.shape.width - 1 - x;
+                        for c in 0..channels {
+                            row.swap(x * channels + c, mirrored * channels + c);
+                        }
+                    }
+                }
+            }
+            FlipType::Vertical => {
+                let height = self.height();
+                for y in 0..height / 2 {
+                    let (top, bottom) = self.data.split_at_mut((height - 1 - y) * row_size);
+                    top[y * row_size..(y + 1) * row_size].swap_with_slice(&mut bottom[..row_size]);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
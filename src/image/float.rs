@@ -0,0 +1,114 @@
+use super::Image;
+use crate::color::ColorSpace;
+use crate::geometry::{self, Point, Shape};
+
+///
+/// An image of `f32` samples, laid out like `Image`.
+///
+/// Holds data that does not fit 8 bits, such as high dynamic range
+/// radiance, where 1.0 is nominal white but brighter values are
+/// kept. Convert with `from_image` and `to_image`.
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct FloatImage {
+    shape: Shape,
+    data: Vec<f32>,
+    colorspace: ColorSpace,
+}
+
+impl FloatImage {
+    pub fn new(shape: Shape, colorspace: ColorSpace) -> Self {
+        FloatImage {
+            shape,
+            data: vec![0.0; shape.size()],
+            colorspace,
+        }
+    }
+
+    pub fn from_data(data: Vec<f32>, shape: Shape, colorspace: ColorSpace) -> Self {
+        assert_eq!(data.len(), shape.size());
+        FloatImage {
+            shape,
+            data,
+            colorspace,
+        }
+    }
+
+    ///
+    /// Scale the samples of an 8-bit image to 0.0 - 1.0.
+    ///
+    pub fn from_image(image: &Image) -> Self {
+        let data = image
+            .slice(0, image.size())
+            .iter()
+            .map(|&sample| sample as f32 / 255.0)
+            .collect();
+        FloatImage::from_data(data, image.shape(), image.colorspace())
+    }
+
+    ///
+    /// Scale the samples to 0 - 255, clamping anything outside
+    /// 0.0 - 1.0. Apply any exposure or tone mapping beforehand.
+    ///
+    pub fn to_image(&self) -> Image {
+        let data = self
+            .data
+            .iter()
+            .map(|&sample| (sample.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+        Image::from_data(data, self.shape, self.colorspace)
+    }
+
+    pub fn slice(&self, start: usize, end: usize) -> &[f32] {
+        &self.data[start..end]
+    }
+
+    pub fn mut_slice(&mut self, start: usize, end: usize) -> &mut [f32] {
+        &mut self.data[start..end]
+    }
+
+    pub fn width(&self) -> usize {
+        self.shape.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.shape.height
+    }
+
+    pub fn size(&self) -> usize {
+        self.shape.size()
+    }
+
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    pub fn colorspace(&self) -> ColorSpace {
+        self.colorspace
+    }
+
+    fn get_index(&self, point: &Point) -> usize {
+        geometry::get_index_from_xywh(
+            point.x,
+            point.y,
+            self.width(),
+            self.height(),
+            self.shape.ndim,
+        )
+        .unwrap()
+    }
+
+    ///
+    /// Return a slice of 1 pixel (including all channels)
+    ///
+    pub fn get_pixel(&self, point: &Point) -> &[f32] {
+        let index = self.get_index(point);
+        &self.data[index..index + self.shape.ndim]
+    }
+
+    /// Same as `get_pixel` but just a mutable reference
+    pub fn get_mut_pixel(&mut self, point: &Point) -> &mut [f32] {
+        let index = self.get_index(point);
+        &mut self.data[index..index + self.shape.ndim]
+    }
+}
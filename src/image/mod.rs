@@ -1,7 +1,14 @@
+mod demosaic;
 mod draw;
+mod flip;
+mod float;
 mod ops;
+mod orient;
 mod resize;
 mod rotate;
+
+pub use float::FloatImage;
+
 use log::debug;
 
 use crate::color::{Color, ColorSpace};
@@ -85,6 +92,14 @@ impl Image {
         self.shape.size()
     }
 
+    pub fn shape(&self) -> Shape {
+        self.shape
+    }
+
+    pub fn colorspace(&self) -> ColorSpace {
+        self.colorspace
+    }
+
     pub fn crop(&self, topleft: Point, shape: Shape) -> Self {
         let bottomright = topleft + shape;
 
@@ -125,7 +140,7 @@ impl Image {
     /// # Returns
     ///
     /// * usize containing Index if within bounds,
-    ///     otherwise Error
+    ///   otherwise Error
     ///
     pub fn get_index(&self, point: &Point) -> Result<usize, Error> {
         self.get_index_from_xy(point.x, point.y)
@@ -142,7 +157,7 @@ impl Image {
     /// # Returns
     ///
     /// * usize containing Index if within bounds,
-    ///     otherwise Error
+    ///   otherwise Error
     ///
     pub fn get_index_from_xy(&self, x: usize, y: usize) -> Result<usize, Error> {
         geometry::get_index_from_xywh(
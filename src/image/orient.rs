@@ -0,0 +1,33 @@
+use super::Image;
+use crate::codec::exif::Orientation;
+use crate::error::Error;
+use crate::traits::*;
+
+impl Image {
+    ///
+    /// Transform the pixels so an image stored with the given
+    /// EXIF orientation is displayed upright.
+    ///
+    /// # Arguments
+    ///
+    /// * `orientation` - Usually `Exif::orientation` of the source file
+    ///
+    pub fn apply_orientation(&mut self, orientation: Orientation) -> Result<(), Error> {
+        match orientation {
+            Orientation::Normal => Ok(()),
+            Orientation::FlipHorizontal => self.flip(FlipType::Horizontal),
+            Orientation::Rotate180 => self.rotate(RotationType::Clockwise180),
+            Orientation::FlipVertical => self.flip(FlipType::Vertical),
+            Orientation::Transpose => {
+                self.rotate(RotationType::Clockwise90)?;
+                self.flip(FlipType::Horizontal)
+            }
+            Orientation::Rotate90 => self.rotate(RotationType::Clockwise90),
+            Orientation::Transverse => {
+                self.rotate(RotationType::Clockwise90)?;
+                self.flip(FlipType::Vertical)
+            }
+            Orientation::Rotate270 => self.rotate(RotationType::Clockwise270),
+        }
+    }
+}
@@ -26,18 +26,17 @@ impl Rotatable<RotationType> for Image {
             RotationType::Custom(_) => todo!(),
         };
 
-        if new_shape == self.shape {
-            for x in 0..self.width() / 2 {
-                for y in 0..self.height() {
-                    let p1 = Point::new(x, y);
-                    let p2 = p1.relocate(&self.shape, value.degree());
-
-                    let idx_a = self.get_index(&p1).unwrap();
-                    let idx_b = geometry::get_index_from_point_and_shape(p2, &new_shape).unwrap();
-
-                    for c in 0..self.colorspace.channels() {
-                        self.swap(idx_a + c, idx_b + c);
-                    }
+        if matches!(
+            value,
+            RotationType::Clockwise180 | RotationType::Anticlockwise180
+        ) {
+            // A half turn reverses the order of the pixels.
+            let channels = self.colorspace.channels();
+            let pixels = self.width() * self.height();
+            for i in 0..pixels / 2 {
+                let (idx_a, idx_b) = (i * channels, (pixels - 1 - i) * channels);
+                for c in 0..channels {
+                    self.swap(idx_a + c, idx_b + c);
                 }
             }
         } else {
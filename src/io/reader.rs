@@ -1,22 +1,90 @@
 use std::fs::File;
+use std::io::{BufReader, Read};
 
 use crate::{
-    codec::{decoders::png::decode, Codex},
+    codec::{decoders, detect, exif, Codex},
     error::Error,
     image::Image,
 };
 
 pub trait Reader {
     fn read(path: &str, codex: Codex) -> Result<Image, Error>;
+
+    ///
+    /// Decode from any byte source, such as a socket or stdin.
+    /// The stream is read to its end before decoding.
+    ///
+    fn read_from<R: Read>(reader: &mut R, codex: Codex) -> Result<Image, Error>;
+
+    ///
+    /// Decode from memory, e.g. data embedded with `include_bytes!`.
+    ///
+    fn read_bytes(data: &[u8], codex: Codex) -> Result<Image, Error>;
+
+    ///
+    /// Same as `read`, but the format is detected from the file's
+    /// signature instead of being passed in. See `codec::detect`.
+    ///
+    fn read_auto(path: &str) -> Result<Image, Error>;
+
+    ///
+    /// Same as `read_bytes`, with the format detected from `data`.
+    ///
+    fn read_bytes_auto(data: &[u8]) -> Result<Image, Error>;
+
+    ///
+    /// Same as `read_auto`, then applies the file's EXIF orientation
+    /// so photos come out upright. See `Image::apply_orientation`.
+    ///
+    fn read_oriented(path: &str) -> Result<Image, Error>;
+
+    ///
+    /// Same as `read_bytes_auto`, then applies the EXIF orientation.
+    ///
+    fn read_bytes_oriented(data: &[u8]) -> Result<Image, Error>;
 }
 
 impl Reader for Image {
     fn read(path: &str, codex: Codex) -> Result<Image, Error> {
-        let mut file = File::open(path)?;
+        let file = File::open(path)?;
+        Self::read_from(&mut BufReader::new(file), codex)
+    }
+
+    fn read_from<R: Read>(reader: &mut R, codex: Codex) -> Result<Image, Error> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::read_bytes(&data, codex)
+    }
+
+    fn read_bytes(data: &[u8], codex: Codex) -> Result<Image, Error> {
+        decoders::decode(data, codex)
+    }
+
+    fn read_auto(path: &str) -> Result<Image, Error> {
+        let data = std::fs::read(path)?;
+        Self::read_bytes_auto(&data)
+    }
+
+    fn read_bytes_auto(data: &[u8]) -> Result<Image, Error> {
+        Self::read_bytes(data, detect(data)?)
+    }
+
+    fn read_oriented(path: &str) -> Result<Image, Error> {
+        let data = std::fs::read(path)?;
+        Self::read_bytes_oriented(&data)
+    }
+
+    fn read_bytes_oriented(data: &[u8]) -> Result<Image, Error> {
+        let mut image = Self::read_bytes_auto(data)?;
 
-        match codex {
-            Codex::PNG => Ok(decode(&mut file)?),
-            Codex::JPG => todo!(),
+        // Unreadable EXIF is treated as having no orientation.
+        let orientation = exif::read_exif(data)
+            .ok()
+            .flatten()
+            .and_then(|exif| exif.orientation());
+        if let Some(orientation) = orientation {
+            image.apply_orientation(orientation)?;
         }
+        Ok(image)
     }
 }
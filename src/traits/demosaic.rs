@@ -0,0 +1,52 @@
+use derive_new::new;
+
+use crate::error::Error;
+
+///
+/// Colours of the top left 2x2 block of a Bayer mosaic,
+/// row by row, which repeats across the sensor.
+///
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum BayerPattern {
+    RGGB,
+    BGGR,
+    GRBG,
+    GBRG,
+}
+
+impl BayerPattern {
+    ///
+    /// The channel (0 red, 1 green, 2 blue) sampled at `(x, y)`.
+    ///
+    pub fn channel(&self, x: usize, y: usize) -> usize {
+        let block = match self {
+            BayerPattern::RGGB => [0, 1, 1, 2],
+            BayerPattern::BGGR => [2, 1, 1, 0],
+            BayerPattern::GRBG => [1, 0, 2, 1],
+            BayerPattern::GBRG => [1, 2, 0, 1],
+        };
+        block[(y % 2) * 2 + x % 2]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DemosaicMethod {
+    /// Average the nearest samples of each missing colour.
+    /// Fast, but edges get colour fringes and lose sharpness.
+    Bilinear,
+    /// Bilinear, corrected by the gradient of the colour sampled
+    /// at the pixel (Malvar, He and Cutler, 2004). Sharper, with
+    /// fewer fringes, for little extra cost.
+    MalvarHeCutler,
+}
+
+#[derive(Debug, Clone, Copy, new)]
+pub struct DemosaicParams {
+    pub pattern: BayerPattern,
+    pub method: DemosaicMethod,
+}
+
+pub trait Demosaicable<T> {
+    fn demosaic(&mut self, params: &T) -> Result<(), Error>;
+}
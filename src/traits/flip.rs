@@ -0,0 +1,13 @@
+use crate::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FlipType {
+    /// Mirror left to right.
+    Horizontal,
+    /// Mirror top to bottom.
+    Vertical,
+}
+
+pub trait Flippable<T> {
+    fn flip(&mut self, value: T) -> Result<(), Error>;
+}
@@ -10,11 +10,15 @@
  * other formats.
  *
  */
+mod demosaic;
 mod draw;
+mod flip;
 mod resize;
 mod rotate;
 
 // TODO: Find a better way to import.
+pub use demosaic::*;
 pub use draw::*;
+pub use flip::*;
 pub use resize::*;
 pub use rotate::*;
@@ -0,0 +1,228 @@
+use rusty_vision as rv;
+use rv::codec::decoders::bmp::{self, BmpDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::Encoder;
+use rv::codec::{detect, Codex};
+use rv::color::ColorSpace;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::Reader;
+
+///
+/// A `BITMAPINFOHEADER` of the given size. Fields added by
+/// later header versions are appended by the caller.
+///
+fn info_header(size: u32, width: i32, height: i32, bits: u16, compression: u32) -> Vec<u8> {
+    let mut header = Vec::new();
+    header.extend_from_slice(&size.to_le_bytes());
+    header.extend_from_slice(&width.to_le_bytes());
+    header.extend_from_slice(&height.to_le_bytes());
+    header.extend_from_slice(&1u16.to_le_bytes());
+    header.extend_from_slice(&bits.to_le_bytes());
+    header.extend_from_slice(&compression.to_le_bytes());
+    header.extend_from_slice(&[0; 20]);
+    header
+}
+
+///
+/// Wrap a DIB header, colour table and pixel data in a file header.
+///
+fn bitmap(header: &[u8], palette: &[[u8; 3]], pixels: &[u8]) -> Vec<u8> {
+    let offset = 14 + header.len() + palette.len() * 4;
+    let mut out = b"BM".to_vec();
+    out.extend_from_slice(&((offset + pixels.len()) as u32).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&(offset as u32).to_le_bytes());
+    out.extend_from_slice(header);
+    for &[r, g, b] in palette {
+        out.extend_from_slice(&[b, g, r, 0]);
+    }
+    out.extend_from_slice(pixels);
+    out
+}
+
+const PALETTE: [[u8; 3]; 4] = [[0, 0, 0], [255, 0, 0], [0, 255, 0], [0, 0, 255]];
+
+fn rgb(indices: &[usize]) -> Vec<u8> {
+    indices.iter().flat_map(|&i| PALETTE[i]).collect()
+}
+
+///
+/// Rows that differ from each other and end in a white pixel, so
+/// reading the row padding as pixels shifts a visible edge.
+///
+fn padded_rows(width: usize, height: usize, colorspace: ColorSpace) -> Image {
+    let channels = colorspace.channels();
+    let mut data = Vec::with_capacity(width * height * channels);
+    for y in 0..height {
+        for x in 0..width {
+            for c in 0..channels {
+                let value = if x == width - 1 {
+                    255
+                } else {
+                    (y * 40 + x * 9 + c * 3) % 250
+                };
+                data.push(value as u8);
+            }
+        }
+    }
+    Image::from_data(data, Shape::new(width, height, Some(channels)), colorspace)
+}
+
+#[test]
+fn test_roundtrip_rgb() {
+    // An odd width exercises the row padding.
+    let image = padded_rows(13, 7, ColorSpace::RGB);
+    let bytes = image.encode(Codex::BMP).unwrap();
+    assert_eq!(detect(&bytes).unwrap(), Codex::BMP);
+    // 24-bit rows of 39 bytes padded to 40.
+    assert_eq!(bytes.len(), 54 + 40 * 7);
+
+    let decoded = Image::read_bytes(&bytes, Codex::BMP).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+    assert_eq!(decoded.shape(), image.shape());
+    assert_eq!(
+        decoded.slice(0, decoded.size()),
+        image.slice(0, image.size())
+    );
+}
+
+#[test]
+fn test_roundtrip_rgba() {
+    let image = padded_rows(5, 3, ColorSpace::RGBA);
+    let bytes = image.encode(Codex::BMP).unwrap();
+    assert_eq!(u16::from_le_bytes([bytes[28], bytes[29]]), 32);
+
+    let decoded = Image::read_bytes_auto(&bytes).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGBA);
+    assert_eq!(
+        decoded.slice(0, decoded.size()),
+        image.slice(0, image.size())
+    );
+}
+
+#[test]
+fn test_encode_gray_and_bgr() {
+    // Rows of 9 bytes, padded to 12.
+    let gray = padded_rows(3, 2, ColorSpace::GRAY);
+    let decoded = BmpDecoder
+        .decode(&gray.encode(Codex::BMP).unwrap())
+        .unwrap();
+    let expected: Vec<u8> = gray
+        .slice(0, gray.size())
+        .iter()
+        .flat_map(|&g| [g, g, g])
+        .collect();
+    assert_eq!(decoded.slice(0, decoded.size()), expected.as_slice());
+
+    let bgr = Image::from_data(vec![1, 2, 3], Shape::new(1, 1, Some(3)), ColorSpace::BGR);
+    let decoded = BmpDecoder.decode(&bgr.encode(Codex::BMP).unwrap()).unwrap();
+    assert_eq!(decoded.slice(0, 3), &[3, 2, 1]);
+}
+
+#[test]
+fn test_decode_1bit_bottom_up() {
+    // Rows are stored bottom-up, each padded to 4 bytes.
+    let pixels = [0b0110_0000, 0, 0, 0, 0b1001_0000, 0, 0, 0];
+    let bytes = bitmap(&info_header(40, 4, 2, 1, 0), &PALETTE[..2], &pixels);
+
+    let image = bmp::decode_bytes(&bytes).unwrap();
+    assert_eq!(image.shape(), Shape::new(4, 2, Some(3)));
+    assert_eq!(
+        image.slice(0, image.size()),
+        rgb(&[1, 0, 0, 1, 0, 1, 1, 0]).as_slice()
+    );
+}
+
+#[test]
+fn test_decode_4bit_top_down() {
+    let pixels = [0x01, 0x20, 0, 0, 0x33, 0x10, 0, 0];
+    let bytes = bitmap(&info_header(40, 3, -2, 4, 0), &PALETTE, &pixels);
+
+    let image = bmp::decode(&mut bytes.as_slice()).unwrap();
+    assert_eq!(
+        image.slice(0, image.size()),
+        rgb(&[0, 1, 2, 3, 3, 1]).as_slice()
+    );
+}
+
+#[test]
+fn test_decode_rle8() {
+    let pixels = [
+        3, 1, // run of three 1s
+        0, 3, 2, 3, 2, 0, // absolute run of three, padded
+        0, 0, // end of line
+        0, 2, 1, 1, // delta one right, one up
+        2, 3, // run of two 3s
+        0, 1, // end of bitmap
+    ];
+    let bytes = bitmap(&info_header(40, 6, 3, 8, 1), &PALETTE, &pixels);
+
+    let image = BmpDecoder.decode(&bytes).unwrap();
+    // Skipped pixels are black, the bottom row comes first in the file.
+    let expected = rgb(&[
+        0, 3, 3, 0, 0, 0, //
+        0, 0, 0, 0, 0, 0, //
+        1, 1, 1, 2, 3, 2, //
+    ]);
+    assert_eq!(image.slice(0, image.size()), expected.as_slice());
+}
+
+#[test]
+fn test_decode_rle4() {
+    let pixels = [
+        5, 0x12, // alternating 1 and 2
+        0, 0, // end of line
+        0, 3, 0x30, 0x10, // absolute run of three nibbles
+        0, 1,
+    ];
+    let bytes = bitmap(&info_header(40, 5, 2, 4, 2), &PALETTE, &pixels);
+
+    let image = BmpDecoder.decode(&bytes).unwrap();
+    let expected = rgb(&[3, 0, 1, 0, 0, 1, 2, 1, 2, 1]);
+    assert_eq!(image.slice(0, image.size()), expected.as_slice());
+}
+
+#[test]
+fn test_decode_bitfields_565() {
+    let mut header = info_header(40, 2, 1, 16, 3);
+    for mask in [0xF800u32, 0x07E0, 0x001F] {
+        header.extend_from_slice(&mask.to_le_bytes());
+    }
+    let pixels = [0x00, 0xF8, 0xE0, 0x07];
+    let image = BmpDecoder.decode(&bitmap(&header, &[], &pixels)).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::RGB);
+    assert_eq!(image.slice(0, image.size()), &[255, 0, 0, 0, 255, 0]);
+}
+
+#[test]
+fn test_decode_v4_alpha() {
+    let mut header = info_header(108, 1, 1, 32, 3);
+    for mask in [0x0000_00FFu32, 0x0000_FF00, 0x00FF_0000, 0xFF00_0000] {
+        header.extend_from_slice(&mask.to_le_bytes());
+    }
+    header.extend_from_slice(b"BGRs");
+    header.extend_from_slice(&[0; 48]);
+
+    let image = BmpDecoder
+        .decode(&bitmap(&header, &[], &[10, 20, 30, 40]))
+        .unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    assert_eq!(image.slice(0, 4), &[10, 20, 30, 40]);
+}
+
+#[test]
+fn test_decode_invalid() {
+    let bytes = bitmap(&info_header(40, 4, 4, 24, 0), &[], &[0; 20]);
+    assert!(BmpDecoder.decode(&bytes).is_err());
+    assert!(BmpDecoder.decode(&bytes[..30]).is_err());
+    assert!(BmpDecoder.decode(b"not a bitmap").is_err());
+
+    // Dimensions far beyond the data are rejected before allocating.
+    for (width, height) in [(i32::MAX, i32::MAX), (60000, 60000)] {
+        let bytes = bitmap(&info_header(40, width, height, 24, 0), &[], &[]);
+        assert!(BmpDecoder.decode(&bytes).is_err());
+        let bytes = bitmap(&info_header(40, width, height, 8, 1), &PALETTE, &[0, 1]);
+        assert!(BmpDecoder.decode(&bytes).is_err());
+    }
+}
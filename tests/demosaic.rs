@@ -0,0 +1,166 @@
+use rusty_vision as rv;
+use rv::color::ColorSpace;
+use rv::error::Error;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::traits::{BayerPattern, DemosaicMethod, DemosaicParams, Demosaicable};
+
+const PATTERNS: [BayerPattern; 4] = [
+    BayerPattern::RGGB,
+    BayerPattern::BGGR,
+    BayerPattern::GRBG,
+    BayerPattern::GBRG,
+];
+const METHODS: [DemosaicMethod; 2] = [DemosaicMethod::Bilinear, DemosaicMethod::MalvarHeCutler];
+
+fn rgb_image<F>(width: usize, height: usize, color: F) -> Image
+where
+    F: Fn(usize, usize) -> [u8; 3],
+{
+    let data = (0..height)
+        .flat_map(|y| (0..width).map(move |x| (x, y)))
+        .flat_map(|(x, y)| color(x, y))
+        .collect();
+    Image::from_data(data, Shape::new(width, height, Some(3)), ColorSpace::RGB)
+}
+
+///
+/// Keep the one colour of each pixel that a sensor with `pattern`
+/// would have measured.
+///
+fn mosaic(image: &Image, pattern: BayerPattern) -> Image {
+    let width = image.width();
+    let data = image
+        .slice(0, image.size())
+        .chunks_exact(3)
+        .enumerate()
+        .map(|(i, pixel)| pixel[pattern.channel(i % width, i / width)])
+        .collect();
+    Image::from_data(
+        data,
+        Shape::new(width, image.height(), Some(1)),
+        ColorSpace::GRAY,
+    )
+}
+
+fn demosaic(image: &Image, pattern: BayerPattern, method: DemosaicMethod) -> Image {
+    let mut raw = mosaic(image, pattern);
+    raw.demosaic(&DemosaicParams::new(pattern, method)).unwrap();
+    raw
+}
+
+///
+/// Largest difference between the images, ignoring `margin` pixels
+/// at each edge.
+///
+fn max_error(a: &Image, b: &Image, margin: usize) -> u8 {
+    assert_eq!(a.shape(), b.shape());
+    let (width, height) = (a.width(), a.height());
+    let mut error = 0;
+    for y in margin..height - margin {
+        for x in margin..width - margin {
+            let i = (y * width + x) * 3;
+            for c in 0..3 {
+                error =
+                    error.max(a.slice(i + c, i + c + 1)[0].abs_diff(b.slice(i + c, i + c + 1)[0]));
+            }
+        }
+    }
+    error
+}
+
+fn mean_error(a: &Image, b: &Image) -> f64 {
+    let (a, b) = (a.slice(0, a.size()), b.slice(0, b.size()));
+    let total: u64 = a.iter().zip(b).map(|(x, y)| x.abs_diff(*y) as u64).sum();
+    total as f64 / a.len() as f64
+}
+
+#[test]
+fn test_flat_color() {
+    // Every pixel, borders included, recovers a constant colour.
+    let image = rgb_image(9, 6, |_, _| [200, 90, 30]);
+    for pattern in PATTERNS {
+        for method in METHODS {
+            let result = demosaic(&image, pattern, method);
+            assert_eq!(result.colorspace(), ColorSpace::RGB);
+            assert_eq!(result.shape(), image.shape());
+            assert_eq!(max_error(&result, &image, 0), 0, "{pattern:?} {method:?}");
+        }
+    }
+}
+
+#[test]
+fn test_linear_gradient() {
+    // Both methods reproduce linear ramps away from the edges,
+    // and stay close at the edges, where the ramp is mirrored.
+    let image = rgb_image(16, 12, |x, y| {
+        [
+            (10 + 8 * x) as u8,
+            (20 + 6 * y) as u8,
+            (5 * x + 7 * y) as u8,
+        ]
+    });
+    for pattern in PATTERNS {
+        for method in METHODS {
+            let result = demosaic(&image, pattern, method);
+            assert_eq!(max_error(&result, &image, 2), 0, "{pattern:?} {method:?}");
+            assert!(
+                max_error(&result, &image, 0) <= 16,
+                "{pattern:?} {method:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_edge_aware() {
+    // Sharp detail shared by every channel, as in most scenes.
+    let image = rgb_image(40, 40, |x, y| {
+        let value = ((x as f64 * 0.5).sin() * (y as f64 * 0.3).cos() * 80.0 + 120.0) as u8;
+        [value + 20, value, value - 30]
+    });
+    for pattern in PATTERNS {
+        let bilinear = mean_error(&demosaic(&image, pattern, DemosaicMethod::Bilinear), &image);
+        let malvar = mean_error(
+            &demosaic(&image, pattern, DemosaicMethod::MalvarHeCutler),
+            &image,
+        );
+        assert!(malvar < bilinear * 0.6, "{pattern:?} {malvar} {bilinear}");
+    }
+}
+
+#[test]
+fn test_patterns() {
+    // A 2x2 mosaic holds one sample of red and blue, two of green.
+    let raw = Image::from_data(
+        vec![250, 100, 100, 10],
+        Shape::new(2, 2, Some(1)),
+        ColorSpace::GRAY,
+    );
+    let expected = [
+        (BayerPattern::RGGB, [250, 100, 10]),
+        (BayerPattern::BGGR, [10, 100, 250]),
+        (BayerPattern::GRBG, [100, 250, 100]),
+        (BayerPattern::GBRG, [100, 250, 100]),
+    ];
+    for (pattern, top_left) in expected {
+        let mut image = raw.clone();
+        image
+            .demosaic(&DemosaicParams::new(pattern, DemosaicMethod::Bilinear))
+            .unwrap();
+        assert_eq!(image.slice(0, 3), &top_left, "{pattern:?}");
+    }
+}
+
+#[test]
+fn test_invalid() {
+    let params = DemosaicParams::new(BayerPattern::RGGB, DemosaicMethod::Bilinear);
+    let mut rgb = rgb_image(4, 4, |_, _| [0; 3]);
+    assert!(matches!(
+        rgb.demosaic(&params),
+        Err(Error::ColorSpaceError(_))
+    ));
+
+    let mut line = Image::from_data(vec![0; 4], Shape::new(4, 1, Some(1)), ColorSpace::GRAY);
+    assert!(line.demosaic(&params).is_err());
+}
@@ -0,0 +1,193 @@
+use rusty_vision as rv;
+use rv::codec::decoders::{jpeg, png};
+use rv::codec::encoders::Encoder;
+use rv::codec::exif::{read_exif, Exif, Ifd, Orientation, Value, TAG_MAKE};
+use rv::codec::Codex;
+use rv::color::ColorSpace;
+use rv::geometry::{Point, Shape};
+use rv::image::Image;
+use rv::io::Reader;
+use rv::traits::{FlipType, Flippable, Rotatable, RotationType};
+
+///
+/// A TIFF structure with Make, Orientation and an Exif sub-IFD
+/// holding DateTimeOriginal.
+///
+fn tiff(big_endian: bool, orientation: u16) -> Vec<u8> {
+    let u16b = |v: u16| {
+        if big_endian {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let u32b = |v: u32| {
+        if big_endian {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let entry = |out: &mut Vec<u8>, tag: u16, field_type: u16, count: u32, value: [u8; 4]| {
+        out.extend_from_slice(&u16b(tag));
+        out.extend_from_slice(&u16b(field_type));
+        out.extend_from_slice(&u32b(count));
+        out.extend_from_slice(&value);
+    };
+
+    let make = b"Rusty\0";
+    let date = b"2024:05:17 10:30:00\0";
+    // Header (8), IFD0 with 3 entries (2 + 36 + 4), then the data.
+    let exif_ifd = 8 + 42;
+    let make_offset = exif_ifd + 18;
+    let date_offset = make_offset + make.len() as u32;
+
+    let mut out = if big_endian {
+        b"MM".to_vec()
+    } else {
+        b"II".to_vec()
+    };
+    out.extend_from_slice(&u16b(42));
+    out.extend_from_slice(&u32b(8));
+
+    out.extend_from_slice(&u16b(3));
+    entry(&mut out, 0x010F, 2, make.len() as u32, u32b(make_offset));
+    let mut short = [0; 4];
+    short[..2].copy_from_slice(&u16b(orientation));
+    entry(&mut out, 0x0112, 3, 1, short);
+    entry(&mut out, 0x8769, 4, 1, u32b(exif_ifd));
+    out.extend_from_slice(&u32b(0));
+
+    out.extend_from_slice(&u16b(1));
+    entry(&mut out, 0x9003, 2, date.len() as u32, u32b(date_offset));
+    out.extend_from_slice(&u32b(0));
+
+    out.extend_from_slice(make);
+    out.extend_from_slice(date);
+    out
+}
+
+fn with_app1(jpeg: &[u8], tiff: &[u8]) -> Vec<u8> {
+    let mut out = jpeg[..2].to_vec();
+    out.extend_from_slice(&[0xFF, 0xE1]);
+    out.extend_from_slice(&(tiff.len() as u16 + 8).to_be_bytes());
+    out.extend_from_slice(b"Exif\0\0");
+    out.extend_from_slice(tiff);
+    out.extend_from_slice(&jpeg[2..]);
+    out
+}
+
+fn with_exif_chunk(png: &[u8], tiff: &[u8]) -> Vec<u8> {
+    // Right after the signature and IHDR.
+    let mut out = png[..33].to_vec();
+    let start = out.len();
+    out.extend_from_slice(&(tiff.len() as u32).to_be_bytes());
+    out.extend_from_slice(b"eXIf");
+    out.extend_from_slice(tiff);
+    let checksum = crc32fast::hash(&out[start + 4..]);
+    out.extend_from_slice(&checksum.to_be_bytes());
+    out.extend_from_slice(&png[33..]);
+    out
+}
+
+fn labelled(width: usize, height: usize) -> Image {
+    let data = (0..width * height).map(|i| i as u8 * 10).collect();
+    Image::from_data(data, Shape::new(width, height, Some(1)), ColorSpace::GRAY)
+}
+
+#[test]
+fn test_parse_exif() {
+    for big_endian in [false, true] {
+        let exif = Exif::parse(&tiff(big_endian, 6)).unwrap();
+        assert_eq!(exif.orientation(), Some(Orientation::Rotate90));
+        assert_eq!(exif.make(), Some("Rusty"));
+        assert_eq!(exif.date_time(), Some("2024:05:17 10:30:00"));
+        assert_eq!(
+            exif.get(Ifd::Primary, TAG_MAKE),
+            Some(&Value::Ascii("Rusty".to_string()))
+        );
+        assert_eq!(exif.get(Ifd::Exif, TAG_MAKE), None);
+    }
+
+    assert!(Exif::parse(b"XX\0*").is_err());
+    assert!(Exif::parse(&tiff(false, 6)[..20]).is_err());
+}
+
+#[test]
+fn test_exif_in_containers() {
+    let image = labelled(4, 3);
+    let block = tiff(true, 3);
+
+    let jpg = with_app1(&image.encode(Codex::JPG).unwrap(), &block);
+    let (_, metadata) = jpeg::decode_with_metadata(&mut &jpg[..]).unwrap();
+    assert_eq!(
+        metadata.exif.unwrap().orientation(),
+        Some(Orientation::Rotate180)
+    );
+    assert_eq!(
+        read_exif(&jpg).unwrap().unwrap().orientation(),
+        Some(Orientation::Rotate180)
+    );
+
+    let png = with_exif_chunk(&image.encode(Codex::PNG).unwrap(), &block);
+    let (_, metadata) = png::decode_with_metadata(&mut &png[..]).unwrap();
+    assert_eq!(metadata.exif.unwrap().make(), Some("Rusty"));
+    assert!(read_exif(&png).unwrap().is_some());
+
+    assert!(read_exif(&image.encode(Codex::PNG).unwrap())
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_flip_and_rotate() {
+    let mut image = labelled(3, 3);
+    image.rotate(RotationType::Clockwise90).unwrap();
+    assert_eq!(image.slice(0, 9), &[60, 30, 0, 70, 40, 10, 80, 50, 20]);
+
+    let mut image = labelled(3, 2);
+    image.rotate(RotationType::Clockwise180).unwrap();
+    assert_eq!(image.slice(0, 6), &[50, 40, 30, 20, 10, 0]);
+
+    let mut image = labelled(3, 2);
+    image.flip(FlipType::Horizontal).unwrap();
+    assert_eq!(image.slice(0, 6), &[20, 10, 0, 50, 40, 30]);
+    image.flip(FlipType::Vertical).unwrap();
+    assert_eq!(image.slice(0, 6), &[50, 40, 30, 20, 10, 0]);
+}
+
+#[test]
+fn test_read_oriented() {
+    for (width, height) in [(3, 2), (3, 3)] {
+        let stored = labelled(width, height);
+        let png = stored.encode(Codex::PNG).unwrap();
+        let at = |x: usize, y: usize| stored.get_pixel(&Point::new(x, y))[0];
+        let (w, h) = (width, height);
+
+        for orientation in 1..=8u16 {
+            let file = with_exif_chunk(&png, &tiff(false, orientation));
+            let image = Image::read_bytes_oriented(&file).unwrap();
+
+            let transposed = orientation >= 5;
+            let (out_w, out_h) = if transposed { (h, w) } else { (w, h) };
+            assert_eq!(image.shape(), Shape::new(out_w, out_h, Some(1)));
+
+            for y in 0..out_h {
+                for x in 0..out_w {
+                    let expected = match orientation {
+                        1 => at(x, y),
+                        2 => at(w - 1 - x, y),
+                        3 => at(w - 1 - x, h - 1 - y),
+                        4 => at(x, h - 1 - y),
+                        5 => at(y, x),
+                        6 => at(y, h - 1 - x),
+                        7 => at(w - 1 - y, h - 1 - x),
+                        _ => at(w - 1 - y, x),
+                    };
+                    let actual = image.get_pixel(&Point::new(x, y))[0];
+                    assert_eq!(actual, expected, "orientation {orientation} at ({x}, {y})");
+                }
+            }
+        }
+    }
+}
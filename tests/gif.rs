@@ -0,0 +1,354 @@
+use std::time::Duration;
+
+use rusty_vision as rv;
+use rv::codec::decoders::gif::{self, GifDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::gif::{encode_animation, encode_with_options, EncodeOptions};
+use rv::codec::encoders::Encoder;
+use rv::codec::{detect, Codex};
+use rv::color::ColorSpace;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::{Reader, Writer};
+
+const PALETTE: [[u8; 3]; 4] = [[255, 255, 255], [255, 0, 0], [0, 0, 255], [0, 0, 0]];
+
+///
+/// LZW data that never grows the code table: every index is
+/// written as a 3-bit code, with a clear code every other index.
+///
+fn uncompressed_lzw(indices: &[u8]) -> Vec<u8> {
+    let (clear, end) = (4u32, 5u32);
+    let mut codes = vec![clear];
+    for pair in indices.chunks(2) {
+        codes.extend(pair.iter().map(|&index| index as u32));
+        codes.push(clear);
+    }
+    codes.push(end);
+
+    let (mut bytes, mut buffer, mut bits) = (Vec::new(), 0u32, 0);
+    for code in codes {
+        buffer |= code << bits;
+        bits += 3;
+        while bits >= 8 {
+            bytes.push(buffer as u8);
+            buffer >>= 8;
+            bits -= 8;
+        }
+    }
+    if bits > 0 {
+        bytes.push(buffer as u8);
+    }
+    bytes
+}
+
+fn sub_blocks(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for chunk in data.chunks(255) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+    out.push(0);
+    out
+}
+
+fn header(width: u16, height: u16) -> Vec<u8> {
+    let mut out = b"GIF89a".to_vec();
+    out.extend_from_slice(&width.to_le_bytes());
+    out.extend_from_slice(&height.to_le_bytes());
+    // Global colour table of four entries.
+    out.extend_from_slice(&[0x81, 0, 0]);
+    out.extend(PALETTE.iter().flatten());
+    out
+}
+
+fn graphic_control(disposal: u8, delay: u16, transparent: Option<u8>) -> Vec<u8> {
+    let flags = disposal << 2 | transparent.is_some() as u8;
+    let mut out = vec![0x21, 0xF9, 4, flags];
+    out.extend_from_slice(&delay.to_le_bytes());
+    out.extend_from_slice(&[transparent.unwrap_or(0), 0]);
+    out
+}
+
+fn image_block(rect: [u16; 4], flags: u8, palette: &[[u8; 3]], indices: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x2C];
+    for value in rect {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.push(flags);
+    out.extend(palette.iter().flatten());
+    out.push(2);
+    out.extend(sub_blocks(&uncompressed_lzw(indices)));
+    out
+}
+
+fn rgba(indices: &[u8]) -> Vec<u8> {
+    indices
+        .iter()
+        .flat_map(|&i| match i {
+            // 9 marks a transparent pixel in expectations.
+            9 => [0, 0, 0, 0],
+            i => {
+                let [r, g, b] = PALETTE[i as usize];
+                [r, g, b, 255]
+            }
+        })
+        .collect()
+}
+
+fn pixels(image: &Image) -> &[u8] {
+    image.slice(0, image.size())
+}
+
+#[test]
+fn test_decode_compressed() {
+    // The 10x10 sample from "What's in a GIF", with real LZW compression.
+    let mut bytes = b"GIF89a\x0A\x00\x0A\x00\x91\x00\x00".to_vec();
+    bytes.extend(PALETTE.iter().flatten());
+    bytes.extend_from_slice(&graphic_control(0, 0, None));
+    bytes.extend_from_slice(b"\x2C\x00\x00\x00\x00\x0A\x00\x0A\x00\x00\x02\x16");
+    bytes.extend_from_slice(&[
+        0x8C, 0x2D, 0x99, 0x87, 0x2A, 0x1C, 0xDC, 0x33, 0xA0, 0x02, 0x75, 0xEC, 0x95, 0xFA, 0xA8,
+        0xDE, 0x60, 0x8C, 0x04, 0x91, 0x4C, 0x01, 0x00, 0x3B,
+    ]);
+    assert_eq!(detect(&bytes).unwrap(), Codex::GIF);
+
+    let image = Image::read_bytes_auto(&bytes).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    assert_eq!(image.shape(), Shape::new(10, 10, Some(4)));
+
+    let rows: [&[u8; 10]; 10] = [
+        b"1111122222",
+        b"1111122222",
+        b"1111122222",
+        b"1110000222",
+        b"1110000222",
+        b"2220000111",
+        b"2220000111",
+        b"2222211111",
+        b"2222211111",
+        b"2222211111",
+    ];
+    let expected: Vec<u8> = rows.iter().flat_map(|row| row.map(|c| c - b'0')).collect();
+    assert_eq!(pixels(&image), rgba(&expected).as_slice());
+}
+
+#[test]
+fn test_local_palette_and_transparency() {
+    let mut bytes = header(3, 1);
+    bytes.extend(graphic_control(0, 0, Some(1)));
+    // Local table that swaps red and blue.
+    let local = [[255, 255, 255], [0, 0, 255], [255, 0, 0], [0, 0, 0]];
+    bytes.extend(image_block([0, 0, 3, 1], 0x81, &local, &[2, 1, 3]));
+    bytes.push(0x3B);
+
+    let image = GifDecoder.decode(&bytes).unwrap();
+    assert_eq!(pixels(&image), &[255, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 255]);
+}
+
+#[test]
+fn test_interlaced() {
+    // Stored in pass order: rows 0, 8 / 4 / 2, 6 / 1, 3, 5, 7, 9.
+    let stored = [0, 8, 4, 2, 6, 1, 3, 5, 7, 9].map(|row| (row % 4) as u8);
+    let mut bytes = header(1, 10);
+    bytes.extend(image_block([0, 0, 1, 10], 0x40, &[], &stored));
+    bytes.push(0x3B);
+
+    let image = gif::decode_bytes(&bytes).unwrap();
+    let expected: Vec<u8> = (0..10).map(|row| row % 4).collect();
+    assert_eq!(pixels(&image), rgba(&expected).as_slice());
+}
+
+#[test]
+fn test_animation() {
+    let mut bytes = header(4, 1);
+    // Loop forever.
+    bytes.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+    // A red background, kept.
+    bytes.extend(graphic_control(1, 10, None));
+    bytes.extend(image_block([0, 0, 4, 1], 0, &[], &[1, 1, 1, 1]));
+    // Blue over the middle, then cleared to transparent.
+    bytes.extend(graphic_control(2, 20, None));
+    bytes.extend(image_block([1, 0, 2, 1], 0, &[], &[2, 2]));
+    // Black on the left, then restored to what was there before.
+    bytes.extend(graphic_control(3, 30, None));
+    bytes.extend(image_block([0, 0, 2, 1], 0, &[], &[3, 3]));
+    // White on the right, partly outside the screen.
+    bytes.extend(graphic_control(0, 0, None));
+    bytes.extend(image_block([3, 0, 2, 1], 0, &[], &[0, 0]));
+    bytes.push(0x3B);
+
+    let frames = gif::frames(&bytes).unwrap();
+    assert_eq!((frames.width(), frames.height()), (4, 1));
+    assert_eq!(frames.loop_count(), Some(0));
+
+    let frames: Vec<_> = frames.map(Result::unwrap).collect();
+    let expected = [
+        (&[1, 1, 1, 1], 100),
+        (&[1, 2, 2, 1], 200),
+        (&[3, 3, 9, 1], 300),
+        (&[1, 9, 9, 0], 0),
+    ];
+    assert_eq!(frames.len(), expected.len());
+    for (frame, (indices, delay)) in frames.iter().zip(expected) {
+        assert_eq!(pixels(&frame.image), rgba(indices).as_slice());
+        assert_eq!(frame.delay, Duration::from_millis(delay));
+    }
+
+    // The first frame is what a still decode returns.
+    let still = GifDecoder.decode(&bytes).unwrap();
+    assert_eq!(pixels(&still), pixels(&frames[0].image));
+}
+
+#[test]
+fn test_decode_invalid() {
+    let mut bytes = header(2, 2);
+    bytes.push(0x3B);
+    assert!(GifDecoder.decode(&bytes).is_err());
+    assert!(GifDecoder.decode(b"GIF89a\x01\x00").is_err());
+    assert!(GifDecoder
+        .decode(b"GIF90a\x01\x00\x01\x00\x00\x00\x00")
+        .is_err());
+
+    // No colour table anywhere.
+    let mut bytes = b"GIF89a\x01\x00\x01\x00\x00\x00\x00".to_vec();
+    bytes.extend(image_block([0, 0, 1, 1], 0, &[], &[0]));
+    assert!(GifDecoder.decode(&bytes).is_err());
+
+    // A forged logical screen, and a forged frame on a small screen.
+    let mut bytes = header(65535, 65535);
+    bytes.extend(image_block([0, 0, 1, 1], 0, &[], &[0]));
+    bytes.push(0x3B);
+    assert!(GifDecoder.decode(&bytes).is_err());
+    let mut bytes = header(1, 1);
+    bytes.extend(image_block([0, 0, 65535, 65535], 0, &[], &[0]));
+    bytes.push(0x3B);
+    assert!(GifDecoder.decode(&bytes).is_err());
+}
+
+///
+/// Colours that change in every pixel, to force quantisation and
+/// fill the LZW code table.
+///
+fn gradient(width: usize, height: usize) -> Image {
+    let mut data = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            data.extend_from_slice(&[(x * 4) as u8, (y * 4) as u8, ((x * y) % 251) as u8]);
+        }
+    }
+    Image::from_data(data, Shape::new(width, height, Some(3)), ColorSpace::RGB)
+}
+
+#[test]
+fn test_encode_roundtrip() {
+    let data: Vec<u8> = (0..64).flat_map(|i| [i * 4, 255 - i, 7]).collect();
+    let image = Image::from_data(data, Shape::new(8, 8, Some(3)), ColorSpace::RGB);
+    let bytes = image.encode(Codex::GIF).unwrap();
+    assert_eq!(detect(&bytes).unwrap(), Codex::GIF);
+
+    let decoded = Image::read_bytes(&bytes, Codex::GIF).unwrap();
+    let expected: Vec<u8> = pixels(&image)
+        .chunks(3)
+        .flat_map(|p| [p[0], p[1], p[2], 255])
+        .collect();
+    assert_eq!(pixels(&decoded), expected.as_slice());
+}
+
+#[test]
+fn test_encode_file() {
+    let image = Image::from_data(
+        vec![10, 20, 30, 255, 0, 0, 0, 0],
+        Shape::new(2, 1, Some(4)),
+        ColorSpace::RGBA,
+    );
+    let path = std::env::temp_dir().join("rusty-vision-gif.gif");
+    let path = path.to_str().unwrap();
+    image.write(path.to_string(), Codex::GIF).unwrap();
+
+    let decoded = Image::read_auto(path).unwrap();
+    assert_eq!(pixels(&decoded), pixels(&image));
+}
+
+#[test]
+fn test_encode_quantised() {
+    let image = gradient(64, 64);
+    let original = pixels(&image);
+    for options in [EncodeOptions::default(), EncodeOptions::dithered()] {
+        let decoded = gif::decode_bytes(&encode_with_options(&image, &options).unwrap()).unwrap();
+        assert_eq!(decoded.shape(), Shape::new(64, 64, Some(4)));
+
+        let rgb: Vec<u8> = pixels(&decoded)
+            .chunks(4)
+            .flat_map(|p| [p[0], p[1], p[2]])
+            .collect();
+        let error: usize = rgb
+            .iter()
+            .zip(original)
+            .map(|(&a, &b)| a.abs_diff(b) as usize)
+            .sum();
+        assert!(error / original.len() < 16);
+    }
+}
+
+#[test]
+fn test_encode_animation() {
+    let frame = |indices: &[u8], millis| gif::Frame {
+        image: Image::from_data(rgba(indices), Shape::new(3, 1, Some(4)), ColorSpace::RGBA),
+        delay: Duration::from_millis(millis),
+    };
+    let frames = [
+        frame(&[1, 1, 2], 100),
+        frame(&[9, 3, 9], 250),
+        frame(&[0, 9, 2], 40),
+    ];
+    let options = EncodeOptions {
+        loop_count: Some(2),
+        ..EncodeOptions::default()
+    };
+    let bytes = encode_animation(&frames, &options).unwrap();
+
+    let decoded = gif::frames(&bytes).unwrap();
+    assert_eq!(decoded.loop_count(), Some(2));
+    let decoded: Vec<_> = decoded.map(Result::unwrap).collect();
+    assert_eq!(decoded.len(), frames.len());
+    for (decoded, frame) in decoded.iter().zip(&frames) {
+        assert_eq!(pixels(&decoded.image), pixels(&frame.image));
+        assert_eq!(decoded.delay, frame.delay);
+    }
+
+    let bytes = encode_animation(&frames[..2], &EncodeOptions::default()).unwrap();
+    assert_eq!(gif::frames(&bytes).unwrap().loop_count(), Some(0));
+}
+
+#[test]
+fn test_encode_large_frame() {
+    // 200 colours in an irregular pattern, which clears the
+    // LZW code table several times but needs no quantisation.
+    let (width, height) = (300, 200);
+    let data: Vec<u8> = (0..width * height)
+        .flat_map(|i| {
+            let color = (i * 7 + i * i / 13) % 200;
+            [color as u8, (color * 3) as u8, 255, 255]
+        })
+        .collect();
+    let image = Image::from_data(data, Shape::new(width, height, Some(4)), ColorSpace::RGBA);
+
+    let decoded = gif::decode_bytes(&image.encode(Codex::GIF).unwrap()).unwrap();
+    assert_eq!(pixels(&decoded), pixels(&image));
+}
+
+#[test]
+fn test_encode_invalid() {
+    assert!(encode_animation(&[], &EncodeOptions::default()).is_err());
+
+    let frame = |width| gif::Frame {
+        image: Image::from_data(
+            vec![0; width * 3],
+            Shape::new(width, 1, Some(3)),
+            ColorSpace::RGB,
+        ),
+        delay: Duration::ZERO,
+    };
+    assert!(encode_animation(&[frame(2), frame(3)], &EncodeOptions::default()).is_err());
+}
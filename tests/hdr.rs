@@ -0,0 +1,164 @@
+use rusty_vision as rv;
+use rv::codec::decoders::hdr::{self, HdrDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::{hdr as hdr_encoder, Encoder};
+use rv::codec::{detect, Codex};
+use rv::color::ColorSpace;
+use rv::error::Error;
+use rv::geometry::Shape;
+use rv::image::{FloatImage, Image};
+use rv::io::Reader;
+
+///
+/// Radiance from 1/64 to over 500 times white, with runs for the
+/// encoder to compress.
+///
+fn radiance(width: usize, height: usize) -> FloatImage {
+    let mut data = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let brightness = 2f32.powi((x / 4) as i32 % 16 - 6);
+            data.extend_from_slice(&[
+                brightness * (1.0 + y as f32 / 8.0),
+                brightness * 0.5,
+                if x / 4 % 2 == 0 { 0.0 } else { 0.1 },
+            ]);
+        }
+    }
+    FloatImage::from_data(data, Shape::new(width, height, Some(3)), ColorSpace::RGB)
+}
+
+fn header(resolution: &str) -> Vec<u8> {
+    format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n{resolution}\n").into_bytes()
+}
+
+///
+/// RGBE keeps 8 bits relative to the brightest channel of a pixel.
+///
+fn assert_close(decoded: &FloatImage, expected: &FloatImage) {
+    assert_eq!(decoded.shape(), expected.shape());
+    let pairs = decoded
+        .slice(0, decoded.size())
+        .chunks_exact(3)
+        .zip(expected.slice(0, expected.size()).chunks_exact(3));
+    for (pixel, original) in pairs {
+        let max = original.iter().cloned().fold(0.0, f32::max);
+        for (value, target) in pixel.iter().zip(original) {
+            assert!(
+                (value - target).abs() <= max / 256.0,
+                "{pixel:?} {original:?}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_roundtrip_float() {
+    for width in [64, 5] {
+        let image = radiance(width, 6);
+        let bytes = hdr_encoder::encode_float(&image).unwrap();
+        assert_eq!(detect(&bytes).unwrap(), Codex::HDR);
+
+        let decoded = hdr::decode_float(&mut &bytes[..]).unwrap();
+        assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+        assert_close(&decoded, &image);
+    }
+
+    // Wide scanlines are run length encoded.
+    let bytes = hdr_encoder::encode_float(&radiance(400, 2)).unwrap();
+    assert!(bytes.len() < 400 * 2 * 4);
+    let decoded = hdr::decode_float_bytes(&bytes).unwrap();
+    assert!(decoded.slice(0, decoded.size()).iter().any(|&v| v > 500.0));
+}
+
+#[test]
+fn test_roundtrip_image() {
+    let gray = Image::from_data(
+        (0..=255).collect(),
+        Shape::new(16, 16, Some(1)),
+        ColorSpace::GRAY,
+    );
+    let decoded = Image::read_bytes_auto(&gray.encode(Codex::HDR).unwrap()).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+    let expected: Vec<u8> = (0..=255).flat_map(|v| [v; 3]).collect();
+    assert_eq!(decoded.slice(0, decoded.size()), expected.as_slice());
+
+    // Channels much darker than their neighbour lose precision.
+    let bgra = Image::from_data(
+        vec![10, 20, 255, 7, 60, 61, 62, 0],
+        Shape::new(2, 1, Some(4)),
+        ColorSpace::BGRA,
+    );
+    let decoded = HdrDecoder
+        .decode(&bgra.encode(Codex::HDR).unwrap())
+        .unwrap();
+    let expected = [255, 20, 10, 62, 61, 60];
+    for (value, target) in decoded.slice(0, decoded.size()).iter().zip(expected) {
+        assert!(value.abs_diff(target) <= 1);
+    }
+}
+
+#[test]
+fn test_to_image_clamps() {
+    let image = FloatImage::from_data(
+        vec![-1.0, 0.5, 4.0],
+        Shape::new(1, 1, Some(3)),
+        ColorSpace::RGB,
+    );
+    let bytes = hdr_encoder::encode_float(&image).unwrap();
+    let decoded = hdr::decode_bytes(&bytes).unwrap();
+    assert_eq!(decoded.slice(0, 3), &[0, 128, 255]);
+}
+
+#[test]
+fn test_decode_flat() {
+    // Too narrow for run length encoding, with an original
+    // format repeat code filling the last two pixels.
+    let mut bytes = b"#?RGBE\nEXPOSURE=2.0\n\n-Y 1 +X 3\n".to_vec();
+    bytes.extend_from_slice(&[128, 64, 0, 129, 1, 1, 1, 2]);
+    let decoded = hdr::decode_float_bytes(&bytes).unwrap();
+    assert_eq!(
+        decoded.slice(0, decoded.size()),
+        &[1.0, 0.5, 0.0, 1.0, 0.5, 0.0, 1.0, 0.5, 0.0]
+    );
+}
+
+#[test]
+fn test_decode_invalid() {
+    let bytes = hdr_encoder::encode_float(&radiance(64, 6)).unwrap();
+    assert!(HdrDecoder.decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(HdrDecoder.decode(&bytes[..20]).is_err());
+    assert!(HdrDecoder.decode(b"#?RADIANCE\n\n-Y 0 +X 1\n").is_err());
+
+    // A repeat with no previous pixel.
+    let mut repeat = header("-Y 1 +X 2");
+    repeat.extend_from_slice(&[1, 1, 1, 2]);
+    assert!(HdrDecoder.decode(&repeat).is_err());
+
+    // Resolutions that overflow, or that the data cannot back.
+    let huge = format!("-Y {} +X 3", usize::MAX / 2);
+    let resolutions = [
+        huge.as_str(),
+        "-Y 100000 +X 100000",
+        "-Y 20000 +X 20000",
+        "-Y 1 +X 100000000",
+        "-Y 100000000 +X 1",
+    ];
+    for resolution in resolutions {
+        let mut bytes = header(resolution);
+        bytes.extend_from_slice(&[0; 8]);
+        assert!(HdrDecoder.decode(&bytes).is_err());
+    }
+
+    let mut flipped = header("+Y 1 +X 1");
+    flipped.extend_from_slice(&[0; 4]);
+    assert!(matches!(
+        HdrDecoder.decode(&flipped),
+        Err(Error::NotImplemented(_))
+    ));
+    let xyze = b"#?RADIANCE\nFORMAT=32-bit_rle_xyze\n\n-Y 1 +X 1\n\0\0\0\0";
+    assert!(matches!(
+        HdrDecoder.decode(xyze),
+        Err(Error::NotImplemented(_))
+    ));
+}
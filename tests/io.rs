@@ -0,0 +1,94 @@
+use std::io::Cursor;
+
+use rusty_vision as rv;
+use rv::codec::decoders::png::{DecodeOptions, PngDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::Encoder;
+use rv::codec::{detect, Codex};
+use rv::color::ColorSpace;
+use rv::error::Error;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::{Reader, Writer};
+
+fn sample_image() -> Image {
+    let shape = Shape::new(4, 3, Some(3));
+    let data = (0..shape.size()).map(|i| (i * 7) as u8).collect();
+    Image::from_data(data, shape, ColorSpace::RGB)
+}
+
+fn assert_same(a: &Image, b: &Image) {
+    assert_eq!(a.shape(), b.shape());
+    assert_eq!(a.slice(0, a.size()), b.slice(0, b.size()));
+}
+
+#[test]
+fn test_read_bytes() {
+    let image = sample_image();
+    let png = image.encode(Codex::PNG).unwrap();
+
+    let decoded = Image::read_bytes(&png, Codex::PNG).unwrap();
+    assert_same(&image, &decoded);
+}
+
+#[test]
+fn test_read_from_reader() {
+    let image = sample_image();
+    let png = image.encode(Codex::PNG).unwrap();
+
+    let mut cursor = Cursor::new(png);
+    let decoded = Image::read_from(&mut cursor, Codex::PNG).unwrap();
+    assert_same(&image, &decoded);
+}
+
+#[test]
+fn test_read_path() {
+    let image = sample_image();
+    let path = std::env::temp_dir().join("rusty-vision-io-read.png");
+    let path = path.to_str().unwrap();
+    image.write(path.to_string(), Codex::PNG).unwrap();
+
+    let decoded = Image::read(path, Codex::PNG).unwrap();
+    assert_same(&image, &decoded);
+}
+
+#[test]
+fn test_png_decoder_trait() {
+    let image = sample_image();
+    let png = image.encode(Codex::PNG).unwrap();
+
+    let decoder = PngDecoder::new(DecodeOptions::lenient());
+    let decoded = decoder.decode(&png).unwrap();
+    assert_same(&image, &decoded);
+
+    assert!(PngDecoder::default().decode(&png[..20]).is_err());
+}
+
+#[test]
+fn test_detect_format() {
+    let png = sample_image().encode(Codex::PNG).unwrap();
+    assert_eq!(detect(&png).unwrap(), Codex::PNG);
+    assert_eq!(detect(&[0xFF, 0xD8, 0xFF, 0xE0]).unwrap(), Codex::JPG);
+
+    assert!(matches!(
+        detect(b"not an image"),
+        Err(Error::UnsupportedFormat(_))
+    ));
+    assert!(matches!(detect(&[]), Err(Error::UnsupportedFormat(_))));
+}
+
+#[test]
+fn test_read_auto() {
+    let image = sample_image();
+    let path = std::env::temp_dir().join("rusty-vision-io-auto.img");
+    let path = path.to_str().unwrap();
+    image.write(path.to_string(), Codex::PNG).unwrap();
+
+    let decoded = Image::read_auto(path).unwrap();
+    assert_same(&image, &decoded);
+
+    assert!(matches!(
+        Image::read_bytes_auto(b"GARBAGE"),
+        Err(Error::UnsupportedFormat(_))
+    ));
+}
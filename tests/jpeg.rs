@@ -0,0 +1,235 @@
+use rusty_vision as rv;
+use rv::codec::decoders::jpeg::{self, JpegDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::jpeg::{encode_with_options, ChromaSubsampling, EncodeOptions};
+use rv::codec::encoders::Encoder;
+use rv::codec::Codex;
+use rv::color::ColorSpace;
+use rv::error::Error;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::Reader;
+
+///
+/// The pattern every fixture in `tests/data/jpeg` was encoded from.
+///
+fn pattern(width: usize, height: usize, channels: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(width * height * channels);
+    for y in 0..height {
+        for x in 0..width {
+            for c in 0..channels {
+                let ramp = ((x * 255 / width) as u8).wrapping_add((y * (c + 1) * 3) as u8) / 2;
+                data.push(ramp + ((x ^ y) as u8 & 0x1F) + c as u8 * 20);
+            }
+        }
+    }
+    data
+}
+
+fn mean_error(image: &Image, expected: &[u8]) -> f64 {
+    let data = image.slice(0, image.size());
+    assert_eq!(data.len(), expected.len());
+    let total: u64 = data
+        .iter()
+        .zip(expected)
+        .map(|(&a, &b)| (a as i64 - b as i64).unsigned_abs())
+        .sum();
+    total as f64 / data.len() as f64
+}
+
+fn assert_decodes(
+    bytes: &[u8],
+    width: usize,
+    height: usize,
+    colorspace: ColorSpace,
+    tolerance: f64,
+) {
+    let image = JpegDecoder.decode(bytes).unwrap();
+    let channels = colorspace.channels();
+    assert_eq!(image.shape(), Shape::new(width, height, Some(channels)));
+    assert_eq!(image.colorspace(), colorspace);
+
+    let error = mean_error(&image, &pattern(width, height, channels));
+    assert!(error < tolerance, "mean error {error} exceeds {tolerance}");
+}
+
+#[test]
+fn test_decode_grayscale() {
+    let bytes = include_bytes!("data/jpeg/gray.jpg");
+    assert_decodes(bytes, 33, 21, ColorSpace::GRAY, 2.0);
+}
+
+#[test]
+fn test_decode_444() {
+    let bytes = include_bytes!("data/jpeg/444.jpg");
+    assert_decodes(bytes, 33, 21, ColorSpace::RGB, 4.0);
+}
+
+#[test]
+fn test_decode_subsampled() {
+    assert_decodes(
+        include_bytes!("data/jpeg/422.jpg"),
+        33,
+        21,
+        ColorSpace::RGB,
+        7.0,
+    );
+    assert_decodes(
+        include_bytes!("data/jpeg/420.jpg"),
+        33,
+        21,
+        ColorSpace::RGB,
+        8.0,
+    );
+}
+
+#[test]
+fn test_decode_restart_interval() {
+    let bytes = include_bytes!("data/jpeg/420_restart.jpg");
+    assert_decodes(bytes, 64, 48, ColorSpace::RGB, 10.0);
+}
+
+#[test]
+fn test_read_bytes_jpeg() {
+    let bytes = include_bytes!("data/jpeg/420.jpg");
+    let image = Image::read_bytes_auto(bytes).unwrap();
+    assert_eq!(image.shape(), Shape::new(33, 21, Some(3)));
+
+    let mut reader = &bytes[..];
+    let decoded = jpeg::decode(&mut reader).unwrap();
+    assert_eq!(
+        decoded.slice(0, decoded.size()),
+        image.slice(0, image.size())
+    );
+    assert!(Image::read_bytes(bytes, Codex::JPG).is_ok());
+}
+
+#[test]
+fn test_decode_invalid() {
+    assert!(matches!(
+        JpegDecoder.decode(b"\x89PNG\r\n\x1a\n"),
+        Err(Error::ImageDecodeError(_))
+    ));
+
+    // Truncated inside the frame header
+    let bytes = include_bytes!("data/jpeg/gray.jpg");
+    let sof = bytes.windows(2).position(|w| w == [0xFF, 0xC0]).unwrap();
+    assert!(JpegDecoder.decode(&bytes[..sof + 6]).is_err());
+
+    // A Huffman table with three 1-bit codes
+    let mut dht = vec![0xFF, 0xD8, 0xFF, 0xC4, 0x00, 0x16, 0x00, 3];
+    dht.extend_from_slice(&[0; 15]);
+    dht.extend_from_slice(&[1, 2, 3, 0xFF, 0xD9]);
+    assert!(JpegDecoder.decode(&dht).is_err());
+
+    // A 65535x65535 frame header with no scan behind it
+    let sof = [
+        0xFF, 0xD8, 0xFF, 0xC0, 0x00, 0x0B, 8, 0xFF, 0xFF, 0xFF, 0xFF, 1, 1, 0x11, 0, 0xFF, 0xD9,
+    ];
+    assert!(JpegDecoder.decode(&sof).is_err());
+}
+
+#[test]
+fn test_decode_progressive() {
+    // Spectral selection only
+    let bytes = include_bytes!("data/jpeg/progressive.jpg");
+    assert_decodes(bytes, 50, 37, ColorSpace::RGB, 8.0);
+
+    // libjpeg's default script: spectral selection and successive approximation
+    let bytes = include_bytes!("data/jpeg/progressive_sa.jpg");
+    assert_decodes(bytes, 35, 27, ColorSpace::RGB, 9.0);
+}
+
+#[test]
+fn test_decode_progressive_partial() {
+    let bytes = include_bytes!("data/jpeg/progressive_sa.jpg");
+    let full = JpegDecoder.decode(bytes).unwrap();
+
+    // Stop after the first AC scan: coefficients decoded so far still form an image.
+    let sos: Vec<usize> = (0..bytes.len() - 1)
+        .filter(|&i| bytes[i] == 0xFF && bytes[i + 1] == 0xDA)
+        .collect();
+    let mut partial = bytes[..sos[2]].to_vec();
+    partial.extend_from_slice(&[0xFF, 0xD9]);
+
+    let coarse = JpegDecoder.decode(&partial).unwrap();
+    assert_eq!(coarse.shape(), full.shape());
+
+    let expected = pattern(35, 27, 3);
+    assert!(mean_error(&coarse, &expected) > mean_error(&full, &expected));
+    assert!(mean_error(&coarse, &expected) < 20.0);
+}
+
+fn pattern_image(width: usize, height: usize, colorspace: ColorSpace) -> Image {
+    let channels = colorspace.channels();
+    let shape = Shape::new(width, height, Some(channels));
+    Image::from_data(pattern(width, height, channels), shape, colorspace)
+}
+
+#[test]
+fn test_encode_roundtrip() {
+    let image = pattern_image(45, 30, ColorSpace::RGB);
+    let bytes = image.encode(Codex::JPG).unwrap();
+    assert!(bytes.starts_with(&[0xFF, 0xD8, 0xFF, 0xE0]));
+
+    let decoded = Image::read_bytes_auto(&bytes).unwrap();
+    assert_eq!(decoded.shape(), image.shape());
+    assert!(mean_error(&decoded, &pattern(45, 30, 3)) < 10.0);
+
+    let gray = pattern_image(17, 9, ColorSpace::GRAY);
+    let decoded = JpegDecoder
+        .decode(&gray.encode(Codex::JPG).unwrap())
+        .unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::GRAY);
+    assert!(mean_error(&decoded, &pattern(17, 9, 1)) < 2.0);
+}
+
+#[test]
+fn test_encode_quality() {
+    let image = pattern_image(64, 48, ColorSpace::RGB);
+    let expected = pattern(64, 48, 3);
+
+    let encode = |quality| {
+        let options = EncodeOptions {
+            subsampling: ChromaSubsampling::Yuv444,
+            ..EncodeOptions::with_quality(quality)
+        };
+        encode_with_options(&image, &options).unwrap()
+    };
+    let (low, high) = (encode(10), encode(95));
+    assert!(low.len() < high.len());
+
+    let low = JpegDecoder.decode(&low).unwrap();
+    let high = JpegDecoder.decode(&high).unwrap();
+    assert!(mean_error(&high, &expected) < mean_error(&low, &expected));
+    assert!(mean_error(&high, &expected) < 4.0);
+}
+
+#[test]
+fn test_encode_subsampling_and_huffman() {
+    let image = pattern_image(64, 48, ColorSpace::BGRA);
+    let encode = |subsampling, optimize_huffman| {
+        let options = EncodeOptions {
+            quality: 90,
+            subsampling,
+            optimize_huffman,
+        };
+        encode_with_options(&image, &options).unwrap()
+    };
+
+    let full = encode(ChromaSubsampling::Yuv444, false);
+    let half = encode(ChromaSubsampling::Yuv422, false);
+    let quarter = encode(ChromaSubsampling::Yuv420, false);
+    assert!(quarter.len() < half.len() && half.len() < full.len());
+
+    let optimized = encode(ChromaSubsampling::Yuv420, true);
+    assert!(optimized.len() < quarter.len());
+
+    let standard = JpegDecoder.decode(&quarter).unwrap();
+    let decoded = JpegDecoder.decode(&optimized).unwrap();
+    assert_eq!(decoded.shape(), Shape::new(64, 48, Some(3)));
+    assert_eq!(
+        decoded.slice(0, decoded.size()),
+        standard.slice(0, standard.size())
+    );
+}
@@ -0,0 +1,171 @@
+use rusty_vision as rv;
+use rv::codec::decoders::netpbm::{self, NetpbmDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::netpbm::{encode_with_options, EncodeOptions, Encoding};
+use rv::codec::encoders::Encoder;
+use rv::codec::{detect, Codex};
+use rv::color::ColorSpace;
+use rv::error::Error;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::Reader;
+
+fn sample_image(width: usize, height: usize, colorspace: ColorSpace) -> Image {
+    let channels = colorspace.channels();
+    let data = (0..width * height * channels)
+        .map(|i| (i * 37 % 251) as u8)
+        .collect();
+    Image::from_data(data, Shape::new(width, height, Some(channels)), colorspace)
+}
+
+fn pixels(image: &Image) -> &[u8] {
+    image.slice(0, image.size())
+}
+
+#[test]
+fn test_decode_plain_pbm() {
+    // Digits may run together and comments may appear anywhere in the header.
+    let bytes = b"P1\n# a comment\n3 2\n010\n1 1 0\n";
+    let image = NetpbmDecoder.decode(bytes).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::GRAY);
+    assert_eq!(image.shape(), Shape::new(3, 2, Some(1)));
+    assert_eq!(pixels(&image), &[255, 0, 255, 0, 0, 255]);
+}
+
+#[test]
+fn test_decode_raw_pbm() {
+    // Rows are padded to whole bytes.
+    let bytes = b"P4 10 2\n\xC0\x40\x00\x80";
+    let image = netpbm::decode_bytes(bytes).unwrap();
+    let mut expected = vec![255; 20];
+    expected[0] = 0;
+    expected[1] = 0;
+    expected[9] = 0;
+    expected[18] = 0;
+    assert_eq!(pixels(&image), expected.as_slice());
+}
+
+#[test]
+fn test_decode_plain_pgm_maxval() {
+    let bytes = b"P2\n2 2\n15\n0 5\n# inline\n10 15\n";
+    let image = NetpbmDecoder.decode(bytes).unwrap();
+    assert_eq!(pixels(&image), &[0, 85, 170, 255]);
+}
+
+#[test]
+fn test_decode_plain_ppm() {
+    let bytes = b"P3 2 1 255\n255 0 0  0 128 255\n";
+    let image = netpbm::decode(&mut bytes.as_slice()).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::RGB);
+    assert_eq!(pixels(&image), &[255, 0, 0, 0, 128, 255]);
+}
+
+#[test]
+fn test_decode_16bit() {
+    let mut bytes = b"P5\n2 1\n65535\n".to_vec();
+    bytes.extend_from_slice(&[0xFF, 0xFF, 0x80, 0x00]);
+    let image = NetpbmDecoder.decode(&bytes).unwrap();
+    assert_eq!(pixels(&image), &[255, 128]);
+}
+
+#[test]
+fn test_decode_pam() {
+    let mut bytes =
+        b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\n# comment\nTUPLTYPE RGB_ALPHA\nENDHDR\n"
+            .to_vec();
+    bytes.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    let image = NetpbmDecoder.decode(&bytes).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    assert_eq!(pixels(&image), &[1, 2, 3, 4, 5, 6, 7, 8]);
+
+    let bytes = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nENDHDR\n\x00";
+    assert!(NetpbmDecoder.decode(bytes).is_err());
+}
+
+#[test]
+fn test_roundtrip() {
+    let cases = [
+        (Codex::PGM, ColorSpace::GRAY),
+        (Codex::PPM, ColorSpace::RGB),
+        (Codex::PAM, ColorSpace::GRAY),
+        (Codex::PAM, ColorSpace::GRAYA),
+        (Codex::PAM, ColorSpace::RGBA),
+    ];
+    for (codec, colorspace) in cases {
+        let image = sample_image(7, 3, colorspace);
+        for options in [
+            EncodeOptions::default(),
+            EncodeOptions::ascii(),
+            EncodeOptions::sixteen_bit(),
+        ] {
+            let bytes = match encode_with_options(&image, codec, &options) {
+                Err(Error::UnsupportedFormat(_)) if codec == Codex::PAM => continue,
+                result => result.unwrap(),
+            };
+            assert_eq!(detect(&bytes).unwrap(), codec);
+
+            let decoded = Image::read_bytes_auto(&bytes).unwrap();
+            assert_eq!(decoded.colorspace(), colorspace, "{codec:?} {options:?}");
+            assert_eq!(pixels(&decoded), pixels(&image), "{codec:?} {options:?}");
+        }
+    }
+}
+
+#[test]
+fn test_encode_conversions() {
+    let rgba = Image::from_data(
+        vec![255, 0, 0, 10, 20, 20, 20, 255],
+        Shape::new(2, 1, Some(4)),
+        ColorSpace::RGBA,
+    );
+
+    // PPM drops alpha, PGM and PBM store luminance.
+    let ppm = NetpbmDecoder
+        .decode(&rgba.encode(Codex::PPM).unwrap())
+        .unwrap();
+    assert_eq!(pixels(&ppm), &[255, 0, 0, 20, 20, 20]);
+    let pgm = NetpbmDecoder
+        .decode(&rgba.encode(Codex::PGM).unwrap())
+        .unwrap();
+    assert_eq!(pixels(&pgm), &[76, 20]);
+    let pbm = rgba.encode(Codex::PBM).unwrap();
+    assert_eq!(pbm, b"P4\n2 1\n\xC0");
+
+    let bgr = Image::from_data(vec![1, 2, 3], Shape::new(1, 1, Some(3)), ColorSpace::BGR);
+    let pam = NetpbmDecoder
+        .decode(&bgr.encode(Codex::PAM).unwrap())
+        .unwrap();
+    assert_eq!(pam.colorspace(), ColorSpace::RGB);
+    assert_eq!(pixels(&pam), &[3, 2, 1]);
+}
+
+#[test]
+fn test_encode_plain() {
+    let image = Image::from_data(
+        vec![0, 128, 255, 64],
+        Shape::new(2, 2, Some(1)),
+        ColorSpace::GRAY,
+    );
+    let options = EncodeOptions {
+        encoding: Encoding::Ascii,
+        maxval: 15,
+    };
+    let bytes = encode_with_options(&image, Codex::PGM, &options).unwrap();
+    assert_eq!(bytes, b"P2\n2 2\n15\n0 8 15 4\n");
+
+    let bytes = encode_with_options(&image, Codex::PBM, &options).unwrap();
+    assert_eq!(bytes, b"P1\n2 2\n1001\n");
+
+    assert!(matches!(
+        encode_with_options(&image, Codex::PNG, &options),
+        Err(Error::UnsupportedFormat(_))
+    ));
+}
+
+#[test]
+fn test_decode_invalid() {
+    assert!(NetpbmDecoder.decode(b"P5\n2 2\n255\n\x00\x00").is_err());
+    assert!(NetpbmDecoder.decode(b"P2\n1 1\n15\n16\n").is_err());
+    assert!(NetpbmDecoder.decode(b"P6\n0 1\n255\n").is_err());
+    assert!(NetpbmDecoder.decode(b"P9\n1 1\n").is_err());
+}
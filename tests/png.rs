@@ -0,0 +1,667 @@
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use rusty_vision as rv;
+use rv::codec::decoders::png::{
+    decode, decode_with_metadata, decode_with_options, DecodeOptions, FilterType,
+    PhysicalDimensions, Unit,
+};
+use rv::codec::encoders::png::{encode_with_options, BitDepth, EncodeOptions, FilterStrategy};
+use rv::codec::encoders::Encoder;
+use rv::codec::Codex;
+use rv::color::ColorSpace;
+use rv::geometry::Shape;
+use rv::image::Image;
+
+fn chunk(chunk_type: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut chunk = Vec::new();
+    chunk.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(chunk_type);
+    chunk.extend_from_slice(data);
+    let checksum = crc32fast::hash(&chunk[4..]);
+    chunk.extend_from_slice(&checksum.to_be_bytes());
+    chunk
+}
+
+fn build_png(width: u32, height: u32, bit_depth: u8, color_type: u8, raw: &[u8]) -> Vec<u8> {
+    build_png_with_chunks(width, height, bit_depth, color_type, raw, &[])
+}
+
+fn build_interlaced_png(
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    raw: &[u8],
+) -> Vec<u8> {
+    let mut png = build_png(width, height, bit_depth, color_type, raw);
+    // Patch the interlace byte of IHDR and recompute its CRC.
+    png[28] = 1;
+    let checksum = crc32fast::hash(&png[12..29]);
+    png[29..33].copy_from_slice(&checksum.to_be_bytes());
+    png
+}
+
+fn build_png_with_chunks(
+    width: u32,
+    height: u32,
+    bit_depth: u8,
+    color_type: u8,
+    raw: &[u8],
+    extra: &[(&[u8], Vec<u8>)],
+) -> Vec<u8> {
+    let mut ihdr = Vec::new();
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[bit_depth, color_type, 0, 0, 0]);
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(raw).unwrap();
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png.extend(chunk(b"IHDR", &ihdr));
+    for (chunk_type, data) in extra {
+        png.extend(chunk(chunk_type, data));
+    }
+    png.extend(chunk(b"IDAT", &encoder.finish().unwrap()));
+    png.extend(chunk(b"IEND", &[]));
+    png
+}
+
+fn decode_bytes(mut png: &[u8]) -> rv::image::Image {
+    decode(&mut png).unwrap()
+}
+
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).unwrap();
+    encoder.finish().unwrap()
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let (pa, pb, pc) = (
+        (p - a as i16).abs(),
+        (p - b as i16).abs(),
+        (p - c as i16).abs(),
+    );
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+/// Reference forward filter, applied independently of the library.
+fn filter_row(filter: u8, bpp: usize, previous: &[u8], row: &[u8]) -> Vec<u8> {
+    let mut out = vec![filter];
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let b = previous[i];
+        let c = if i >= bpp { previous[i - bpp] } else { 0 };
+        let predicted = match filter {
+            0 => 0,
+            1 => a,
+            2 => b,
+            3 => ((a as u16 + b as u16) / 2) as u8,
+            4 => paeth(a, b, c),
+            _ => unreachable!(),
+        };
+        out.push(row[i].wrapping_sub(predicted));
+    }
+    out
+}
+
+#[test]
+fn test_png_decode_all_filter_types() {
+    let (width, height) = (7usize, 10usize);
+    let pixels: Vec<u8> = (0..width * height * 4)
+        .map(|i| ((i * 37) ^ (i / 5)) as u8)
+        .collect();
+
+    let stride = width * 4;
+    let mut raw = Vec::new();
+    let mut previous = vec![0; stride];
+    for y in 0..height {
+        let row = &pixels[y * stride..(y + 1) * stride];
+        raw.extend(filter_row((y % 5) as u8, 4, &previous, row));
+        previous = row.to_vec();
+    }
+
+    let png = build_png(width as u32, height as u32, 8, 6, &raw);
+    let image = decode_bytes(&png);
+
+    assert_eq!(image.width(), width);
+    assert_eq!(image.height(), height);
+    assert_eq!(image.slice(0, image.size()), &pixels[..]);
+}
+
+#[test]
+fn test_png_decode_invalid_filter_type() {
+    let raw = [5, 0, 0, 0, 0];
+    let png = build_png(1, 1, 8, 6, &raw);
+
+    assert!(decode(&mut &png[..]).is_err());
+}
+
+#[test]
+fn test_png_decode_grayscale_low_bit_depths() {
+    // One row of 1-bit samples: 1 0 1 1 0 0 1 0 | 1 (padded)
+    let png = build_png(9, 1, 1, 0, &[0, 0b1011_0010, 0b1000_0000]);
+    let image = decode_bytes(&png);
+    assert_eq!(image.colorspace(), ColorSpace::GRAY);
+    assert_eq!(
+        image.slice(0, image.size()),
+        &[255, 0, 255, 255, 0, 0, 255, 0, 255]
+    );
+
+    // 2-bit samples scale 0..=3 to 0..=255
+    let png = build_png(4, 1, 2, 0, &[0, 0b00_01_10_11]);
+    let image = decode_bytes(&png);
+    assert_eq!(image.slice(0, image.size()), &[0, 85, 170, 255]);
+}
+
+#[test]
+fn test_png_decode_sixteen_bit() {
+    let raw = [
+        0, 0xFF, 0xFF, 0x00, 0x00, 0x12, 0x34, 0x80, 0x00, 0x00, 0xFF, 0xAB, 0xCD,
+    ];
+    let png = build_png(2, 1, 16, 2, &raw);
+    let image = decode_bytes(&png);
+
+    assert_eq!(image.colorspace(), ColorSpace::RGB);
+    assert_eq!(image.slice(0, image.size()), &[255, 0, 18, 128, 1, 171]);
+}
+
+#[test]
+fn test_png_decode_gray_alpha() {
+    let png = build_png(2, 1, 8, 4, &[0, 10, 20, 30, 40]);
+    let image = decode_bytes(&png);
+
+    assert_eq!(image.colorspace(), ColorSpace::GRAYA);
+    assert_eq!(image.shape().ndim, 2);
+    assert_eq!(image.slice(0, image.size()), &[10, 20, 30, 40]);
+}
+
+#[test]
+fn test_png_decode_palette_with_transparency() {
+    let palette = vec![255, 0, 0, 0, 255, 0, 0, 0, 255];
+    let alpha = vec![0, 128];
+    // 4-bit indices: 0, 1, 2
+    let raw = [0, 0x01, 0x20];
+    let png = build_png_with_chunks(3, 1, 4, 3, &raw, &[(b"PLTE", palette), (b"tRNS", alpha)]);
+    let image = decode_bytes(&png);
+
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    assert_eq!(
+        image.slice(0, image.size()),
+        &[255, 0, 0, 0, 0, 255, 0, 128, 0, 0, 255, 255]
+    );
+}
+
+#[test]
+fn test_png_decode_truecolor_with_transparency() {
+    let key = vec![0, 1, 0, 2, 0, 3];
+    let raw = [0, 1, 2, 3, 4, 5, 6];
+    let png = build_png_with_chunks(2, 1, 8, 2, &raw, &[(b"tRNS", key)]);
+    let image = decode_bytes(&png);
+
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    assert_eq!(image.slice(0, image.size()), &[1, 2, 3, 0, 4, 5, 6, 255]);
+}
+
+#[test]
+fn test_png_decode_invalid_bit_depth() {
+    let png = build_png(1, 1, 4, 2, &[0, 0]);
+    assert!(decode(&mut &png[..]).is_err());
+}
+
+const ADAM7: [(usize, usize, usize, usize); 7] = [
+    (0, 0, 8, 8),
+    (4, 0, 8, 8),
+    (0, 4, 4, 8),
+    (2, 0, 4, 4),
+    (0, 2, 2, 4),
+    (1, 0, 2, 2),
+    (0, 1, 1, 2),
+];
+
+/// Pack samples of `bit_depth` bits into filtered scanlines, one filter per row.
+fn pack_rows(samples: &[Vec<u16>], bit_depth: usize, bpp: usize, raw: &mut Vec<u8>) {
+    let mut previous: Vec<u8> = Vec::new();
+    for (y, row) in samples.iter().enumerate() {
+        let mut packed = vec![0u8; (row.len() * bit_depth).div_ceil(8)];
+        for (i, &value) in row.iter().enumerate() {
+            if bit_depth == 8 {
+                packed[i] = value as u8;
+            } else {
+                let bit = i * bit_depth;
+                packed[bit / 8] |= (value as u8) << (8 - bit_depth - bit % 8);
+            }
+        }
+        if previous.is_empty() {
+            previous = vec![0; packed.len()];
+        }
+        raw.extend(filter_row((y % 5) as u8, bpp, &previous, &packed));
+        previous = packed;
+    }
+}
+
+fn interlaced_matches_progressive(width: usize, height: usize, bit_depth: usize, spp: usize) {
+    let max = (1usize << bit_depth) - 1;
+    let sample = |x: usize, y: usize, c: usize| ((x * 31 + y * 17 + c * 7) % (max + 1)) as u16;
+    let bpp = (spp * bit_depth / 8).max(1);
+    let color_type = if spp == 1 { 0 } else { 2 };
+
+    let rows: Vec<Vec<u16>> = (0..height)
+        .map(|y| {
+            (0..width)
+                .flat_map(|x| (0..spp).map(move |c| sample(x, y, c)))
+                .collect()
+        })
+        .collect();
+    let mut raw = Vec::new();
+    pack_rows(&rows, bit_depth, bpp, &mut raw);
+
+    let mut interlaced = Vec::new();
+    for (x0, y0, dx, dy) in ADAM7 {
+        let pass: Vec<Vec<u16>> = (y0..height)
+            .step_by(dy)
+            .map(|y| {
+                (x0..width)
+                    .step_by(dx)
+                    .flat_map(|x| (0..spp).map(move |c| sample(x, y, c)))
+                    .collect()
+            })
+            .filter(|row: &Vec<u16>| !row.is_empty())
+            .collect();
+        pack_rows(&pass, bit_depth, bpp, &mut interlaced);
+    }
+
+    let (w, h, depth) = (width as u32, height as u32, bit_depth as u8);
+    let expected = decode_bytes(&build_png(w, h, depth, color_type, &raw));
+    let image = decode_bytes(&build_interlaced_png(w, h, depth, color_type, &interlaced));
+
+    assert_eq!(image.shape(), expected.shape());
+    assert_eq!(
+        image.slice(0, image.size()),
+        expected.slice(0, expected.size())
+    );
+}
+
+#[test]
+fn test_png_decode_adam7_interlaced() {
+    for (width, height) in [(1, 1), (3, 2), (8, 8), (9, 9), (17, 13)] {
+        interlaced_matches_progressive(width, height, 8, 3);
+        interlaced_matches_progressive(width, height, 2, 1);
+    }
+}
+
+#[test]
+fn test_png_decode_oversized() {
+    // Headers claiming far more pixels than the data holds.
+    for size in [60000, 0x7FFF_FFFF] {
+        let interlaced = build_interlaced_png(size, size, 8, 2, &[0]);
+        assert!(decode(&mut &interlaced[..]).is_err());
+        let progressive = build_png(size, size, 16, 6, &[0]);
+        assert!(decode(&mut &progressive[..]).is_err());
+    }
+}
+
+#[test]
+fn test_png_decode_metadata() {
+    let mut ztxt = b"Comment\0\0".to_vec();
+    ztxt.extend(zlib(b"compressed text"));
+    let mut itxt = b"Title\0\0\0en\0Titel\0".to_vec();
+    itxt.extend("caf\u{e9}".as_bytes());
+    let mut iccp = b"Profile\0\0".to_vec();
+    iccp.extend(zlib(&[1, 2, 3, 4]));
+    let mut phys = Vec::new();
+    phys.extend(3780u32.to_be_bytes());
+    phys.extend(3780u32.to_be_bytes());
+    phys.push(1);
+    let chrm: Vec<u8> = [31270u32, 32900, 64000, 33000, 30000, 60000, 15000, 6000]
+        .iter()
+        .flat_map(|value| value.to_be_bytes())
+        .collect();
+
+    let extra: Vec<(&[u8], Vec<u8>)> = vec![
+        (b"tEXt", b"Author\0Jane \xc9".to_vec()),
+        (b"zTXt", ztxt),
+        (b"iTXt", itxt),
+        (b"pHYs", phys),
+        (b"gAMA", 45455u32.to_be_bytes().to_vec()),
+        (b"cHRM", chrm),
+        (b"iCCP", iccp),
+        (b"prVt", vec![1, 2, 3]),
+        (b"tIME", vec![0; 7]),
+    ];
+    let png = build_png_with_chunks(1, 1, 8, 0, &[0, 42], &extra);
+
+    let (image, metadata) = decode_with_metadata(&mut &png[..]).unwrap();
+
+    assert_eq!(image.slice(0, 1), &[42]);
+
+    let text: Vec<(&str, &str)> = metadata
+        .text
+        .iter()
+        .map(|entry| (entry.keyword.as_str(), entry.text.as_str()))
+        .collect();
+    assert_eq!(
+        text,
+        vec![
+            ("Author", "Jane \u{c9}"),
+            ("Comment", "compressed text"),
+            ("Title", "caf\u{e9}"),
+        ]
+    );
+    assert_eq!(
+        metadata.physical_dimensions,
+        Some(PhysicalDimensions {
+            x: 3780,
+            y: 3780,
+            unit: Unit::Meter
+        })
+    );
+    assert!((metadata.gamma.unwrap() - 0.45455).abs() < 1e-6);
+    let chromaticities = metadata.chromaticities.unwrap();
+    assert!((chromaticities.white.0 - 0.3127).abs() < 1e-6);
+    assert!((chromaticities.blue.1 - 0.06).abs() < 1e-6);
+    let profile = metadata.icc_profile.unwrap();
+    assert_eq!(profile.name, "Profile");
+    assert_eq!(profile.data, vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_png_decode_metadata_bomb() {
+    // A few kilobytes that inflate to 32 MiB are dropped, not expanded.
+    let mut ztxt = b"Comment\0\0".to_vec();
+    ztxt.extend(zlib(&vec![0; 32 << 20]));
+    let extra: Vec<(&[u8], Vec<u8>)> = vec![(b"zTXt", ztxt)];
+    let png = build_png_with_chunks(1, 1, 8, 0, &[0, 42], &extra);
+
+    let (image, metadata) = decode_with_metadata(&mut &png[..]).unwrap();
+
+    assert_eq!(image.slice(0, 1), &[42]);
+    assert!(metadata.text.is_empty());
+}
+
+#[test]
+fn test_png_decode_unknown_critical_chunk() {
+    let extra: Vec<(&[u8], Vec<u8>)> = vec![(b"CRIT", vec![0])];
+    let png = build_png_with_chunks(1, 1, 8, 0, &[0, 42], &extra);
+
+    assert!(matches!(
+        decode(&mut &png[..]),
+        Err(rv::error::Error::ImageDecodeError(_))
+    ));
+}
+
+fn noisy_rgb_png(width: usize, height: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut state = 0x1234_5678u32;
+    let pixels: Vec<u8> = (0..width * height * 3)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state as u8
+        })
+        .collect();
+    let raw: Vec<u8> = pixels
+        .chunks(width * 3)
+        .flat_map(|row| std::iter::once(0).chain(row.iter().copied()))
+        .collect();
+    (build_png(width as u32, height as u32, 8, 2, &raw), pixels)
+}
+
+#[test]
+fn test_png_decode_crc_mismatch() {
+    let (mut png, pixels) = noisy_rgb_png(8, 8);
+    // The IDAT CRC is the 4 bytes before the 12 byte IEND chunk.
+    let crc = png.len() - 13;
+    png[crc] ^= 0xFF;
+
+    assert!(decode(&mut &png[..]).is_err());
+
+    let decoded = decode_with_options(&mut &png[..], &DecodeOptions::lenient()).unwrap();
+    assert_eq!(decoded.report.corrupt_chunks, vec!["IDAT".to_string()]);
+    assert_eq!(decoded.report.missing_rows, 0);
+    assert_eq!(decoded.image.slice(0, decoded.image.size()), &pixels[..]);
+}
+
+#[test]
+fn test_png_decode_corrupt_ancillary_chunk_is_dropped() {
+    let extra: Vec<(&[u8], Vec<u8>)> = vec![(b"tEXt", b"Key\0Value".to_vec())];
+    let mut png = build_png_with_chunks(1, 1, 8, 0, &[0, 42], &extra);
+    // Corrupt the text itself so its CRC no longer matches.
+    png[33 + 8] ^= 0xFF;
+
+    let decoded = decode_with_options(&mut &png[..], &DecodeOptions::lenient()).unwrap();
+    assert!(decoded.metadata.text.is_empty());
+    assert_eq!(decoded.report.corrupt_chunks, vec!["tEXt".to_string()]);
+    assert_eq!(decoded.image.slice(0, 1), &[42]);
+}
+
+#[test]
+fn test_png_decode_truncated_lenient() {
+    let (width, height) = (64, 32);
+    let (png, pixels) = noisy_rgb_png(width, height);
+    let truncated = &png[..png.len() / 2];
+
+    assert!(decode(&mut &truncated[..]).is_err());
+
+    let decoded = decode_with_options(&mut &truncated[..], &DecodeOptions::lenient()).unwrap();
+    let report = &decoded.report;
+    assert!(report.truncated);
+    assert!(!report.is_clean());
+    assert!(report.missing_rows > 0 && report.missing_rows < height);
+
+    let recovered = (height - report.missing_rows) * width * 3;
+    let image = &decoded.image;
+    assert_eq!(image.slice(0, recovered), &pixels[..recovered]);
+    assert!(image.slice(recovered, image.size()).iter().all(|&v| v == 0));
+
+    // Zero-filling stops at a sane size, even with no image data.
+    let mut header_only = build_png(60000, 60000, 8, 2, &[]);
+    header_only.truncate(33);
+    assert!(decode_with_options(&mut &header_only[..], &DecodeOptions::lenient()).is_err());
+}
+
+#[test]
+fn test_png_encode_roundtrip_all_colorspaces() {
+    let (width, height) = (5, 3);
+    for colorspace in [
+        ColorSpace::GRAY,
+        ColorSpace::GRAYA,
+        ColorSpace::RGB,
+        ColorSpace::RGBA,
+        ColorSpace::BGR,
+        ColorSpace::BGRA,
+    ] {
+        let channels = colorspace.channels();
+        let data: Vec<u8> = (0..width * height * channels)
+            .map(|i| (i * 11) as u8)
+            .collect();
+        let shape = Shape::new(width, height, Some(channels));
+        let image = Image::from_data(data.clone(), shape, colorspace);
+
+        let png = image.encode(Codex::PNG).unwrap();
+        let decoded = decode_bytes(&png);
+
+        let expected: Vec<u8> = match colorspace {
+            ColorSpace::BGR | ColorSpace::BGRA => data
+                .chunks(channels)
+                .flat_map(|pixel| {
+                    let mut swapped = pixel.to_vec();
+                    swapped.swap(0, 2);
+                    swapped
+                })
+                .collect(),
+            _ => data,
+        };
+        let expected_colorspace = match colorspace {
+            ColorSpace::BGR => ColorSpace::RGB,
+            ColorSpace::BGRA => ColorSpace::RGBA,
+            other => other,
+        };
+
+        assert_eq!(decoded.colorspace(), expected_colorspace);
+        assert_eq!(decoded.slice(0, decoded.size()), &expected[..]);
+    }
+}
+
+#[test]
+fn test_png_encode_rejects_shape_mismatch() {
+    let shape = Shape::new(2, 2, Some(4));
+    let image = Image::from_data(vec![0; shape.size()], shape, ColorSpace::RGB);
+
+    assert!(matches!(
+        image.encode(Codex::PNG),
+        Err(rv::error::Error::ColorSpaceError(_))
+    ));
+}
+
+#[test]
+fn test_png_encode_rejects_empty() {
+    let image = Image::from_data(vec![], Shape::new(0, 3, Some(3)), ColorSpace::RGB);
+
+    for options in [EncodeOptions::default(), EncodeOptions::indexed()] {
+        assert!(matches!(
+            encode_with_options(&image, &options),
+            Err(rv::error::Error::ImageEncodeError(_))
+        ));
+    }
+}
+
+fn gradient_image(width: usize, height: usize) -> Image {
+    let data: Vec<u8> = (0..height)
+        .flat_map(|y| (0..width).flat_map(move |x| [x as u8, y as u8, (x + y) as u8, 255]))
+        .collect();
+    Image::from_data(data, Shape::new(width, height, Some(4)), ColorSpace::RGBA)
+}
+
+#[test]
+fn test_png_encode_filter_strategies_roundtrip() {
+    let image = gradient_image(33, 17);
+    let strategies = [
+        FilterStrategy::Fixed(FilterType::None),
+        FilterStrategy::Fixed(FilterType::Sub),
+        FilterStrategy::Fixed(FilterType::Up),
+        FilterStrategy::Fixed(FilterType::Average),
+        FilterStrategy::Fixed(FilterType::Paeth),
+        FilterStrategy::Adaptive,
+    ];
+
+    for filter in strategies {
+        let options = EncodeOptions {
+            filter,
+            ..Default::default()
+        };
+        let png = encode_with_options(&image, &options).unwrap();
+        let decoded = decode_bytes(&png);
+        assert_eq!(
+            decoded.slice(0, decoded.size()),
+            image.slice(0, image.size())
+        );
+    }
+}
+
+#[test]
+fn test_png_encode_adaptive_is_smaller() {
+    let image = gradient_image(256, 64);
+    let fast = encode_with_options(&image, &EncodeOptions::fast()).unwrap();
+    let best = encode_with_options(&image, &EncodeOptions::best()).unwrap();
+
+    assert!(best.len() < fast.len());
+}
+
+#[test]
+fn test_png_encode_sixteen_bit() {
+    let image = gradient_image(9, 4);
+    let options = EncodeOptions {
+        bit_depth: BitDepth::Sixteen,
+        ..Default::default()
+    };
+    let png = encode_with_options(&image, &options).unwrap();
+    // Bit depth field of IHDR
+    assert_eq!(png[24], 16);
+
+    let decoded = decode_bytes(&png);
+    assert_eq!(
+        decoded.slice(0, decoded.size()),
+        image.slice(0, image.size())
+    );
+}
+
+fn contains_chunk(png: &[u8], chunk_type: &[u8]) -> bool {
+    png.windows(4).any(|window| window == chunk_type)
+}
+
+#[test]
+fn test_png_encode_indexed_exact() {
+    let colors = [[255, 0, 0, 255], [0, 255, 0, 128], [0, 0, 255, 0]];
+    let data: Vec<u8> = (0..6 * 4).flat_map(|i| colors[i % 3]).collect();
+    let image = Image::from_data(data.clone(), Shape::new(6, 4, Some(4)), ColorSpace::RGBA);
+
+    let png = encode_with_options(&image, &EncodeOptions::indexed()).unwrap();
+    assert_eq!(png[24], 2); // Bit depth
+    assert_eq!(png[25], 3); // Colour type
+    assert!(contains_chunk(&png, b"PLTE"));
+    assert!(contains_chunk(&png, b"tRNS"));
+
+    let decoded = decode_bytes(&png);
+    assert_eq!(decoded.colorspace(), ColorSpace::RGBA);
+    assert_eq!(decoded.slice(0, decoded.size()), &data[..]);
+}
+
+#[test]
+fn test_png_encode_indexed_two_colors_opaque() {
+    let data: Vec<u8> = (0..10 * 3)
+        .map(|i| if i % 2 == 0 { 0 } else { 255 })
+        .collect();
+    let image = Image::from_data(data.clone(), Shape::new(10, 1, Some(3)), ColorSpace::RGB);
+
+    let png = encode_with_options(&image, &EncodeOptions::indexed()).unwrap();
+    assert_eq!(png[24], 1);
+    assert!(!contains_chunk(&png, b"tRNS"));
+
+    let decoded = decode_bytes(&png);
+    assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+    assert_eq!(decoded.slice(0, decoded.size()), &data[..]);
+}
+
+#[test]
+fn test_png_encode_indexed_quantized() {
+    let (width, height) = (128, 64);
+    let data: Vec<u8> = (0..height)
+        .flat_map(|y| (0..width).flat_map(move |x| [(x * 2) as u8, (y * 4) as u8, 128]))
+        .collect();
+    let image = Image::from_data(
+        data.clone(),
+        Shape::new(width, height, Some(3)),
+        ColorSpace::RGB,
+    );
+
+    let indexed = encode_with_options(&image, &EncodeOptions::indexed()).unwrap();
+    assert_eq!(indexed[24], 8);
+
+    let decoded = decode_bytes(&indexed);
+    let max_error = decoded
+        .slice(0, decoded.size())
+        .iter()
+        .zip(&data)
+        .map(|(&a, &b)| (a as i32 - b as i32).abs())
+        .max()
+        .unwrap();
+    assert!(max_error <= 16, "max error {max_error}");
+
+    // Noise does not compress, so the one byte indices beat three byte pixels.
+    let (png, _) = noisy_rgb_png(64, 64);
+    let noisy = decode_bytes(&png);
+    let indexed = encode_with_options(&noisy, &EncodeOptions::indexed()).unwrap();
+    let truecolor = encode_with_options(&noisy, &EncodeOptions::best()).unwrap();
+    assert!(indexed.len() < truecolor.len());
+}
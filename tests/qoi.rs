@@ -0,0 +1,123 @@
+use rusty_vision as rv;
+use rv::codec::decoders::qoi::{self, QoiDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::Encoder;
+use rv::codec::{detect, Codex};
+use rv::color::ColorSpace;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::{Reader, Writer};
+
+///
+/// A pattern that exercises every QOI chunk type: runs (including
+/// ones longer than 62 pixels), index hits, small and luma
+/// differences, and full colour and alpha changes.
+///
+fn sample_image(width: usize, height: usize, colorspace: ColorSpace) -> Image {
+    let channels = colorspace.channels();
+    let mut data = Vec::with_capacity(width * height * channels);
+    for i in 0..width * height {
+        let pixel = match (i / 80) % 4 {
+            0 => [10, 20, 30, 255],
+            1 => [(i % 256) as u8, (i / 2 % 256) as u8, 7, 255],
+            2 => [
+                (i * 3 % 256) as u8,
+                (i * 5 % 256) as u8,
+                (i * 7 % 256) as u8,
+                128,
+            ],
+            _ => [[1, 2, 3, 4], [200, 100, 50, 255]][i % 2],
+        };
+        data.extend_from_slice(&pixel[..channels]);
+    }
+    Image::from_data(data, Shape::new(width, height, Some(channels)), colorspace)
+}
+
+fn pixels(image: &Image) -> &[u8] {
+    image.slice(0, image.size())
+}
+
+#[test]
+fn test_roundtrip_rgb() {
+    let image = sample_image(37, 19, ColorSpace::RGB);
+    let bytes = image.encode(Codex::QOI).unwrap();
+    assert_eq!(detect(&bytes).unwrap(), Codex::QOI);
+    assert_eq!(bytes[12], 3);
+
+    let decoded = Image::read_bytes(&bytes, Codex::QOI).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+    assert_eq!(decoded.shape(), image.shape());
+    assert_eq!(pixels(&decoded), pixels(&image));
+}
+
+#[test]
+fn test_roundtrip_rgba() {
+    let image = sample_image(37, 19, ColorSpace::RGBA);
+    let bytes = image.encode(Codex::QOI).unwrap();
+    assert_eq!(bytes[12], 4);
+
+    let decoded = QoiDecoder.decode(&bytes).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGBA);
+    assert_eq!(pixels(&decoded), pixels(&image));
+}
+
+#[test]
+fn test_roundtrip_file() {
+    let image = sample_image(16, 16, ColorSpace::RGBA);
+    let path = std::env::temp_dir().join("rusty-vision-qoi.qoi");
+    let path = path.to_str().unwrap();
+    image.write(path.to_string(), Codex::QOI).unwrap();
+
+    let decoded = Image::read_auto(path).unwrap();
+    assert_eq!(pixels(&decoded), pixels(&image));
+}
+
+#[test]
+fn test_encode_other_colorspaces() {
+    let bgr = Image::from_data(vec![1, 2, 3], Shape::new(1, 1, Some(3)), ColorSpace::BGR);
+    let decoded = qoi::decode_bytes(&bgr.encode(Codex::QOI).unwrap()).unwrap();
+    assert_eq!(pixels(&decoded), &[3, 2, 1]);
+
+    let gray = Image::from_data(vec![9, 200], Shape::new(1, 1, Some(2)), ColorSpace::GRAYA);
+    let decoded = qoi::decode_bytes(&gray.encode(Codex::QOI).unwrap()).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGBA);
+    assert_eq!(pixels(&decoded), &[9, 9, 9, 200]);
+}
+
+#[test]
+fn test_decode_chunks() {
+    let mut bytes = b"qoif".to_vec();
+    bytes.extend_from_slice(&6u32.to_be_bytes());
+    bytes.extend_from_slice(&1u32.to_be_bytes());
+    bytes.extend_from_slice(&[3, 0]);
+    // RGB
+    bytes.extend_from_slice(&[0xFE, 100, 100, 100]);
+    // DIFF of +1, -1, +0
+    bytes.push(0x40 | 3 << 4 | 1 << 2 | 2);
+    // LUMA with a green difference of +8, dr-dg of 0 and db-dg of +1
+    bytes.extend_from_slice(&[0x80 | 40, 0x8 << 4 | 0x9]);
+    // RUN of two
+    bytes.push(0xC1);
+    // INDEX of the first pixel
+    bytes.push(17);
+    bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+    let image = QoiDecoder.decode(&bytes).unwrap();
+    assert_eq!(
+        pixels(&image),
+        &[100, 100, 100, 101, 99, 100, 109, 107, 109, 109, 107, 109, 109, 107, 109, 100, 100, 100]
+    );
+}
+
+#[test]
+fn test_decode_invalid() {
+    let bytes = sample_image(37, 19, ColorSpace::RGB)
+        .encode(Codex::QOI)
+        .unwrap();
+    assert!(QoiDecoder.decode(&bytes[..bytes.len() / 2]).is_err());
+    assert!(QoiDecoder.decode(&bytes[..10]).is_err());
+
+    let mut bad_channels = bytes.clone();
+    bad_channels[12] = 2;
+    assert!(QoiDecoder.decode(&bad_channels).is_err());
+}
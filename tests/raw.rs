@@ -0,0 +1,187 @@
+use rusty_vision as rv;
+use rv::codec::raw::{self, BufferLayout, PixelFormat};
+use rv::color::{ColorRange, ColorSpace};
+use rv::geometry::Shape;
+use rv::image::Image;
+
+fn gradient(width: usize, height: usize) -> Image {
+    let mut data = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            data.extend_from_slice(&[
+                (x * 255 / width) as u8,
+                (y * 255 / height) as u8,
+                ((x + y) * 4) as u8,
+                (x * 9) as u8,
+            ]);
+        }
+    }
+    Image::from_data(data, Shape::new(width, height, Some(4)), ColorSpace::RGBA)
+}
+
+fn solid(width: usize, height: usize, color: [u8; 3]) -> Image {
+    let data = color.repeat(width * height);
+    Image::from_data(data, Shape::new(width, height, Some(3)), ColorSpace::RGB)
+}
+
+fn pixels(image: &Image) -> &[u8] {
+    image.slice(0, image.size())
+}
+
+fn assert_close(decoded: &Image, expected: &Image, tolerance: u8) {
+    assert_eq!(decoded.shape(), expected.shape());
+    for (value, target) in pixels(decoded).iter().zip(pixels(expected)) {
+        assert!(value.abs_diff(*target) <= tolerance, "{value} {target}");
+    }
+}
+
+#[test]
+fn test_packed_rgb() {
+    let image = gradient(5, 3);
+    let layout = BufferLayout::aligned(PixelFormat::Rgba32, 5, 3, 16);
+    assert_eq!(layout.strides, vec![32]);
+    let buffer = raw::export(&image, &layout).unwrap();
+    assert_eq!(buffer.len(), layout.size());
+    assert_eq!(&buffer[20..32], &[0; 12]);
+    assert_eq!(
+        pixels(&raw::import(&buffer, &layout).unwrap()),
+        pixels(&image)
+    );
+
+    // The last row's padding may be missing.
+    let layout = BufferLayout::aligned(PixelFormat::Bgr24, 5, 3, 8);
+    let buffer = raw::export(&image, &layout).unwrap();
+    let bgr = raw::import(&buffer[..buffer.len() - 1], &layout).unwrap();
+    assert_eq!(bgr.colorspace(), ColorSpace::BGR);
+    assert_eq!(&pixels(&bgr)[..6], &[0, 0, 0, 4, 0, 51]);
+
+    let layout = BufferLayout::packed(PixelFormat::Gray8, 5, 3);
+    let gray = raw::import(&raw::export(&image, &layout).unwrap(), &layout).unwrap();
+    assert_eq!(gray.colorspace(), ColorSpace::GRAY);
+    assert_eq!(gray.size(), 15);
+}
+
+#[test]
+fn test_rgb565_and_10_bit() {
+    let layout = BufferLayout::packed(PixelFormat::Rgb565, 2, 1);
+    let image = raw::import(&[0x00, 0xF8, 0xE0, 0x07], &layout).unwrap();
+    assert_eq!(pixels(&image), &[255, 0, 0, 0, 255, 0]);
+    let orange = solid(2, 1, [255, 128, 0]);
+    assert_eq!(
+        raw::export(&orange, &layout).unwrap(),
+        &[0x00, 0xFC, 0x00, 0xFC]
+    );
+
+    let layout = BufferLayout::packed(PixelFormat::X2Rgb10, 1, 1);
+    let image = raw::import(&(0x3FF << 20 | 0x200u32).to_le_bytes(), &layout).unwrap();
+    assert_eq!(pixels(&image), &[255, 0, 128]);
+    let image = gradient(7, 4);
+    let layout = BufferLayout::packed(PixelFormat::X2Rgb10, 7, 4);
+    let decoded = raw::import(&raw::export(&image, &layout).unwrap(), &layout).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+    let rgb: Vec<u8> = pixels(&image)
+        .chunks_exact(4)
+        .flat_map(|p| [p[0], p[1], p[2]])
+        .collect();
+    assert_eq!(pixels(&decoded), rgb.as_slice());
+}
+
+#[test]
+fn test_yuv_planes() {
+    // Limited range black and white luma with neutral chroma.
+    let nv12 = BufferLayout::packed(PixelFormat::Nv12, 2, 2);
+    let image = raw::import(&[16, 235, 235, 16, 128, 128], &nv12).unwrap();
+    assert_eq!(
+        pixels(&image),
+        &[0, 0, 0, 255, 255, 255, 255, 255, 255, 0, 0, 0]
+    );
+
+    let mut i420 = BufferLayout::packed(PixelFormat::I420, 2, 2);
+    i420.color_range = ColorRange::Full;
+    let planes: [&[u8]; 3] = [&[76, 76, 76, 76], &[85], &[255]];
+    let image = raw::import_planes(&planes, &i420).unwrap();
+    assert_close(&image, &solid(2, 2, [255, 0, 0]), 1);
+
+    // Odd sizes and padded strides, in every subsampled layout.
+    let colors = [[200, 30, 40], [0, 0, 0], [255, 255, 255], [20, 120, 250]];
+    for format in [
+        PixelFormat::Nv12,
+        PixelFormat::I420,
+        PixelFormat::Yuyv,
+        PixelFormat::V210,
+    ] {
+        for color in colors {
+            let image = solid(13, 7, color);
+            let layout = BufferLayout::aligned(format, 13, 7, 64);
+            let buffer = raw::export(&image, &layout).unwrap();
+            assert_eq!(buffer.len(), layout.size());
+            assert_close(&raw::import(&buffer, &layout).unwrap(), &image, 2);
+        }
+    }
+}
+
+#[test]
+fn test_yuv_packed() {
+    // Two pixels: black and white with neutral chroma.
+    let layout = BufferLayout::packed(PixelFormat::Yuyv, 2, 1);
+    let image = raw::import(&[16, 128, 235, 128], &layout).unwrap();
+    assert_eq!(pixels(&image), &[0, 0, 0, 255, 255, 255]);
+    assert_eq!(raw::export(&image, &layout).unwrap(), &[16, 128, 235, 128]);
+
+    // Six pixels of V210 in four words of Cb Y Cr, Y Cb Y, Cr Y Cb, Y Cr Y.
+    let words = [
+        512 | 64 << 10 | 512 << 20,
+        940 | 512 << 10 | 64 << 20,
+        512 | 940 << 10 | 512 << 20,
+        64 | 512 << 10 | 940 << 20,
+    ];
+    let bytes: Vec<u8> = words
+        .iter()
+        .flat_map(|word: &u32| word.to_le_bytes())
+        .collect();
+    let layout = BufferLayout::aligned(PixelFormat::V210, 6, 1, 128);
+    assert_eq!(layout.strides, vec![128]);
+    let image = raw::import(&bytes, &layout).unwrap();
+    let gray: Vec<u8> = [0, 255, 0, 255, 0, 255]
+        .iter()
+        .flat_map(|&v| [v; 3])
+        .collect();
+    assert_eq!(pixels(&image), gray.as_slice());
+    assert_eq!(
+        &raw::export(&image, &layout).unwrap()[..16],
+        bytes.as_slice()
+    );
+
+    // A smooth image survives 4:2:2 closely.
+    let image = gradient(31, 6);
+    let layout = BufferLayout::packed(PixelFormat::Yuyv, 31, 6);
+    let decoded = raw::import(&raw::export(&image, &layout).unwrap(), &layout).unwrap();
+    let rgb: Vec<u8> = pixels(&image)
+        .chunks_exact(4)
+        .flat_map(|p| [p[0], p[1], p[2]])
+        .collect();
+    let expected = Image::from_data(rgb, Shape::new(31, 6, Some(3)), ColorSpace::RGB);
+    assert_close(&decoded, &expected, 10);
+}
+
+#[test]
+fn test_invalid() {
+    let layout = BufferLayout::packed(PixelFormat::Nv12, 4, 4);
+    assert!(raw::import(&[0; 23], &layout).is_err());
+    assert!(raw::import(&[0; 24], &layout).is_ok());
+    assert!(raw::import_planes(&[&[0; 16]], &layout).is_err());
+
+    let mut narrow = layout.clone();
+    narrow.strides = vec![3, 4];
+    assert!(raw::import(&[0; 64], &narrow).is_err());
+    narrow.strides = vec![4];
+    assert!(raw::import(&[0; 64], &narrow).is_err());
+
+    let empty = BufferLayout::packed(PixelFormat::Rgb24, 0, 4);
+    assert!(raw::import(&[], &empty).is_err());
+    assert!(raw::export(
+        &solid(4, 4, [0; 3]),
+        &BufferLayout::packed(PixelFormat::Rgb24, 4, 3)
+    )
+    .is_err());
+}
@@ -0,0 +1,330 @@
+use rusty_vision as rv;
+use rv::codec::decoders::tiff::{self, Compression, TiffDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::tiff::{
+    encode_pages, encode_with_options, BitDepth, EncodeOptions, Layout,
+};
+use rv::codec::encoders::Encoder;
+use rv::codec::{detect, Codex};
+use rv::color::ColorSpace;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::{Reader, Writer};
+
+///
+/// A single page TIFF with one strip per entry of `strips`, placed
+/// after the header. `StripOffsets` and `StripByteCounts` are added
+/// to `fields`, and values are written as SHORT when they fit.
+///
+fn tiff(big_endian: bool, fields: &[(u16, &[u32])], strips: &[&[u8]]) -> Vec<u8> {
+    let u16b = |v: u16| {
+        if big_endian {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+    let u32b = |v: u32| {
+        if big_endian {
+            v.to_be_bytes()
+        } else {
+            v.to_le_bytes()
+        }
+    };
+
+    let mut out = if big_endian {
+        b"MM".to_vec()
+    } else {
+        b"II".to_vec()
+    };
+    out.extend_from_slice(&u16b(42));
+    out.extend_from_slice(&[0; 4]);
+
+    let mut offsets = Vec::new();
+    for strip in strips {
+        offsets.push(out.len() as u32);
+        out.extend_from_slice(strip);
+    }
+    let counts: Vec<u32> = strips.iter().map(|strip| strip.len() as u32).collect();
+    let mut fields = fields.to_vec();
+    fields.extend([(273, offsets.as_slice()), (279, counts.as_slice())]);
+    fields.sort_by_key(|&(tag, _)| tag);
+
+    let ifd = out.len() as u32;
+    out[4..8].copy_from_slice(&u32b(ifd));
+    let mut overflow = ifd + 2 + fields.len() as u32 * 12 + 4;
+    let mut values = Vec::new();
+    out.extend_from_slice(&u16b(fields.len() as u16));
+    for (tag, field) in fields {
+        let short = field.iter().all(|&v| v <= u16::MAX as u32);
+        let bytes: Vec<u8> = if short {
+            field.iter().flat_map(|&v| u16b(v as u16)).collect()
+        } else {
+            field.iter().flat_map(|&v| u32b(v)).collect()
+        };
+        out.extend_from_slice(&u16b(tag));
+        out.extend_from_slice(&u16b(if short { 3 } else { 4 }));
+        out.extend_from_slice(&u32b(field.len() as u32));
+        if bytes.len() <= 4 {
+            out.extend_from_slice(&bytes);
+            out.resize(out.len() + 4 - bytes.len(), 0);
+        } else {
+            out.extend_from_slice(&u32b(overflow));
+            overflow += bytes.len() as u32;
+            values.extend(bytes);
+        }
+    }
+    out.extend_from_slice(&[0; 4]);
+    out.extend(values);
+    out
+}
+
+fn sample_image(width: usize, height: usize, colorspace: ColorSpace) -> Image {
+    let channels = colorspace.channels();
+    let data = (0..width * height * channels)
+        .map(|i| {
+            let (x, y) = (i / channels % width, i / channels / width);
+            if (x / 4 + y / 3) % 3 == 0 {
+                (i % channels * 60) as u8
+            } else {
+                (x * 7 + y * 3 + i % channels * 50) as u8 ^ (x * y) as u8
+            }
+        })
+        .collect();
+    Image::from_data(data, Shape::new(width, height, Some(channels)), colorspace)
+}
+
+fn pixels(image: &Image) -> &[u8] {
+    image.slice(0, image.size())
+}
+
+#[test]
+fn test_roundtrip_options() {
+    let layouts = [
+        Layout::Strips { rows_per_strip: 5 },
+        Layout::Tiles {
+            width: 16,
+            height: 32,
+        },
+    ];
+    let compressions = [
+        Compression::None,
+        Compression::PackBits,
+        Compression::Lzw,
+        Compression::Deflate,
+    ];
+    for colorspace in [ColorSpace::GRAY, ColorSpace::RGB, ColorSpace::RGBA] {
+        let image = sample_image(37, 41, colorspace);
+        for layout in layouts {
+            for compression in compressions {
+                for bit_depth in [BitDepth::Eight, BitDepth::Sixteen] {
+                    let options = EncodeOptions {
+                        compression,
+                        bit_depth,
+                        layout,
+                    };
+                    let bytes = encode_with_options(&image, &options).unwrap();
+                    let decoded = TiffDecoder.decode(&bytes).unwrap();
+                    assert_eq!(decoded.colorspace(), colorspace, "{options:?}");
+                    assert_eq!(pixels(&decoded), pixels(&image), "{options:?}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_roundtrip_file() {
+    let image = sample_image(300, 200, ColorSpace::BGR);
+    let path = std::env::temp_dir().join("rusty-vision-tiff.tiff");
+    let path = path.to_str().unwrap();
+    image.write(path.to_string(), Codex::TIFF).unwrap();
+
+    let decoded = Image::read_auto(path).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+    let expected: Vec<u8> = pixels(&image)
+        .chunks(3)
+        .flat_map(|p| [p[2], p[1], p[0]])
+        .collect();
+    assert_eq!(pixels(&decoded), expected.as_slice());
+}
+
+#[test]
+fn test_pages() {
+    let images = [
+        sample_image(10, 12, ColorSpace::RGB),
+        sample_image(33, 5, ColorSpace::GRAY),
+        sample_image(7, 70, ColorSpace::GRAYA),
+    ];
+    let bytes = encode_pages(&images, &EncodeOptions::default()).unwrap();
+    assert_eq!(detect(&bytes).unwrap(), Codex::TIFF);
+
+    let pages: Vec<Image> = tiff::pages(&bytes).unwrap().map(Result::unwrap).collect();
+    assert_eq!(pages.len(), images.len());
+    for (page, image) in pages.iter().zip(&images) {
+        assert_eq!(page.shape(), image.shape());
+        assert_eq!(pixels(page), pixels(image));
+    }
+
+    // A still decode reads the first page.
+    let first = Image::read_bytes(&bytes, Codex::TIFF).unwrap();
+    assert_eq!(pixels(&first), pixels(&images[0]));
+}
+
+#[test]
+fn test_decode_planar_big_endian() {
+    // 2x1 RGB, 16 bits, one strip per sample plane.
+    let planes: [&[u8]; 3] = [&[0xFF, 0xFF, 0, 0], &[0x80, 0, 0, 0], &[0, 0, 0x10, 0]];
+    let bytes = tiff(
+        true,
+        &[
+            (256, &[2]),
+            (257, &[1]),
+            (258, &[16, 16, 16]),
+            (262, &[2]),
+            (277, &[3]),
+            (284, &[2]),
+        ],
+        &planes,
+    );
+    assert_eq!(detect(&bytes).unwrap(), Codex::TIFF);
+
+    let image = Image::read_bytes_auto(&bytes).unwrap();
+    assert_eq!(image.shape(), Shape::new(2, 1, Some(3)));
+    assert_eq!(pixels(&image), &[255, 128, 0, 0, 0, 16]);
+}
+
+#[test]
+fn test_decode_palette() {
+    // 3x1, 4-bit indices 0, 1 and 15 packed into two bytes.
+    let mut map = vec![0u32; 48];
+    map[1] = 0xFFFF;
+    map[16 + 15] = 0x8080;
+    map[32 + 1] = 0x1010;
+    let bytes = tiff(
+        false,
+        &[
+            (256, &[3]),
+            (257, &[1]),
+            (258, &[4]),
+            (262, &[3]),
+            (320, &map),
+        ],
+        &[&[0x01, 0xF0]],
+    );
+
+    let image = TiffDecoder.decode(&bytes).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::RGB);
+    assert_eq!(pixels(&image), &[0, 0, 0, 255, 0, 16, 0, 128, 0]);
+}
+
+#[test]
+fn test_decode_bilevel_packbits() {
+    // 10x2 WhiteIsZero bits, each row packed separately: the first
+    // as a run of two 0xFF bytes, the second as two literals.
+    let bytes = tiff(
+        false,
+        &[(256, &[10]), (257, &[2]), (259, &[32773]), (262, &[0])],
+        &[&[0xFF, 0xFF, 0x01, 0x00, 0x40]],
+    );
+
+    let image = TiffDecoder.decode(&bytes).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::GRAY);
+    let mut expected = vec![0; 10];
+    expected.extend([255, 255, 255, 255, 255, 255, 255, 255, 255, 0]);
+    assert_eq!(pixels(&image), expected.as_slice());
+}
+
+#[test]
+fn test_decode_associated_alpha() {
+    // Grey 100 at half opacity, premultiplied to 50.
+    let bytes = tiff(
+        false,
+        &[
+            (256, &[1]),
+            (257, &[1]),
+            (258, &[8, 8]),
+            (262, &[1]),
+            (277, &[2]),
+            (338, &[1]),
+        ],
+        &[&[50, 128]],
+    );
+    let image = TiffDecoder.decode(&bytes).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::GRAYA);
+    assert_eq!(pixels(&image), &[99, 128]);
+}
+
+#[test]
+fn test_decode_oriented() {
+    // 2x1 grey with Orientation 6, a quarter turn clockwise.
+    let bytes = tiff(
+        false,
+        &[
+            (256, &[2]),
+            (257, &[1]),
+            (258, &[8]),
+            (262, &[1]),
+            (274, &[6]),
+        ],
+        &[&[10, 20]],
+    );
+    let image = Image::read_bytes_oriented(&bytes).unwrap();
+    assert_eq!(image.shape(), Shape::new(1, 2, Some(1)));
+    assert_eq!(pixels(&image), &[10, 20]);
+}
+
+#[test]
+fn test_invalid() {
+    let image = sample_image(4, 4, ColorSpace::RGB);
+    let tiles = EncodeOptions {
+        layout: Layout::Tiles {
+            width: 20,
+            height: 16,
+        },
+        ..EncodeOptions::default()
+    };
+    assert!(encode_with_options(&image, &tiles).is_err());
+    assert!(encode_pages(&[], &EncodeOptions::default()).is_err());
+
+    let bytes = image.encode(Codex::TIFF).unwrap();
+    assert!(TiffDecoder.decode(&bytes[..6]).is_err());
+    assert!(TiffDecoder.decode(b"II\x2B\0\x08\0\0\0").is_err());
+
+    // 32-bit samples are not supported.
+    let wide = tiff(false, &[(256, &[1]), (257, &[1]), (258, &[32])], &[&[0; 4]]);
+    assert!(TiffDecoder.decode(&wide).is_err());
+
+    // No samples per pixel, or far more pixels than the data holds.
+    let empty = tiff(false, &[(256, &[2]), (257, &[2]), (277, &[0])], &[&[0; 4]]);
+    assert!(TiffDecoder.decode(&empty).is_err());
+    let huge = tiff(
+        false,
+        &[(256, &[u32::MAX]), (257, &[u32::MAX]), (259, &[5])],
+        &[&[0; 4]],
+    );
+    assert!(TiffDecoder.decode(&huge).is_err());
+    let deep = tiff(
+        false,
+        &[(256, &[20000]), (257, &[20000]), (258, &[16]), (277, &[16])],
+        &[&[0; 4]],
+    );
+    assert!(TiffDecoder.decode(&deep).is_err());
+
+    // Tiles that are not a multiple of 16, or far larger than the page.
+    for size in [17, 100_000, u32::MAX] {
+        let tiled = tiff(
+            false,
+            &[
+                (256, &[1]),
+                (257, &[1]),
+                (322, &[size]),
+                (323, &[size]),
+                (324, &[8]),
+                (325, &[4]),
+            ],
+            &[&[0; 4]],
+        );
+        assert!(TiffDecoder.decode(&tiled).is_err(), "{size}");
+    }
+}
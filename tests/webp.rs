@@ -0,0 +1,200 @@
+use rusty_vision as rv;
+use rv::codec::decoders::webp::{self, WebpDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::Encoder;
+use rv::codec::exif::{read_exif, Orientation};
+use rv::codec::{detect, Codex};
+use rv::color::ColorSpace;
+use rv::error::Error;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::Reader;
+
+///
+/// The RGBA pattern `rgba.webp` and `exif.webp` in
+/// `tests/data/webp` were losslessly encoded from.
+///
+fn pattern(width: usize, height: usize) -> Vec<u8> {
+    let mut data = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let band = (x / 8 + y / 8) % 3;
+            data.extend_from_slice(&[
+                (x * 5) as u8,
+                (y * 7) as u8,
+                [40, 160, (x * y) as u8][band],
+                if band == 2 { 128 } else { 255 },
+            ]);
+        }
+    }
+    data
+}
+
+fn pixels(image: &Image) -> &[u8] {
+    image.slice(0, image.size())
+}
+
+///
+/// Writes values least significant bit first, as VP8L stores them.
+///
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    bits: usize,
+}
+
+impl BitWriter {
+    fn put(&mut self, value: u32, count: usize) {
+        for i in 0..count {
+            if self.bits & 7 == 0 {
+                self.bytes.push(0);
+            }
+            let bit = (value >> i & 1) as u8;
+            *self.bytes.last_mut().unwrap() |= bit << (self.bits % 8);
+            self.bits += 1;
+        }
+    }
+
+    ///
+    /// A simple prefix code of one or two 8-bit symbols.
+    ///
+    fn simple_code(&mut self, symbols: &[u32]) {
+        self.put(1, 1);
+        self.put(symbols.len() as u32 - 1, 1);
+        self.put(1, 1);
+        for &symbol in symbols {
+            self.put(symbol, 8);
+        }
+    }
+}
+
+fn riff(vp8l: &[u8]) -> Vec<u8> {
+    let mut data = b"RIFF".to_vec();
+    data.extend_from_slice(&(12 + vp8l.len() as u32).to_le_bytes());
+    data.extend_from_slice(b"WEBPVP8L");
+    data.extend_from_slice(&(vp8l.len() as u32).to_le_bytes());
+    data.extend_from_slice(vp8l);
+    data
+}
+
+#[test]
+fn test_decode_lossless() {
+    let bytes = include_bytes!("data/webp/rgba.webp");
+    assert_eq!(detect(bytes).unwrap(), Codex::WEBP);
+
+    let image = Image::read_bytes_auto(bytes).unwrap();
+    assert_eq!(image.shape(), Shape::new(48, 32, Some(4)));
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    assert_eq!(pixels(&image), pattern(48, 32).as_slice());
+}
+
+#[test]
+fn test_decode_extended() {
+    // VP8X container with an EXIF chunk holding Orientation 6.
+    let bytes = include_bytes!("data/webp/exif.webp");
+    let image = WebpDecoder.decode(bytes).unwrap();
+    assert_eq!(pixels(&image), pattern(48, 32).as_slice());
+
+    let exif = read_exif(bytes).unwrap().unwrap();
+    assert_eq!(exif.orientation(), Some(Orientation::Rotate90));
+    let upright = Image::read_bytes_oriented(bytes).unwrap();
+    assert_eq!(upright.shape(), Shape::new(32, 48, Some(4)));
+}
+
+#[test]
+fn test_decode_transforms() {
+    // Every transform, the colour cache, backward references and
+    // several prefix code groups, with pixels from a reference decoder.
+    let bytes = include_bytes!("data/webp/features.webp");
+    let expected = Image::read_bytes(include_bytes!("data/webp/features.pam"), Codex::PAM).unwrap();
+
+    let image = webp::decode(&mut &bytes[..]).unwrap();
+    assert_eq!(image.shape(), expected.shape());
+    assert_eq!(pixels(&image), pixels(&expected));
+}
+
+#[test]
+fn test_decode_palette() {
+    // 4x1 image of two colours, so eight indices pack into a pixel.
+    let mut writer = BitWriter::default();
+    writer.put(0x2F, 8);
+    writer.put(3, 14);
+    writer.put(0, 14);
+    writer.put(1, 1);
+    writer.put(0, 3);
+
+    // Colour indexing transform with a palette of 2 colours.
+    writer.put(1, 1);
+    writer.put(3, 2);
+    writer.put(1, 8);
+    // The palette, delta coded: opaque blue, then + 0x00FF0001 for red.
+    writer.put(0, 1);
+    writer.simple_code(&[0]);
+    writer.simple_code(&[0, 255]);
+    writer.simple_code(&[1, 255]);
+    writer.simple_code(&[0, 255]);
+    writer.simple_code(&[0]);
+    for bit in [0, 1, 1, 1, 0, 0] {
+        writer.put(bit, 1);
+    }
+    writer.put(0, 1);
+
+    // One packed pixel of indices 1, 0, 0, 1 in its green channel.
+    writer.put(0, 1);
+    writer.put(0, 1);
+    writer.simple_code(&[0b1001]);
+    for _ in 0..4 {
+        writer.simple_code(&[0]);
+    }
+
+    let image = WebpDecoder.decode(&riff(&writer.bytes)).unwrap();
+    assert_eq!(image.shape(), Shape::new(4, 1, Some(4)));
+    assert_eq!(
+        pixels(&image),
+        &[255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 255, 255, 255, 0, 0, 255]
+    );
+}
+
+#[test]
+fn test_unsupported() {
+    let mut lossy = b"RIFF\x16\0\0\0WEBPVP8 \x0A\0\0\0".to_vec();
+    lossy.extend_from_slice(&[0x10, 0x02, 0x00, 0x9D, 0x01, 0x2A, 0x01, 0x00, 0x01, 0x00]);
+    assert_eq!(detect(&lossy).unwrap(), Codex::WEBP);
+    assert!(matches!(
+        WebpDecoder.decode(&lossy),
+        Err(Error::UnsupportedFormat(_))
+    ));
+
+    let mut animated = b"RIFF\x16\0\0\0WEBPVP8X\x0A\0\0\0".to_vec();
+    animated.extend_from_slice(&[0x02, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    assert!(matches!(
+        WebpDecoder.decode(&animated),
+        Err(Error::NotImplemented(_))
+    ));
+
+    let image = Image::read_bytes_auto(include_bytes!("data/webp/rgba.webp")).unwrap();
+    assert!(matches!(
+        image.encode(Codex::WEBP),
+        Err(Error::NotImplemented(_))
+    ));
+}
+
+#[test]
+fn test_decode_invalid() {
+    let bytes = include_bytes!("data/webp/rgba.webp");
+    assert!(WebpDecoder.decode(&bytes[..bytes.len() / 2]).is_err());
+    assert!(WebpDecoder.decode(&bytes[..16]).is_err());
+    assert!(WebpDecoder.decode(b"RIFF\0\0\0\0WEBP").is_err());
+    assert!(WebpDecoder.decode(b"RIFF\x04\0\0\0WAVE").is_err());
+
+    // A repeated transform is invalid.
+    let mut writer = BitWriter::default();
+    writer.put(0x2F, 8);
+    writer.put(0, 28);
+    writer.put(0, 4);
+    for _ in 0..2 {
+        writer.put(1, 1);
+        writer.put(2, 2);
+    }
+    assert!(WebpDecoder.decode(&riff(&writer.bytes)).is_err());
+}
@@ -0,0 +1,184 @@
+use rusty_vision as rv;
+use rv::codec::decoders::y4m::{self, Chroma, Y4mDecoder};
+use rv::codec::decoders::Decoder;
+use rv::codec::encoders::y4m::{self as y4m_encoder, EncodeOptions, FrameWriter};
+use rv::codec::encoders::Encoder;
+use rv::codec::{detect, Codex};
+use rv::color::{ColorRange, ColorSpace};
+use rv::error::Error;
+use rv::geometry::Shape;
+use rv::image::Image;
+use rv::io::Reader;
+
+fn gradient(width: usize, height: usize, frame: usize) -> Image {
+    let mut data = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            data.extend_from_slice(&[
+                (x * 255 / width) as u8,
+                (y * 255 / height) as u8,
+                (frame * 60) as u8,
+            ]);
+        }
+    }
+    Image::from_data(data, Shape::new(width, height, Some(3)), ColorSpace::RGB)
+}
+
+fn solid(width: usize, height: usize, color: [u8; 3]) -> Image {
+    let data = color.repeat(width * height);
+    Image::from_data(data, Shape::new(width, height, Some(3)), ColorSpace::RGB)
+}
+
+fn pixels(image: &Image) -> &[u8] {
+    image.slice(0, image.size())
+}
+
+fn assert_close(decoded: &Image, expected: &Image, tolerance: u8) {
+    assert_eq!(decoded.shape(), expected.shape());
+    for (value, target) in pixels(decoded).iter().zip(pixels(expected)) {
+        assert!(value.abs_diff(*target) <= tolerance, "{value} {target}");
+    }
+}
+
+#[test]
+fn test_roundtrip_444() {
+    let image = gradient(24, 16, 2);
+    let options = EncodeOptions {
+        chroma: Chroma::Yuv444,
+        color_range: ColorRange::Full,
+        ..EncodeOptions::default()
+    };
+    let bytes = y4m_encoder::encode_with_options(&image, &options).unwrap();
+    assert_eq!(detect(&bytes).unwrap(), Codex::Y4M);
+    assert!(bytes.starts_with(b"YUV4MPEG2 W24 H16 F25:1 "));
+
+    let decoded = Image::read_bytes_auto(&bytes).unwrap();
+    assert_eq!(decoded.colorspace(), ColorSpace::RGB);
+    assert_close(&decoded, &image, 1);
+}
+
+#[test]
+fn test_stream() {
+    let options = EncodeOptions {
+        frame_rate: (30000, 1001),
+        ..EncodeOptions::default()
+    };
+    let colors = [[200, 30, 40], [0, 0, 0], [255, 255, 255], [20, 120, 250]];
+    let mut writer = FrameWriter::new(Vec::new(), 7, 5, &options).unwrap();
+    for color in colors {
+        writer.write_frame(&solid(7, 5, color)).unwrap();
+    }
+    assert!(writer.write_frame(&solid(5, 7, [0; 3])).is_err());
+    let bytes = writer.into_inner();
+
+    let mut frames = y4m::frames(&bytes[..]).unwrap();
+    assert_eq!((frames.width(), frames.height()), (7, 5));
+    assert_eq!(frames.frame_rate(), (30000, 1001));
+    assert_eq!(frames.chroma(), Chroma::Yuv420);
+    assert_eq!(frames.color_range(), ColorRange::Limited);
+
+    let decoded: Vec<Image> = frames.by_ref().map(Result::unwrap).collect();
+    assert_eq!(decoded.len(), colors.len());
+    for (frame, color) in decoded.iter().zip(colors) {
+        assert_close(frame, &solid(7, 5, color), 2);
+    }
+    assert!(frames.next().is_none());
+
+    // Smooth content survives 4:2:2 closely.
+    let image = gradient(33, 9, 1);
+    let options = EncodeOptions {
+        chroma: Chroma::Yuv422,
+        ..EncodeOptions::default()
+    };
+    let bytes = y4m_encoder::encode_with_options(&image, &options).unwrap();
+    assert_close(&Y4mDecoder.decode(&bytes).unwrap(), &image, 8);
+}
+
+#[test]
+fn test_decode_planes() {
+    // 4:2:0 limited range: black and white luma with neutral chroma,
+    // then a frame with parameters and pure red chroma.
+    let mut bytes = b"YUV4MPEG2 W2 H2 F25:1 Ip A0:0 C420jpeg XYSCSS=420JPEG\n".to_vec();
+    bytes.extend_from_slice(b"FRAME\n");
+    bytes.extend_from_slice(&[16, 235, 235, 16, 128, 128]);
+    bytes.extend_from_slice(b"FRAME Ixyz\n");
+    bytes.extend_from_slice(&[81, 81, 81, 81, 90, 240]);
+    let frames: Vec<Image> = y4m::frames(&bytes[..])
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    assert_eq!(
+        pixels(&frames[0]),
+        &[0, 0, 0, 255, 255, 255, 255, 255, 255, 0, 0, 0]
+    );
+    assert_close(&frames[1], &solid(2, 2, [255, 0, 0]), 1);
+
+    let mut alpha = b"YUV4MPEG2 W2 H1 C444alpha XCOLORRANGE=FULL\nFRAME\n".to_vec();
+    alpha.extend_from_slice(&[10, 200, 128, 128, 128, 128, 0, 255]);
+    let image = y4m::decode(&mut &alpha[..]).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::RGBA);
+    assert_eq!(pixels(&image), &[10, 10, 10, 0, 200, 200, 200, 255]);
+
+    let mono = b"YUV4MPEG2 W3 H1 Cmono\nFRAME\n\x10\x7e\xeb";
+    let image = Y4mDecoder.decode(mono).unwrap();
+    assert_eq!(image.colorspace(), ColorSpace::GRAY);
+    assert_eq!(pixels(&image), &[0, 128, 255]);
+}
+
+#[test]
+fn test_encode_other_colorspaces() {
+    let rgba = Image::from_data(
+        vec![255, 0, 0, 30, 0, 0, 255, 255],
+        Shape::new(2, 1, Some(4)),
+        ColorSpace::RGBA,
+    );
+    let options = EncodeOptions {
+        chroma: Chroma::Yuva444,
+        color_range: ColorRange::Full,
+        ..EncodeOptions::default()
+    };
+    let bytes = y4m_encoder::encode_with_options(&rgba, &options).unwrap();
+    assert_close(&Y4mDecoder.decode(&bytes).unwrap(), &rgba, 1);
+
+    let gray = Image::from_data(
+        vec![0, 77, 255],
+        Shape::new(3, 1, Some(1)),
+        ColorSpace::GRAY,
+    );
+    let options = EncodeOptions {
+        chroma: Chroma::Mono,
+        ..EncodeOptions::default()
+    };
+    let bytes = y4m_encoder::encode_with_options(&gray, &options).unwrap();
+    assert!(bytes.ends_with(b"FRAME\n\x10\x52\xeb"));
+    assert_close(&Y4mDecoder.decode(&bytes).unwrap(), &gray, 1);
+
+    let bgr = Image::from_data(vec![255, 0, 0], Shape::new(1, 1, Some(3)), ColorSpace::BGR);
+    let decoded = Y4mDecoder.decode(&bgr.encode(Codex::Y4M).unwrap()).unwrap();
+    assert_close(&decoded, &solid(1, 1, [0, 0, 255]), 2);
+}
+
+#[test]
+fn test_decode_invalid() {
+    let bytes = gradient(8, 8, 0).encode(Codex::Y4M).unwrap();
+    assert!(Y4mDecoder.decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Y4mDecoder.decode(b"YUV4MPEG2 W8 H8\n").is_err());
+    assert!(Y4mDecoder.decode(b"YUV4MPEG2 W8\nFRAME\n").is_err());
+    assert!(Y4mDecoder.decode(b"YUV4MPEG W1 H1\nFRAME\n\0\0\0").is_err());
+    assert!(Y4mDecoder
+        .decode(b"YUV4MPEG2 W1 H1 C444\nFRAMX\n\0\0\0")
+        .is_err());
+
+    // A truncated frame ends the stream with an error.
+    let mut two = bytes.clone();
+    two.extend_from_slice(b"FRAME\n");
+    two.extend_from_slice(&[128; 20]);
+    let frames: Vec<_> = y4m::frames(&two[..]).unwrap().collect();
+    assert_eq!(frames.len(), 2);
+    assert!(frames[1].is_err());
+
+    assert!(matches!(
+        Y4mDecoder.decode(b"YUV4MPEG2 W1 H1 C420p10\nFRAME\n"),
+        Err(Error::NotImplemented(_))
+    ));
+}